@@ -2,3 +2,13 @@
 set -e
 wasm-pack build --target web --out-dir ../src/generation/wasm-pkg --release
 echo "WASM build complete → src/generation/wasm-pkg/"
+
+# Optional threaded build (shared memory + rayon on Web Workers). Needs a
+# nightly toolchain with rust-src; the app falls back to the plain build
+# when this package is missing or the page isn't cross-origin isolated.
+if [ "$1" = "--threads" ]; then
+  RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+mutable-globals,+simd128 -C link-arg=--max-memory=4294967296' \
+    rustup run nightly wasm-pack build --target web --out-dir ../src/generation/wasm-pkg-threads --release \
+    -- --features wasm-threads -Z build-std=panic_abort,std
+  echo "Threaded WASM build complete → src/generation/wasm-pkg-threads/"
+fi
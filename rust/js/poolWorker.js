@@ -0,0 +1,31 @@
+// Web Worker side of the `wasm-threads` rayon pool (see src/threads.rs).
+// This file is both imported by the generated bindings and loaded as the
+// worker script for every pool thread.
+
+const pending = [];
+
+export function startPoolWorker(module, memory, builder) {
+  const worker = new Worker(new URL('./poolWorker.js', import.meta.url), { type: 'module' });
+  pending.push(new Promise((resolve, reject) => {
+    worker.addEventListener('message', (event) => {
+      if (event.data && event.data.type === 'terra-pool-ready') resolve();
+    }, { once: true });
+    worker.addEventListener('error', reject, { once: true });
+  }));
+  worker.postMessage({ type: 'terra-pool-start', module, memory, builder });
+}
+
+export function poolWorkersReady() {
+  return Promise.all(pending.splice(0)).then(() => undefined);
+}
+
+self.addEventListener('message', async (event) => {
+  const msg = event.data;
+  if (!msg || msg.type !== 'terra-pool-start') return;
+
+  // snippets/<crate>/js/poolWorker.js -> package root
+  const pkg = await import('../../../terra_wasm.js');
+  await pkg.default({ module_or_path: msg.module, memory: msg.memory });
+  self.postMessage({ type: 'terra-pool-ready' });
+  pkg.run_pool_worker(msg.builder);
+});
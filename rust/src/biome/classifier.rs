@@ -1,3 +1,4 @@
+use thiserror::Error;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -18,6 +19,33 @@ pub enum Biome {
     Volcanic = 11,
 }
 
+/// A stored biome id that names no biome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("unknown biome id {0}")]
+pub struct UnknownBiome(pub u8);
+
+impl TryFrom<u8> for Biome {
+    type Error = UnknownBiome;
+
+    fn try_from(v: u8) -> Result<Self, UnknownBiome> {
+        Ok(match v {
+            0 => Biome::DeepOcean,
+            1 => Biome::ShallowWater,
+            2 => Biome::Beach,
+            3 => Biome::Desert,
+            4 => Biome::Grassland,
+            5 => Biome::Forest,
+            6 => Biome::DenseForest,
+            7 => Biome::Jungle,
+            8 => Biome::Tundra,
+            9 => Biome::Snow,
+            10 => Biome::Mountain,
+            11 => Biome::Volcanic,
+            _ => return Err(UnknownBiome(v)),
+        })
+    }
+}
+
 pub fn classify_biome(elevation: f32, moisture: f32, sea_level: f32) -> Biome {
     if elevation < sea_level - 0.1 {
         return Biome::DeepOcean;
@@ -92,6 +120,15 @@ mod tests {
         assert_eq!(classify_biome(0.5, 0.5, 0.35), Biome::Forest);
     }
 
+    #[test]
+    fn ids_round_trip_and_unknown_ids_fail() {
+        for id in 0..=11u8 {
+            assert_eq!(Biome::try_from(id).map(|b| b as u8), Ok(id));
+        }
+        assert_eq!(Biome::try_from(12), Err(UnknownBiome(12)));
+        assert_eq!(Biome::try_from(255), Err(UnknownBiome(255)));
+    }
+
     #[test]
     fn all_extremes_classified() {
         // Verify no panics or missing arms across full range
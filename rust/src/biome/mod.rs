@@ -1,5 +1,5 @@
 pub mod classifier;
 pub mod moisture;
 
-pub use classifier::{classify_biome, Biome};
+pub use classifier::{classify_biome, Biome, UnknownBiome};
 pub use moisture::MoistureGenerator;
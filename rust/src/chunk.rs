@@ -1,25 +1,54 @@
+use std::sync::OnceLock;
+
 use wasm_bindgen::prelude::*;
 
 use crate::biome::classifier::classify_biome;
 use crate::biome::moisture::MoistureGenerator;
-use crate::erosion;
-use crate::noise::{LayeredNoise, RidgedNoise, WarpedNoise};
+use crate::config::ConfigError;
+use crate::erosion::{self, ErosionError, Hardness};
+use crate::features::{Crater, CraterField, CraterParams, StampField};
+use crate::noise::{
+    BillowNoise, ContinentField, ContinentMode, LayeredNoise, Multifractal, MultifractalNoise,
+    NoiseField, RidgeShape, RidgedNoise, WarpParams, WarpedNoise, WorleyNoise,
+};
+use crate::pipeline::{Stage, StageHashes};
+use crate::raycast::HeightQuadtree;
+use crate::sample::TerrainSampler;
+use crate::spline::{ErosionField, HeightSpline, SplineInputs};
+use crate::strata::Terrace;
 use crate::NoiseType;
 use crate::TerrainConfig;
 
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct ChunkData {
-    heightmap: Vec<f32>,
-    moisture_map: Vec<f32>,
-    biome_map: Vec<u8>,
-    width: u32,
-    height: u32,
-    min_height: f32,
-    max_height: f32,
+    pub(crate) heightmap: Vec<f32>,
+    pub(crate) moisture_map: Vec<f32>,
+    pub(crate) biome_map: Vec<u8>,
+    /// Rock type per cell from the strata; zeros without strata.
+    pub(crate) material_map: Vec<u8>,
+    pub(crate) chunk_x: i32,
+    pub(crate) chunk_z: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) min_height: f32,
+    pub(crate) max_height: f32,
+    pub(crate) quadtree: OnceLock<HeightQuadtree>,
+    pub(crate) stages: StageHashes,
 }
 
 #[wasm_bindgen]
 impl ChunkData {
+    #[wasm_bindgen(getter)]
+    pub fn chunk_x(&self) -> i32 {
+        self.chunk_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn chunk_z(&self) -> i32 {
+        self.chunk_z
+    }
+
     #[wasm_bindgen(getter)]
     pub fn width(&self) -> u32 {
         self.width
@@ -51,134 +80,369 @@ impl ChunkData {
     pub fn get_biome_map(&self) -> Vec<u8> {
         self.biome_map.clone()
     }
+
+    pub fn get_material_map(&self) -> Vec<u8> {
+        self.material_map.clone()
+    }
+
+    // The `*_view` methods can't be `unsafe fn`: wasm-bindgen only exports
+    // safe functions. Each documents the contract its JS caller must keep.
+
+    /// View of the heightmap in WASM linear memory, without copying.
+    ///
+    /// Invalid after `free()`, `into_buffers()`, or any later WASM call that
+    /// may grow memory, which detaches every view. Read it or `.slice()` it
+    /// before calling into WASM again, and never transfer the view itself.
+    pub fn heightmap_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the JS caller keeps the contract documented above
+        unsafe { js_sys::Float32Array::view(&self.heightmap) }
+    }
+
+    /// View of the moisture map in WASM linear memory, without copying.
+    ///
+    /// Invalid after `free()`, `into_buffers()`, or any later WASM call that
+    /// may grow memory. `.slice()` it before calling into WASM again.
+    pub fn moisture_map_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the JS caller keeps the contract documented above
+        unsafe { js_sys::Float32Array::view(&self.moisture_map) }
+    }
+
+    /// View of the biome map in WASM linear memory, without copying.
+    ///
+    /// Invalid after `free()`, `into_buffers()`, or any later WASM call that
+    /// may grow memory. `.slice()` it before calling into WASM again.
+    pub fn biome_map_view(&self) -> js_sys::Uint8Array {
+        // SAFETY: the JS caller keeps the contract documented above
+        unsafe { js_sys::Uint8Array::view(&self.biome_map) }
+    }
+
+    /// View of the material map in WASM linear memory, without copying.
+    ///
+    /// Invalid after `free()`, `into_buffers()`, or any later WASM call that
+    /// may grow memory. `.slice()` it before calling into WASM again.
+    pub fn material_map_view(&self) -> js_sys::Uint8Array {
+        // SAFETY: the JS caller keeps the contract documented above
+        unsafe { js_sys::Uint8Array::view(&self.material_map) }
+    }
+
+    /// Move the layers out without cloning them. From JS this consumes the
+    /// chunk: its handle is invalidated and need not be freed.
+    pub fn into_buffers(self) -> ChunkBuffers {
+        ChunkBuffers {
+            heightmap: self.heightmap,
+            moisture_map: self.moisture_map,
+            biome_map: self.biome_map,
+            material_map: self.material_map,
+        }
+    }
+}
+
+/// Owned layers of a chunk, moved out of it without cloning.
+///
+/// This is not zero-copy: WASM memory can't be transferred, so each `take_*`
+/// copies its layer once into a typed array with its own `ArrayBuffer` and
+/// frees the WASM side. The result can be transferred to another thread and
+/// stays valid across later WASM calls. A layer can be taken once; taking it
+/// again returns an empty array.
+#[wasm_bindgen]
+pub struct ChunkBuffers {
+    #[wasm_bindgen(skip)]
+    pub heightmap: Vec<f32>,
+    #[wasm_bindgen(skip)]
+    pub moisture_map: Vec<f32>,
+    #[wasm_bindgen(skip)]
+    pub biome_map: Vec<u8>,
+    #[wasm_bindgen(skip)]
+    pub material_map: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ChunkBuffers {
+    /// Copies the heightmap out of WASM memory once.
+    pub fn take_heightmap(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.heightmap)
+    }
+
+    /// Copies the moisture map out of WASM memory once.
+    pub fn take_moisture_map(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.moisture_map)
+    }
+
+    /// Copies the biome map out of WASM memory once.
+    pub fn take_biome_map(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.biome_map)
+    }
+
+    /// Copies the material map out of WASM memory once.
+    pub fn take_material_map(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.material_map)
+    }
+}
+
+impl ChunkData {
+    pub fn heightmap(&self) -> &[f32] {
+        &self.heightmap
+    }
+
+    pub fn moisture_map(&self) -> &[f32] {
+        &self.moisture_map
+    }
+
+    pub fn biome_map(&self) -> &[u8] {
+        &self.biome_map
+    }
+
+    pub fn material_map(&self) -> &[u8] {
+        &self.material_map
+    }
+
+    /// Bytes held by the layer buffers and, once built, the raycast quadtree.
+    pub fn byte_size(&self) -> usize {
+        self.heightmap.len() * 4
+            + self.moisture_map.len() * 4
+            + self.biome_map.len()
+            + self.material_map.len()
+            + self.quadtree.get().map_or(0, HeightQuadtree::byte_size)
+    }
+
+    /// Min/max height pyramid for raycasting, built on first use.
+    pub fn quadtree(&self) -> &HeightQuadtree {
+        self.quadtree.get_or_init(|| HeightQuadtree::build(self))
+    }
 }
 
 #[wasm_bindgen]
-pub fn generate_chunk(config: &TerrainConfig, chunk_x: i32, chunk_z: i32) -> ChunkData {
+pub fn generate_chunk(
+    config: &TerrainConfig,
+    chunk_x: i32,
+    chunk_z: i32,
+) -> Result<ChunkData, ConfigError> {
+    let sampler = TerrainSampler::new(config)?;
+    Ok(sampler.generate_chunk(config, chunk_x, chunk_z))
+}
+
+/// Generate a chunk with prebuilt samplers; `config` must already be valid.
+pub(crate) fn build_chunk(
+    config: &TerrainConfig,
+    noise: &dyn NoiseSampler,
+    moisture_gen: Option<&MoistureGenerator>,
+    chunk_x: i32,
+    chunk_z: i32,
+) -> ChunkData {
+    let mut chunk = ChunkData {
+        heightmap: Vec::new(),
+        moisture_map: Vec::new(),
+        biome_map: Vec::new(),
+        material_map: Vec::new(),
+        chunk_x,
+        chunk_z,
+        width: config.chunk_size,
+        height: config.chunk_size,
+        min_height: 0.0,
+        max_height: 0.0,
+        quadtree: OnceLock::new(),
+        stages: StageHashes::new(config),
+    };
+    run_stages(&mut chunk, config, noise, moisture_gen, &Stage::ALL);
+    chunk
+}
+
+/// Recompute `stages` of `chunk` in place, in pipeline order, and record
+/// `config` as their source. Stages not listed keep their current layers.
+pub(crate) fn run_stages(
+    chunk: &mut ChunkData,
+    config: &TerrainConfig,
+    noise: &dyn NoiseSampler,
+    moisture_gen: Option<&MoistureGenerator>,
+    stages: &[Stage],
+) {
     let size = config.chunk_size as usize;
     let total = size * size;
-    let mut heightmap = vec![0.0f32; total];
-
-    let noise_type: NoiseType = config.noise_type().into();
+    let (chunk_x, chunk_z) = (chunk.chunk_x, chunk.chunk_z);
+    chunk.width = config.chunk_size;
+    chunk.height = config.chunk_size;
 
-    // Generate heightmap based on noise type
-    match noise_type {
-        NoiseType::Simplex | NoiseType::Perlin => {
-            let noise = LayeredNoise::new(
-                config.seed,
-                noise_type,
-                config.octaves,
-                config.persistence,
-                config.lacunarity,
-                config.scale,
-            );
-            fill_heightmap(&noise, &mut heightmap, size, chunk_x, chunk_z, config.chunk_size);
-        }
-        NoiseType::Ridged => {
-            let noise = RidgedNoise::new(
-                config.seed,
-                config.octaves,
-                config.persistence,
-                config.lacunarity,
-                config.scale,
-            );
-            fill_heightmap(&noise, &mut heightmap, size, chunk_x, chunk_z, config.chunk_size);
-        }
-        NoiseType::Warped => {
-            let noise = WarpedNoise::new(
-                config.seed,
-                config.warp_strength,
-                config.octaves,
-                config.persistence,
-                config.lacunarity,
-                config.scale,
-            );
-            fill_heightmap(&noise, &mut heightmap, size, chunk_x, chunk_z, config.chunk_size);
-        }
+    if stages.contains(&Stage::Height) {
+        chunk.heightmap = vec![0.0f32; total];
+        fill_heightmap(noise, &mut chunk.heightmap, size, chunk_x, chunk_z, config.chunk_size);
     }
 
     // Apply erosion if enabled
-    if config.erosion_enabled && config.erosion_iterations > 0 {
-        erosion::erode(
-            &mut heightmap,
+    if stages.contains(&Stage::Erosion) && config.erosion_enabled && config.erosion_iterations > 0 {
+        erosion::erode_with_hardness(
+            &mut chunk.heightmap,
             config.chunk_size,
             config.chunk_size,
             config,
+            chunk_hardness(config, chunk_x, chunk_z, config.chunk_size),
         );
     }
 
-    // Calculate min/max
-    let mut min_h = f32::MAX;
-    let mut max_h = f32::MIN;
-    for &v in &heightmap {
-        if v < min_h { min_h = v; }
-        if v > max_h { max_h = v; }
-    }
-
-    // Generate moisture and biome maps
-    let mut moisture_map = vec![0.0f32; total];
-    let mut biome_map = vec![0u8; total];
+    if stages.contains(&Stage::Moisture) {
+        chunk.moisture_map = vec![0.0f32; total];
+        if let Some(moisture_gen) = moisture_gen {
+            let edge = config.chunk_size - 1;
 
-    if config.moisture_enabled {
-        let moisture_gen = MoistureGenerator::new(config.seed, config.scale);
-        let edge = config.chunk_size - 1;
-
-        for z in 0..size {
-            for x in 0..size {
-                let world_x = chunk_x as f64 * edge as f64 + x as f64;
+            for_each_row(&mut chunk.moisture_map, size, |z, row| {
                 let world_z = chunk_z as f64 * edge as f64 + z as f64;
-                let idx = z * size + x;
-
-                moisture_map[idx] = moisture_gen.sample(world_x, world_z);
-                biome_map[idx] = classify_biome(
-                    heightmap[idx],
-                    moisture_map[idx],
-                    config.sea_level as f32,
-                ) as u8;
-            }
+                for (x, m) in row.iter_mut().enumerate() {
+                    let world_x = chunk_x as f64 * edge as f64 + x as f64;
+                    *m = moisture_gen.sample(world_x, world_z);
+                }
+            });
+        }
+    }
+
+    if stages.contains(&Stage::Classification) {
+        let sea_level = config.sea_level as f32;
+        let heightmap = &chunk.heightmap;
+        let moisture_map = &chunk.moisture_map;
+        chunk.biome_map = vec![0u8; total];
+        if config.moisture_enabled {
+            for_each_row(&mut chunk.biome_map, size, |z, row| {
+                for (x, b) in row.iter_mut().enumerate() {
+                    let idx = z * size + x;
+                    *b = classify_biome(heightmap[idx], moisture_map[idx], sea_level) as u8;
+                }
+            });
+        } else {
+            // Without moisture, classify by elevation only (moisture = 0.5)
+            for_each_row(&mut chunk.biome_map, size, |z, row| {
+                for (x, b) in row.iter_mut().enumerate() {
+                    *b = classify_biome(heightmap[z * size + x], 0.5, sea_level) as u8;
+                }
+            });
         }
-    } else {
-        // Without moisture, classify by elevation only (moisture = 0.5)
-        for i in 0..total {
-            biome_map[i] = classify_biome(
-                heightmap[i],
-                0.5,
-                config.sea_level as f32,
-            ) as u8;
+
+        chunk.material_map = vec![0u8; total];
+        if let Some(strata) = &config.strata {
+            for_each_row(&mut chunk.material_map, size, |z, row| {
+                for (x, m) in row.iter_mut().enumerate() {
+                    *m = strata.rock_at(heightmap[z * size + x] as f64);
+                }
+            });
         }
     }
 
-    ChunkData {
-        heightmap,
-        moisture_map,
-        biome_map,
-        width: config.chunk_size,
-        height: config.chunk_size,
-        min_height: min_h,
-        max_height: max_h,
+    if stages.contains(&Stage::Derived) {
+        (chunk.min_height, chunk.max_height) = height_range(&chunk.heightmap);
+        chunk.quadtree = OnceLock::new();
     }
+
+    chunk.stages = StageHashes::new(config);
 }
 
-/// Erode an existing heightmap (called from JS for the "Erode" button)
+/// Min and max of a heightmap.
+pub(crate) fn height_range(heightmap: &[f32]) -> (f32, f32) {
+    let mut min_h = f32::MAX;
+    let mut max_h = f32::MIN;
+    for &v in heightmap {
+        if v < min_h { min_h = v; }
+        if v > max_h { max_h = v; }
+    }
+    (min_h, max_h)
+}
+
+/// Erode an existing heightmap (called from JS for the "Erode" button).
+/// `origin_x`/`origin_z` is the world position of its first cell, so the
+/// hardness field lines up with in-chunk erosion; for a chunk that is
+/// `chunk_x * (chunk_size - 1)`.
 #[wasm_bindgen]
 pub fn erode_heightmap(
     heightmap: &mut [f32],
     width: u32,
     height: u32,
+    origin_x: f64,
+    origin_z: f64,
     config: &TerrainConfig,
-) {
-    erosion::erode(heightmap, width, height, config);
+) -> Result<(), ErosionError> {
+    config.validate()?;
+    if heightmap.len() != width as usize * height as usize {
+        return Err(ErosionError::SizeMismatch {
+            width,
+            height,
+            len: heightmap.len(),
+        });
+    }
+    let hardness = Hardness::new(config, origin_x, origin_z, width, height);
+    erosion::erode_with_hardness(heightmap, width, height, config, hardness);
+    Ok(())
+}
+
+fn chunk_hardness(config: &TerrainConfig, chunk_x: i32, chunk_z: i32, size: u32) -> Hardness {
+    let edge = (size - 1) as f64;
+    Hardness::new(config, chunk_x as f64 * edge, chunk_z as f64 * edge, size, size)
+}
+
+/// Erosion hardness of each cell of `chunk` at its current heights, for
+/// debugging; same layout as the heightmap.
+#[wasm_bindgen]
+pub fn chunk_hardness_map(config: &TerrainConfig, chunk: &ChunkData) -> Result<Vec<f32>, ConfigError> {
+    config.validate()?;
+    let hardness = chunk_hardness(config, chunk.chunk_x, chunk.chunk_z, chunk.width);
+    Ok(hardness.map(&chunk.heightmap))
+}
+
+/// Base-octave cellular cell ids over a chunk's grid (same layout as the
+/// heightmap), for masking regions. Uses the config's cellular settings
+/// whatever `noise_type` is.
+#[wasm_bindgen]
+pub fn generate_cell_mask(
+    config: &TerrainConfig,
+    chunk_x: i32,
+    chunk_z: i32,
+) -> Result<Vec<u32>, ConfigError> {
+    config.validate()?;
+    let noise = cellular_noise(config);
+    let size = config.chunk_size as usize;
+    let edge = (config.chunk_size - 1) as f64;
+    let mut mask = vec![0u32; size * size];
+    for_each_row(&mut mask, size, |z, row| {
+        let world_z = chunk_z as f64 * edge + z as f64;
+        for (x, id) in row.iter_mut().enumerate() {
+            *id = noise.cell_id(chunk_x as f64 * edge + x as f64, world_z);
+        }
+    });
+    Ok(mask)
 }
 
 // Generic fill function that works with any noise sampler
-trait NoiseSampler {
+pub(crate) trait NoiseSampler: NoiseField {
     fn sample(&self, x: f64, z: f64) -> f64;
+
+    fn sample_row(&self, world_x0: f64, world_z: f64, out: &mut [f64]) {
+        for (x, v) in out.iter_mut().enumerate() {
+            *v = self.sample(world_x0 + x as f64, world_z);
+        }
+    }
+
+    /// `sample` for points in the world box `x0..=x1` x `z0..=z1`, with any
+    /// per-area lookups done once up front.
+    fn sampler_in(&self, _x0: f64, _z0: f64, _x1: f64, _z1: f64) -> Box<dyn Fn(f64, f64) -> f64 + '_> {
+        Box::new(move |x, z| self.sample(x, z))
+    }
+
+    /// Fill a grid `width` cells wide whose first cell is at
+    /// (`world_x0`, `world_z0`), a row at a time.
+    fn fill_grid(&self, world_x0: f64, world_z0: f64, width: usize, out: &mut [f32]) {
+        for_each_row(out, width, |z, row| {
+            let mut values = vec![0.0; row.len()];
+            self.sample_row(world_x0, world_z0 + z as f64, &mut values);
+            for (h, v) in row.iter_mut().zip(values) {
+                *h = v as f32;
+            }
+        });
+    }
 }
 
 impl NoiseSampler for LayeredNoise {
     fn sample(&self, x: f64, z: f64) -> f64 {
         self.sample(x, z)
     }
+
+    fn sample_row(&self, world_x0: f64, world_z: f64, out: &mut [f64]) {
+        self.sample_row(world_x0, world_z, out)
+    }
 }
 
 impl NoiseSampler for RidgedNoise {
@@ -193,6 +457,275 @@ impl NoiseSampler for WarpedNoise {
     }
 }
 
+impl NoiseSampler for WorleyNoise {
+    fn sample(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, z)
+    }
+}
+
+impl NoiseSampler for BillowNoise {
+    fn sample(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, z)
+    }
+}
+
+impl NoiseSampler for MultifractalNoise {
+    fn sample(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, z)
+    }
+}
+
+fn cellular_noise(config: &TerrainConfig) -> WorleyNoise {
+    WorleyNoise::new(
+        config.seed,
+        config.cellular_return().into(),
+        config.cellular_distance().into(),
+        config.cellular_jitter,
+        config.octaves,
+        config.persistence,
+        config.lacunarity,
+        config.scale,
+    )
+}
+
+/// Height noise shaped by the continent field, remapped by the height
+/// spline, terraced, cratered, then stamped.
+struct ShapedNoise {
+    detail: Box<dyn NoiseSampler>,
+    continent: ContinentField,
+    spline: Option<(HeightSpline, ErosionField)>,
+    terrace: Option<Terrace>,
+    craters: Option<CraterField>,
+    stamps: Option<StampField>,
+    scale: f64,
+}
+
+/// Craters and stamps reaching a world box.
+struct Features {
+    craters: Vec<Crater>,
+    stamps: Vec<usize>,
+}
+
+impl ShapedNoise {
+    /// Features reaching the world box `x0..=x1` x `z0..=z1`.
+    fn features_in(&self, x0: f64, z0: f64, x1: f64, z1: f64) -> Features {
+        Features {
+            craters: self.craters.as_ref().map_or_else(Vec::new, |f| f.craters_in(x0, z0, x1, z1)),
+            stamps: self.stamps.as_ref().map_or_else(Vec::new, |f| f.stamps_in(x0, z0, x1, z1)),
+        }
+    }
+
+    /// `features` must include every crater and stamp reaching (`x`, `z`).
+    fn shape(&self, x: f64, z: f64, detail: f64, features: &Features) -> f64 {
+        let continentalness = self.continent.continentalness(x, z);
+        let height = self.continent.apply(continentalness, detail);
+        let height = match &self.spline {
+            Some((spline, erosion)) => {
+                let inputs = SplineInputs {
+                    height,
+                    continentalness,
+                    erosion: erosion.sample(x, z),
+                };
+                spline.eval(&inputs).clamp(0.0, 1.0)
+            }
+            None => height,
+        };
+        let height = match &self.terrace {
+            Some(terrace) => terrace.apply(x, z, height),
+            None => height,
+        };
+        let height = if features.craters.is_empty() {
+            height
+        } else {
+            CraterField::apply(&features.craters, x, z, height)
+        };
+        match &self.stamps {
+            Some(stamps) if !features.stamps.is_empty() => stamps.apply(&features.stamps, x, z, height),
+            _ => height,
+        }
+    }
+
+    /// One row of `sample_row`; `features` must cover the whole row.
+    fn shape_row(&self, world_x0: f64, world_z: f64, out: &mut [f64], features: &Features) {
+        self.detail.sample_row(world_x0, world_z, out);
+        for (x, v) in out.iter_mut().enumerate() {
+            *v = self.shape(world_x0 + x as f64, world_z, *v, features);
+        }
+    }
+}
+
+impl NoiseSampler for ShapedNoise {
+    fn sample(&self, x: f64, z: f64) -> f64 {
+        let features = self.features_in(x, z, x, z);
+        self.shape(x, z, self.detail.sample(x, z), &features)
+    }
+
+    fn sample_row(&self, world_x0: f64, world_z: f64, out: &mut [f64]) {
+        let world_x1 = world_x0 + out.len().saturating_sub(1) as f64;
+        let features = self.features_in(world_x0, world_z, world_x1, world_z);
+        self.shape_row(world_x0, world_z, out, &features);
+    }
+
+    fn sampler_in(&self, x0: f64, z0: f64, x1: f64, z1: f64) -> Box<dyn Fn(f64, f64) -> f64 + '_> {
+        let features = self.features_in(x0, z0, x1, z1);
+        Box::new(move |x, z| self.shape(x, z, self.detail.sample(x, z), &features))
+    }
+
+    /// Finds the craters and stamps once for the whole grid rather than
+    /// once per row.
+    fn fill_grid(&self, world_x0: f64, world_z0: f64, width: usize, out: &mut [f32]) {
+        let rows = out.len().div_ceil(width.max(1));
+        let world_x1 = world_x0 + width.saturating_sub(1) as f64;
+        let world_z1 = world_z0 + rows.saturating_sub(1) as f64;
+        let features = self.features_in(world_x0, world_z0, world_x1, world_z1);
+        for_each_row(out, width, |z, row| {
+            let mut values = vec![0.0; row.len()];
+            self.shape_row(world_x0, world_z0 + z as f64, &mut values, &features);
+            for (h, v) in row.iter_mut().zip(values) {
+                *h = v as f32;
+            }
+        });
+    }
+}
+
+impl NoiseField for ShapedNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
+        let to_world = 1.0 / (self.scale * 0.005);
+        NoiseSampler::sample(self, sx * to_world, sz * to_world)
+    }
+}
+
+pub(crate) fn continent_field(config: &TerrainConfig) -> ContinentField {
+    ContinentField::new(
+        config.seed,
+        config.continent_mode().into(),
+        config.continent_frequency,
+        config.continent_threshold,
+        config.continent_strength,
+        config.sea_level,
+    )
+}
+
+/// Build the height noise selected by `config.noise_type`, with continents
+/// the height spline, terraces, craters and stamps applied.
+pub(crate) fn height_sampler(config: &TerrainConfig) -> Box<dyn NoiseSampler> {
+    let detail = noise_sampler(config, config.noise_type().into());
+    let continent = continent_field(config);
+    if continent.mode() == ContinentMode::Off
+        && config.height_spline.is_none()
+        && config.terrace_steps == 0
+        && config.crater_density == 0.0
+        && config.stamps.is_none()
+    {
+        return detail;
+    }
+    let spline = config
+        .height_spline
+        .clone()
+        .map(|spline| (spline, ErosionField::new(config.seed, config.spline_erosion_frequency)));
+    let terrace = (config.terrace_steps > 0).then(|| {
+        Terrace::new(
+            config.seed,
+            config.terrace_steps,
+            config.terrace_smoothness,
+            config.terrace_jitter,
+        )
+    });
+    let craters = (config.crater_density > 0.0).then(|| {
+        let params = CraterParams {
+            density: config.crater_density,
+            min_radius: config.crater_min_radius,
+            max_radius: config.crater_max_radius,
+            size_exponent: config.crater_size_exponent,
+            depth: config.crater_depth,
+            peak: config.crater_peak,
+        };
+        CraterField::new(config.seed, params)
+    });
+    let stamps = config.stamps.as_ref().map(StampField::new);
+    Box::new(ShapedNoise {
+        detail,
+        continent,
+        spline,
+        terrace,
+        craters,
+        stamps,
+        scale: config.scale,
+    })
+}
+
+fn noise_sampler(config: &TerrainConfig, noise_type: NoiseType) -> Box<dyn NoiseSampler> {
+    match noise_type {
+        NoiseType::Simplex | NoiseType::Perlin => Box::new(LayeredNoise::new(
+            config.seed,
+            noise_type,
+            config.octaves,
+            config.persistence,
+            config.lacunarity,
+            config.scale,
+        )),
+        NoiseType::Ridged => Box::new(RidgedNoise::new(
+            config.seed,
+            RidgeShape {
+                offset: config.ridged_offset,
+                gain: config.ridged_gain,
+                exponent: config.ridged_exponent,
+                weight_frequency: config.ridged_weight_frequency,
+            },
+            config.octaves,
+            config.persistence,
+            config.lacunarity,
+            config.scale,
+        )),
+        NoiseType::Warped => {
+            // Guard against a config that bypassed the warp_base setter
+            let base = match NoiseType::from(config.warp_base()) {
+                NoiseType::Warped => NoiseType::Simplex,
+                base => base,
+            };
+            let params = WarpParams {
+                strength: config.warp_strength,
+                depth: config.warp_depth,
+                octaves: config.warp_octaves,
+                frequency: config.warp_frequency,
+                level_gain: config.warp_level_gain,
+            };
+            Box::new(WarpedNoise::new(
+                config.seed,
+                noise_sampler(config, base),
+                params,
+                config.persistence,
+                config.lacunarity,
+                config.scale,
+            ))
+        }
+        NoiseType::Cellular => Box::new(cellular_noise(config)),
+        NoiseType::Billow => Box::new(BillowNoise::new(
+            config.seed,
+            config.octaves,
+            config.persistence,
+            config.lacunarity,
+            config.scale,
+        )),
+        NoiseType::HybridMultifractal | NoiseType::HeteroMultifractal => {
+            let kind = match noise_type {
+                NoiseType::HybridMultifractal => Multifractal::Hybrid,
+                _ => Multifractal::Hetero,
+            };
+            Box::new(MultifractalNoise::new(
+                config.seed,
+                kind,
+                config.multifractal_offset,
+                config.multifractal_gain,
+                config.octaves,
+                config.persistence,
+                config.lacunarity,
+                config.scale,
+            ))
+        }
+    }
+}
+
 fn fill_heightmap(
     noise: &dyn NoiseSampler,
     heightmap: &mut [f32],
@@ -201,16 +734,25 @@ fn fill_heightmap(
     chunk_z: i32,
     chunk_size: u32,
 ) {
-    let edge = chunk_size - 1;
-
-    for z in 0..size {
-        for x in 0..size {
-            let world_x = chunk_x as f64 * edge as f64 + x as f64;
-            let world_z = chunk_z as f64 * edge as f64 + z as f64;
-            let value = noise.sample(world_x, world_z) as f32;
-            heightmap[z * size + x] = value;
-        }
+    let edge = (chunk_size - 1) as f64;
+    noise.fill_grid(chunk_x as f64 * edge, chunk_z as f64 * edge, size, heightmap);
+}
+
+/// Run `f(row_index, row)` over each `width`-long row, on the rayon pool
+/// with the `parallel` feature (once a pool is available). Rows are
+/// independent, so the result is identical either way.
+pub(crate) fn for_each_row<T, F>(data: &mut [T], width: usize, f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if crate::threads::rayon_enabled() {
+        use rayon::prelude::*;
+        data.par_chunks_mut(width).enumerate().for_each(|(z, row)| f(z, row));
+        return;
     }
+    data.chunks_mut(width).enumerate().for_each(|(z, row)| f(z, row));
 }
 
 #[cfg(test)]
@@ -220,7 +762,7 @@ mod tests {
     #[test]
     fn generate_chunk_produces_correct_size() {
         let config = TerrainConfig::new(42);
-        let chunk = generate_chunk(&config, 0, 0);
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
         let expected = config.chunk_size * config.chunk_size;
         assert_eq!(chunk.heightmap.len(), expected as usize);
         assert_eq!(chunk.biome_map.len(), expected as usize);
@@ -229,8 +771,8 @@ mod tests {
     #[test]
     fn chunk_boundaries_are_seamless() {
         let config = TerrainConfig::new(42);
-        let chunk_a = generate_chunk(&config, 0, 0);
-        let chunk_b = generate_chunk(&config, 1, 0);
+        let chunk_a = generate_chunk(&config, 0, 0).unwrap();
+        let chunk_b = generate_chunk(&config, 1, 0).unwrap();
 
         let size = config.chunk_size as usize;
         // Rightmost column of chunk_a should match leftmost column of chunk_b
@@ -247,18 +789,220 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cellular_chunks_and_masks_are_seamless() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config.set_noise_type(4);
+        config.set_cellular_return(2);
+        let (a, b) = (generate_chunk(&config, 0, 0).unwrap(), generate_chunk(&config, 1, 0).unwrap());
+        let (mask_a, mask_b) = (generate_cell_mask(&config, 0, 0).unwrap(), generate_cell_mask(&config, 1, 0).unwrap());
+        for z in 0..33 {
+            assert_eq!(a.heightmap[z * 33 + 32], b.heightmap[z * 33]);
+            assert_eq!(mask_a[z * 33 + 32], mask_b[z * 33]);
+        }
+        // Cells are larger than a 33-cell chunk, so ids repeat within it
+        assert!(mask_a.iter().filter(|&&id| id == mask_a[0]).count() > 1);
+    }
+
+    #[test]
+    fn craters_are_seamless_across_regions() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 65;
+        config.crater_density = 64.0;
+        config.crater_min_radius = 30.0;
+        // Chunks 15 and 16 meet on the region border at x = 1024
+        let (a, b) = (generate_chunk(&config, 15, 2).unwrap(), generate_chunk(&config, 16, 2).unwrap());
+        for z in 0..65 {
+            assert_eq!(a.heightmap[z * 65 + 64], b.heightmap[z * 65]);
+        }
+        // Point samples and single rows agree with the chunk, whose craters
+        // are found once for its whole box
+        let sampler = height_sampler(&config);
+        for (i, &h) in b.heightmap.iter().enumerate().step_by(7) {
+            let (x, z) = (1024.0 + (i % 65) as f64, 128.0 + (i / 65) as f64);
+            assert_eq!(sampler.sample(x, z) as f32, h);
+        }
+        let mut row = vec![0.0; 65];
+        for z in [0, 31, 64] {
+            sampler.sample_row(1024.0, 128.0 + z as f64, &mut row);
+            assert!(row.iter().zip(&b.heightmap[z * 65..]).all(|(&r, &h)| r as f32 == h));
+        }
+
+        config.crater_density = 0.0;
+        assert_ne!(b.heightmap, generate_chunk(&config, 16, 2).unwrap().heightmap);
+    }
+
+    #[test]
+    fn stamps_straddle_chunk_borders() {
+        use crate::features::{BlendMode, Brush, StampLibrary, StampPlacement};
+
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        let plain = generate_chunk(&config, 1, 0).unwrap();
+        // A 5x5 pyramid on the border between chunks 0 and 1, at x = 32
+        let data = (0..25)
+            .map(|i: i32| 1.0 - 0.25 * ((i % 5 - 2).abs().max((i / 5 - 2).abs()) as f32))
+            .collect();
+        config.stamps = Some(StampLibrary {
+            brushes: vec![Brush { name: "peak".into(), width: 5, height: 5, data }],
+            stamps: vec![StampPlacement {
+                brush: "peak".into(),
+                x: 32.0,
+                z: 16.0,
+                rotation: 30.0,
+                scale: 4.0,
+                blend: BlendMode::Max,
+                falloff: 0.25,
+            }],
+        });
+        assert_eq!(config.validate(), Ok(()));
+        let (a, b) = (generate_chunk(&config, 0, 0).unwrap(), generate_chunk(&config, 1, 0).unwrap());
+        for z in 0..33 {
+            assert_eq!(a.heightmap[z * 33 + 32], b.heightmap[z * 33]);
+        }
+        assert_eq!(b.heightmap[16 * 33], 1.0);
+        assert!(b.heightmap.iter().zip(&plain.heightmap).all(|(s, p)| s >= p));
+        // Far from the stamp the terrain is untouched
+        assert_eq!(b.heightmap[32], plain.heightmap[32]);
+        let sampler = height_sampler(&config);
+        assert_eq!(sampler.sample(36.0, 18.0) as f32, b.heightmap[18 * 33 + 4]);
+    }
+
+    #[test]
+    fn height_spline_remaps_before_erosion() {
+        use crate::spline::{HeightSpline, SplineInput, SplinePoint, SplineValue};
+
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        // A plateau: everything above 0.5 flattens to 0.7
+        let point = |location, value| SplinePoint {
+            location,
+            value: SplineValue::Constant(value),
+            derivative: 0.0,
+        };
+        config.height_spline = Some(HeightSpline {
+            input: SplineInput::Height,
+            points: vec![point(0.0, 0.0), point(0.5, 0.7)],
+        });
+        let plain = generate_chunk(&TerrainConfig { height_spline: None, ..config.clone() }, 0, 0).unwrap();
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        for (&h, &raw) in chunk.heightmap.iter().zip(&plain.heightmap) {
+            if raw >= 0.5 {
+                assert_eq!(h, 0.7);
+            } else {
+                assert!(h < 0.7);
+            }
+        }
+    }
+
+    #[test]
+    fn terraces_and_strata() {
+        use crate::strata::{RockLayer, Strata};
+
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config.terrace_steps = 4;
+        config.terrace_smoothness = 0.0;
+        let chunk = generate_chunk(&config, 1, 0).unwrap();
+        for &h in chunk.heightmap() {
+            assert_eq!((h * 4.0).fract(), 0.0, "{} is not on a tread", h);
+        }
+        assert!(chunk.material_map().iter().all(|&m| m == 0));
+
+        let layer = |rock, thickness| RockLayer { rock, thickness, hardness: 0.5 };
+        config.strata = Some(Strata {
+            layers: vec![layer(1, 0.25), layer(2, 0.25), layer(3, 0.5)],
+        });
+        let chunk = generate_chunk(&config, 1, 0).unwrap();
+        for (&h, &m) in chunk.heightmap().iter().zip(chunk.material_map()) {
+            let expected = match h {
+                h if h < 0.25 => 1,
+                h if h < 0.5 => 2,
+                _ => 3,
+            };
+            assert_eq!(m, expected, "height {}", h);
+        }
+    }
+
+    #[test]
+    fn hardness_map_matches_erosion() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config.erosion_enabled = true;
+        config.erosion_iterations = 2000;
+        config.hardness_noise = 1.0;
+        let chunk = generate_chunk(&config, -1, 2).unwrap();
+        let map = chunk_hardness_map(&config, &chunk).unwrap();
+        assert_eq!(map.len(), chunk.heightmap().len());
+        assert!(map.iter().all(|&h| (0.0..=1.0).contains(&h)));
+
+        // Nothing erodes through fully hard rock
+        config.strata = Some(crate::Strata {
+            layers: vec![crate::RockLayer { rock: 1, thickness: 1.0, hardness: 1.0 }],
+        });
+        let chunk = generate_chunk(&config, -1, 2).unwrap();
+        assert!(chunk_hardness_map(&config, &chunk).unwrap().iter().all(|&h| h == 1.0));
+        config.erosion_enabled = false;
+        assert_eq!(chunk.heightmap(), generate_chunk(&config, -1, 2).unwrap().heightmap());
+    }
+
     #[test]
     fn negative_chunks_work() {
         let config = TerrainConfig::new(42);
-        let chunk = generate_chunk(&config, -5, -3);
+        let chunk = generate_chunk(&config, -5, -3).unwrap();
         assert_eq!(chunk.heightmap.len(), (config.chunk_size * config.chunk_size) as usize);
         assert!(chunk.heightmap.iter().all(|v| v.is_finite()));
     }
 
+    #[test]
+    fn matches_per_cell_reference() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        let chunk = generate_chunk(&config, 2, -3).unwrap();
+
+        let noise = LayeredNoise::new(42, NoiseType::Simplex, 6, 0.5, 2.0, 1.0);
+        let moisture_gen = MoistureGenerator::new(42, 1.0);
+        for z in 0..33 {
+            for x in 0..33 {
+                let (wx, wz) = ((2 * 32 + x) as f64, z as f64 - 3.0 * 32.0);
+                let idx = z * 33 + x;
+                // Bitwise equality: row-parallel fill must not change any value
+                assert_eq!(chunk.heightmap[idx].to_bits(), (noise.sample(wx, wz) as f32).to_bits());
+                assert_eq!(chunk.moisture_map[idx].to_bits(), moisture_gen.sample(wx, wz).to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn into_buffers_moves_layers() {
+        let config = TerrainConfig::new(42);
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let heights_ptr = chunk.heightmap.as_ptr();
+        let expected = chunk.biome_map.clone();
+
+        let mut buffers = chunk.into_buffers();
+        assert_eq!(buffers.heightmap.as_ptr(), heights_ptr, "Heightmap must not be reallocated");
+        let heightmap = buffers.take_heightmap();
+        assert_eq!(heightmap.as_ptr(), heights_ptr);
+        assert_eq!(buffers.take_biome_map(), expected);
+        assert!(buffers.take_biome_map().is_empty(), "A layer is handed out once");
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 1;
+        assert_eq!(
+            generate_chunk(&config, 0, 0).err(),
+            Some(ConfigError::ChunkSize(1))
+        );
+    }
+
     #[test]
     fn biome_map_contains_valid_values() {
         let config = TerrainConfig::new(42);
-        let chunk = generate_chunk(&config, 0, 0);
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
         assert!(chunk.biome_map.iter().all(|&b| b <= 11));
     }
 }
@@ -0,0 +1,465 @@
+//! Versioned binary encoding of `ChunkData` for persistent caches.
+//!
+//! Layout (little endian):
+//!
+//! ```text
+//! magic "TSCK" | version u8 | flags u8 | reserved u16
+//! config_hash u64 | chunk_x i32 | chunk_z i32 | width u32 | height u32
+//! min_height f32 | max_height f32
+//! per present layer: byte length u32 + payload
+//! crc32 of everything above
+//! ```
+//!
+//! Heights are quantised to u16 over `min_height..=max_height` and moisture
+//! over `0..=1`, then delta coded row-major as zigzag varints. The biome and
+//! material maps are run-length encoded; the material map is stored only
+//! when the config has strata. Quantisation error is at most half a step:
+//! `(max_height - min_height) / 131070` for heights, `1 / 131070` for moisture.
+
+use std::sync::OnceLock;
+
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::chunk::ChunkData;
+use crate::pipeline::StageHashes;
+use crate::TerrainConfig;
+
+pub const FORMAT_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"TSCK";
+const HEADER_LEN: usize = 40;
+
+pub const LAYER_HEIGHTS: u8 = 1 << 0;
+pub const LAYER_MOISTURE: u8 = 1 << 1;
+pub const LAYER_BIOMES: u8 = 1 << 2;
+pub const LAYER_MATERIALS: u8 = 1 << 3;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CodecError {
+    #[error("not a TerraSynth chunk (bad magic)")]
+    BadMagic,
+    #[error("unsupported chunk format version {0} (expected {FORMAT_VERSION})")]
+    UnsupportedVersion(u8),
+    #[error("chunk data truncated")]
+    Truncated,
+    #[error("checksum mismatch: stored {stored:#010x}, computed {computed:#010x}")]
+    ChecksumMismatch { stored: u32, computed: u32 },
+    #[error("chunk was generated with a different config (hash {found:#018x}, expected {expected:#018x})")]
+    ConfigMismatch { found: u64, expected: u64 },
+    #[error("chunk is {width}x{height}, expected {expected}x{expected}")]
+    SizeMismatch { width: u32, height: u32, expected: u32 },
+    #[error("malformed {0} layer")]
+    Malformed(&'static str),
+}
+
+impl From<CodecError> for JsValue {
+    fn from(err: CodecError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkHeader {
+    pub version: u8,
+    pub flags: u8,
+    pub config_hash: u64,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Encode a chunk. The moisture layer is stored only when moisture is
+/// enabled, the material layer only with strata.
+#[wasm_bindgen]
+pub fn encode_chunk(chunk: &ChunkData, config: &TerrainConfig) -> Vec<u8> {
+    let mut flags = LAYER_HEIGHTS | LAYER_BIOMES;
+    if config.moisture_enabled {
+        flags |= LAYER_MOISTURE;
+    }
+    if config.strata.is_some() {
+        flags |= LAYER_MATERIALS;
+    }
+
+    let mut out = Vec::with_capacity(HEADER_LEN + chunk.heightmap.len() * 2);
+    out.extend_from_slice(MAGIC);
+    out.push(FORMAT_VERSION);
+    out.push(flags);
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&config.config_hash().to_le_bytes());
+    out.extend_from_slice(&chunk.chunk_x.to_le_bytes());
+    out.extend_from_slice(&chunk.chunk_z.to_le_bytes());
+    out.extend_from_slice(&chunk.width.to_le_bytes());
+    out.extend_from_slice(&chunk.height.to_le_bytes());
+    out.extend_from_slice(&chunk.min_height.to_le_bytes());
+    out.extend_from_slice(&chunk.max_height.to_le_bytes());
+
+    write_layer(&mut out, &encode_quantised(&chunk.heightmap, chunk.min_height, chunk.max_height));
+    if flags & LAYER_MOISTURE != 0 {
+        write_layer(&mut out, &encode_quantised(&chunk.moisture_map, 0.0, 1.0));
+    }
+    write_layer(&mut out, &encode_rle(&chunk.biome_map));
+    if flags & LAYER_MATERIALS != 0 {
+        write_layer(&mut out, &encode_rle(&chunk.material_map));
+    }
+
+    let crc = crc32(&out);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out
+}
+
+/// Decode a chunk, rejecting corrupt data and chunks from another config.
+#[wasm_bindgen]
+pub fn decode_chunk(bytes: &[u8], config: &TerrainConfig) -> Result<ChunkData, CodecError> {
+    let header = read_header(bytes)?;
+    let expected = config.config_hash();
+    if header.config_hash != expected {
+        return Err(CodecError::ConfigMismatch {
+            found: header.config_hash,
+            expected,
+        });
+    }
+
+    // The size is untrusted; bound it before anything is allocated from it
+    if header.width != config.chunk_size || header.height != config.chunk_size {
+        return Err(CodecError::SizeMismatch {
+            width: header.width,
+            height: header.height,
+            expected: config.chunk_size,
+        });
+    }
+    let total = header.width as usize * header.height as usize;
+    let min_height = f32::from_le_bytes(bytes[32..36].try_into().unwrap());
+    let max_height = f32::from_le_bytes(bytes[36..40].try_into().unwrap());
+
+    let mut reader = Reader {
+        bytes: &bytes[..bytes.len() - 4],
+        pos: HEADER_LEN,
+    };
+    let heightmap = decode_quantised(reader.layer()?, total, min_height, max_height)
+        .ok_or(CodecError::Malformed("height"))?;
+    let moisture_map = if header.flags & LAYER_MOISTURE != 0 {
+        decode_quantised(reader.layer()?, total, 0.0, 1.0).ok_or(CodecError::Malformed("moisture"))?
+    } else {
+        vec![0.0; total]
+    };
+    let biome_map = decode_rle(reader.layer()?, total)
+        .filter(|biomes| biomes.iter().all(|&b| Biome::try_from(b).is_ok()))
+        .ok_or(CodecError::Malformed("biome"))?;
+    let material_map = if header.flags & LAYER_MATERIALS != 0 {
+        decode_rle(reader.layer()?, total).ok_or(CodecError::Malformed("material"))?
+    } else {
+        vec![0; total]
+    };
+
+    Ok(ChunkData {
+        heightmap,
+        moisture_map,
+        biome_map,
+        material_map,
+        chunk_x: header.chunk_x,
+        chunk_z: header.chunk_z,
+        width: header.width,
+        height: header.height,
+        min_height,
+        max_height,
+        quadtree: OnceLock::new(),
+        stages: StageHashes::new(config),
+    })
+}
+
+/// Validate magic, version and checksum, then parse the fixed header.
+pub fn read_header(bytes: &[u8]) -> Result<ChunkHeader, CodecError> {
+    if bytes.len() < 4 || &bytes[..4] != MAGIC {
+        return Err(CodecError::BadMagic);
+    }
+    if bytes.len() < HEADER_LEN + 4 {
+        return Err(CodecError::Truncated);
+    }
+    if bytes[4] != FORMAT_VERSION {
+        return Err(CodecError::UnsupportedVersion(bytes[4]));
+    }
+
+    let body = bytes.len() - 4;
+    let stored = u32::from_le_bytes(bytes[body..].try_into().unwrap());
+    let computed = crc32(&bytes[..body]);
+    if stored != computed {
+        return Err(CodecError::ChecksumMismatch { stored, computed });
+    }
+
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    Ok(ChunkHeader {
+        version: bytes[4],
+        flags: bytes[5],
+        config_hash: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+        chunk_x: u32_at(16) as i32,
+        chunk_z: u32_at(20) as i32,
+        width: u32_at(24),
+        height: u32_at(28),
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn layer(&mut self) -> Result<&'a [u8], CodecError> {
+        let len_end = self.pos + 4;
+        if len_end > self.bytes.len() {
+            return Err(CodecError::Truncated);
+        }
+        let len = u32::from_le_bytes(self.bytes[self.pos..len_end].try_into().unwrap()) as usize;
+        let end = len_end.checked_add(len).ok_or(CodecError::Truncated)?;
+        if end > self.bytes.len() {
+            return Err(CodecError::Truncated);
+        }
+        self.pos = end;
+        Ok(&self.bytes[len_end..end])
+    }
+}
+
+fn write_layer(out: &mut Vec<u8>, payload: &[u8]) {
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut v = 0u32;
+    for shift in (0..35).step_by(7) {
+        let b = *bytes.get(*pos)?;
+        *pos += 1;
+        v |= ((b & 0x7F) as u32) << shift;
+        if b & 0x80 == 0 {
+            return Some(v);
+        }
+    }
+    None
+}
+
+fn encode_quantised(values: &[f32], min: f32, max: f32) -> Vec<u8> {
+    let range = max - min;
+    let mut out = Vec::with_capacity(values.len());
+    let mut prev = 0i32;
+    for &v in values {
+        let q = if range > 0.0 {
+            (((v - min) / range).clamp(0.0, 1.0) * 65535.0).round() as i32
+        } else {
+            0
+        };
+        let delta = q - prev;
+        write_varint(&mut out, ((delta << 1) ^ (delta >> 31)) as u32);
+        prev = q;
+    }
+    out
+}
+
+fn decode_quantised(payload: &[u8], total: usize, min: f32, max: f32) -> Option<Vec<f32>> {
+    let range = max - min;
+    // Every value takes at least one byte, so a forged size can't force a huge allocation
+    let mut values = Vec::with_capacity(total.min(payload.len()));
+    let mut pos = 0;
+    let mut prev = 0i32;
+    for _ in 0..total {
+        let z = read_varint(payload, &mut pos)?;
+        prev = prev.wrapping_add(((z >> 1) as i32) ^ -((z & 1) as i32));
+        if !(0..=65535).contains(&prev) {
+            return None;
+        }
+        values.push(min + prev as f32 / 65535.0 * range);
+    }
+    (pos == payload.len()).then_some(values)
+}
+
+fn encode_rle(values: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        let v = values[i];
+        let run = values[i..].iter().take_while(|&&b| b == v).count();
+        write_varint(&mut out, run as u32);
+        out.push(v);
+        i += run;
+    }
+    out
+}
+
+fn decode_rle(payload: &[u8], total: usize) -> Option<Vec<u8>> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < payload.len() {
+        let run = read_varint(payload, &mut pos)? as usize;
+        let v = *payload.get(pos)?;
+        pos += 1;
+        if run == 0 || values.len() + run > total {
+            return None;
+        }
+        values.resize(values.len() + run, v);
+    }
+    (values.len() == total).then_some(values)
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::generate_chunk;
+
+    fn small_config() -> TerrainConfig {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config
+    }
+
+    #[test]
+    fn round_trip_within_quantisation_error() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, 3, -2).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+        let decoded = decode_chunk(&bytes, &config).unwrap();
+
+        assert_eq!((decoded.chunk_x, decoded.chunk_z), (3, -2));
+        assert_eq!((decoded.width, decoded.height), (chunk.width, chunk.height));
+        assert_eq!(decoded.biome_map, chunk.biome_map);
+
+        let step = (chunk.max_height - chunk.min_height) / 65535.0;
+        for (a, b) in chunk.heightmap.iter().zip(&decoded.heightmap) {
+            assert!((a - b).abs() <= step, "Height error {} exceeds step {}", (a - b).abs(), step);
+        }
+        for (a, b) in chunk.moisture_map.iter().zip(&decoded.moisture_map) {
+            assert!((a - b).abs() <= 1.0 / 65535.0);
+        }
+        assert!(bytes.len() < chunk.heightmap.len() * 4, "Encoding should be smaller than raw f32");
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+
+        let mut flipped = bytes.clone();
+        flipped[HEADER_LEN + 10] ^= 0x40;
+        assert!(matches!(
+            decode_chunk(&flipped, &config),
+            Err(CodecError::ChecksumMismatch { .. })
+        ));
+
+        assert!(decode_chunk(&bytes[..bytes.len() / 2], &config).is_err());
+        assert_eq!(decode_chunk(b"nope", &config).err(), Some(CodecError::BadMagic));
+
+        let mut future = bytes.clone();
+        future[4] = FORMAT_VERSION + 1;
+        assert_eq!(
+            decode_chunk(&future, &config).err(),
+            Some(CodecError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+    }
+
+    /// Rewrite a header or payload byte range and fix up the checksum.
+    fn forge(bytes: &[u8], at: usize, patch: &[u8]) -> Vec<u8> {
+        let mut forged = bytes[..bytes.len() - 4].to_vec();
+        forged[at..at + patch.len()].copy_from_slice(patch);
+        let crc = crc32(&forged);
+        forged.extend_from_slice(&crc.to_le_bytes());
+        forged
+    }
+
+    #[test]
+    fn rejects_forged_sizes_and_biomes() {
+        let mut config = small_config();
+        config.moisture_enabled = false;
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+
+        let huge = forge(&bytes, 24, &[0xFF; 8]);
+        assert_eq!(
+            decode_chunk(&huge, &config).err(),
+            Some(CodecError::SizeMismatch { width: u32::MAX, height: u32::MAX, expected: 33 })
+        );
+
+        // A single run covering the whole chunk, of an unknown biome
+        let mut rle = Vec::new();
+        write_varint(&mut rle, 33 * 33);
+        rle.push(200);
+        let mut forged = bytes[..HEADER_LEN].to_vec();
+        write_layer(&mut forged, &encode_quantised(&chunk.heightmap, chunk.min_height, chunk.max_height));
+        write_layer(&mut forged, &rle);
+        forged.extend_from_slice(&[0; 4]);
+        let forged = forge(&forged, 0, MAGIC);
+        assert_eq!(decode_chunk(&forged, &config).err(), Some(CodecError::Malformed("biome")));
+    }
+
+    #[test]
+    fn rejects_stale_config() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+
+        let mut changed = small_config();
+        changed.sea_level = 0.4;
+        assert!(matches!(
+            decode_chunk(&bytes, &changed),
+            Err(CodecError::ConfigMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn material_layer_round_trips() {
+        let mut config = small_config();
+        config
+            .set_strata_json(Some(r#"{"layers": [{"rock": 3, "thickness": 0.4}, {"rock": 7, "thickness": 0.1}]}"#.into()))
+            .unwrap();
+        let chunk = generate_chunk(&config, 2, 0).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+        assert_ne!(read_header(&bytes).unwrap().flags & LAYER_MATERIALS, 0);
+        assert_eq!(decode_chunk(&bytes, &config).unwrap().material_map, chunk.material_map);
+    }
+
+    #[test]
+    fn moisture_layer_is_optional() {
+        let mut config = small_config();
+        config.moisture_enabled = false;
+        let chunk = generate_chunk(&config, 1, 1).unwrap();
+        let bytes = encode_chunk(&chunk, &config);
+        assert_eq!(read_header(&bytes).unwrap().flags & LAYER_MOISTURE, 0);
+        let decoded = decode_chunk(&bytes, &config).unwrap();
+        assert!(decoded.moisture_map.iter().all(|&m| m == 0.0));
+    }
+}
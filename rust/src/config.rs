@@ -0,0 +1,591 @@
+//! JSON/TOML documents for `TerrainConfig`, shared by the CLI and the browser.
+//!
+//! A document is the flat list of config fields plus a `version`. Documents
+//! without a `version` are treated as version 0: the camelCase settings JSON
+//! the web UI exports (`WorldConfig`), which is migrated field by field.
+
+use std::ops::RangeInclusive;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::features::craters::{MAX_CRATER_DENSITY, MAX_CRATER_RADIUS};
+use crate::features::stamps::{Brush, StampLibrary};
+use crate::strata::MAX_TERRACE_STEPS;
+use crate::TerrainConfig;
+
+pub const CONFIG_VERSION: u32 = 1;
+
+// Render-only settings in version 0 documents; dropped without a warning
+const VIEW_ONLY_FIELDS: &[&str] = &[
+    "view_distance",
+    "generate_water",
+    "generate_fog",
+    "wireframe",
+    "show_shadows",
+];
+
+pub const CHUNK_SIZE_RANGE: RangeInclusive<u32> = 2..=1024;
+pub const OCTAVES_RANGE: RangeInclusive<u32> = 1..=16;
+pub const LACUNARITY_MAX: f64 = 8.0;
+
+/// A parameter outside the range the generator can work with.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ConfigError {
+    #[error("chunk_size must be in {min}..={max}, got {0}", min = CHUNK_SIZE_RANGE.start(), max = CHUNK_SIZE_RANGE.end())]
+    ChunkSize(u32),
+    #[error("scale must be positive and finite, got {0}")]
+    Scale(f64),
+    #[error("octaves must be in {min}..={max}, got {0}", min = OCTAVES_RANGE.start(), max = OCTAVES_RANGE.end())]
+    Octaves(u32),
+    #[error("persistence must be in 0..=1, got {0}")]
+    Persistence(f64),
+    #[error("lacunarity must be in (0, {LACUNARITY_MAX}], got {0}")]
+    Lacunarity(f64),
+    #[error("height_multiplier must be non-negative and finite, got {0}")]
+    HeightMultiplier(f64),
+    #[error("sea_level must be in 0..=1, got {0}")]
+    SeaLevel(f64),
+    #[error("erosion_{field} must be in {min}..={max}, got {value}")]
+    Erosion {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("hardness_{field} must be in {min}..={max}, got {value}")]
+    Hardness {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("warp_strength must be non-negative and finite, got {0}")]
+    WarpStrength(f64),
+    #[error("height_spline {0}")]
+    HeightSpline(&'static str),
+    #[error("spline_erosion_frequency must be in 1e-5..=0.1, got {0}")]
+    SplineErosionFrequency(f64),
+    #[error("terrace_{field} must be in {min}..={max}, got {value}")]
+    Terrace {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("crater_{field} must be in {min}..={max}, got {value}")]
+    Crater {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("strata {0}")]
+    Strata(&'static str),
+    #[error("stamps {0}")]
+    Stamps(&'static str),
+    #[error("continent_{field} must be in {min}..={max}, got {value}")]
+    Continent {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("warp_{field} must be in {min}..={max}, got {value}")]
+    Warp {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    #[error("cellular_jitter must be in 0..=1, got {0}")]
+    CellularJitter(f64),
+    #[error("multifractal_offset must be in 0..=2, got {0}")]
+    MultifractalOffset(f64),
+    #[error("multifractal_gain must be non-negative and finite, got {0}")]
+    MultifractalGain(f64),
+    #[error("ridged_{field} must be in {min}..={max}, got {value}")]
+    Ridged {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl From<ConfigError> for JsValue {
+    fn from(err: ConfigError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigFormatError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid TOML: {0}")]
+    TomlParse(#[from] toml::de::Error),
+    #[error("cannot write TOML: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+    #[error("config document must be a table/object")]
+    NotATable,
+    #[error("config version {0} is newer than supported version {CONFIG_VERSION}")]
+    UnsupportedVersion(u64),
+    #[error("config version must be a non-negative integer, got {0}")]
+    BadVersion(String),
+}
+
+impl From<ConfigFormatError> for JsValue {
+    fn from(err: ConfigFormatError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[derive(Serialize)]
+struct ConfigDocument<'a> {
+    version: u32,
+    #[serde(flatten)]
+    config: &'a TerrainConfig,
+}
+
+/// A parsed config plus any fields that were ignored while reading it.
+pub struct LoadedConfig {
+    pub config: TerrainConfig,
+    pub warnings: Vec<String>,
+}
+
+impl TerrainConfig {
+    pub fn from_json_str(json: &str) -> Result<LoadedConfig, ConfigFormatError> {
+        load_document(serde_json::from_str(json)?)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<LoadedConfig, ConfigFormatError> {
+        let table: toml::Table = text.parse()?;
+        load_document(serde_json::to_value(table)?)
+    }
+
+    pub fn to_json_string(&self) -> String {
+        let doc = ConfigDocument {
+            version: CONFIG_VERSION,
+            config: self,
+        };
+        serde_json::to_string_pretty(&doc).expect("TerrainConfig always serializes to JSON")
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ConfigFormatError> {
+        let doc = ConfigDocument {
+            version: CONFIG_VERSION,
+            config: self,
+        };
+        Ok(toml::to_string(&doc)?)
+    }
+}
+
+#[wasm_bindgen]
+impl TerrainConfig {
+    /// Check every parameter, reporting the first one out of range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !CHUNK_SIZE_RANGE.contains(&self.chunk_size) {
+            return Err(ConfigError::ChunkSize(self.chunk_size));
+        }
+        if !(self.scale.is_finite() && self.scale > 0.0) {
+            return Err(ConfigError::Scale(self.scale));
+        }
+        if !OCTAVES_RANGE.contains(&self.octaves) {
+            return Err(ConfigError::Octaves(self.octaves));
+        }
+        if !(0.0..=1.0).contains(&self.persistence) {
+            return Err(ConfigError::Persistence(self.persistence));
+        }
+        if !(self.lacunarity > 0.0 && self.lacunarity <= LACUNARITY_MAX) {
+            return Err(ConfigError::Lacunarity(self.lacunarity));
+        }
+        if !(self.height_multiplier.is_finite() && self.height_multiplier >= 0.0) {
+            return Err(ConfigError::HeightMultiplier(self.height_multiplier));
+        }
+        if !(0.0..=1.0).contains(&self.sea_level) {
+            return Err(ConfigError::SeaLevel(self.sea_level));
+        }
+
+        let erosion = [
+            ("inertia", self.erosion_inertia, 0.0, 1.0),
+            ("capacity", self.erosion_capacity, 0.0, 64.0),
+            ("deposition", self.erosion_deposition, 0.0, 1.0),
+            ("erosion_rate", self.erosion_erosion_rate, 0.0, 1.0),
+            ("evaporation", self.erosion_evaporation, 0.0, 1.0),
+        ];
+        for (field, value, min, max) in erosion {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Erosion { field, value, min, max });
+            }
+        }
+        let hardness = [
+            ("noise", self.hardness_noise, 0.0, 1.0),
+            ("frequency", self.hardness_frequency, 1e-4, 0.1),
+        ];
+        for (field, value, min, max) in hardness {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Hardness { field, value, min, max });
+            }
+        }
+
+        if !(self.warp_strength.is_finite() && self.warp_strength >= 0.0) {
+            return Err(ConfigError::WarpStrength(self.warp_strength));
+        }
+        let warp = [
+            ("depth", self.warp_depth as f64, 1.0, 4.0),
+            ("octaves", self.warp_octaves as f64, 1.0, 8.0),
+            ("frequency", self.warp_frequency, 1e-4, 0.1),
+            ("level_gain", self.warp_level_gain, 0.0, 4.0),
+        ];
+        for (field, value, min, max) in warp {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Warp { field, value, min, max });
+            }
+        }
+        if !(0.0..=1.0).contains(&self.cellular_jitter) {
+            return Err(ConfigError::CellularJitter(self.cellular_jitter));
+        }
+        if !(0.0..=2.0).contains(&self.multifractal_offset) {
+            return Err(ConfigError::MultifractalOffset(self.multifractal_offset));
+        }
+        if !(self.multifractal_gain.is_finite() && self.multifractal_gain >= 0.0) {
+            return Err(ConfigError::MultifractalGain(self.multifractal_gain));
+        }
+
+        let continent = [
+            ("frequency", self.continent_frequency, 1e-5, 0.01),
+            ("threshold", self.continent_threshold, 0.05, 0.95),
+            ("strength", self.continent_strength, 0.0, 1.0),
+        ];
+        for (field, value, min, max) in continent {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Continent { field, value, min, max });
+            }
+        }
+
+        if let Some(spline) = &self.height_spline {
+            spline.validate()?;
+        }
+        if !(1e-5..=0.1).contains(&self.spline_erosion_frequency) {
+            return Err(ConfigError::SplineErosionFrequency(self.spline_erosion_frequency));
+        }
+
+        let terrace = [
+            ("steps", self.terrace_steps as f64, 0.0, MAX_TERRACE_STEPS as f64),
+            ("smoothness", self.terrace_smoothness, 0.0, 1.0),
+            ("jitter", self.terrace_jitter, 0.0, 1.0),
+        ];
+        for (field, value, min, max) in terrace {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Terrace { field, value, min, max });
+            }
+        }
+        if let Some(strata) = &self.strata {
+            strata.validate()?;
+        }
+
+        let crater = [
+            ("density", self.crater_density, 0.0, MAX_CRATER_DENSITY),
+            ("min_radius", self.crater_min_radius, 1.0, MAX_CRATER_RADIUS),
+            ("max_radius", self.crater_max_radius, self.crater_min_radius, MAX_CRATER_RADIUS),
+            ("size_exponent", self.crater_size_exponent, 0.5, 4.0),
+            ("depth", self.crater_depth, 0.0, 1.0),
+            ("peak", self.crater_peak, 0.0, 1.0),
+        ];
+        for (field, value, min, max) in crater {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Crater { field, value, min, max });
+            }
+        }
+        if let Some(stamps) = &self.stamps {
+            stamps.validate()?;
+        }
+
+        let ridged = [
+            ("offset", self.ridged_offset, 0.1, 2.0),
+            ("gain", self.ridged_gain, 0.0, 8.0),
+            ("exponent", self.ridged_exponent, 0.5, 4.0),
+            ("weight_frequency", self.ridged_weight_frequency, 0.0, 1.0),
+        ];
+        for (field, value, min, max) in ridged {
+            if !(min..=max).contains(&value) {
+                return Err(ConfigError::Ridged { field, value, min, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a JSON config document, logging ignored fields to the console.
+    pub fn from_json(json: &str) -> Result<TerrainConfig, ConfigFormatError> {
+        let loaded = Self::from_json_str(json)?;
+        #[cfg(target_arch = "wasm32")]
+        for warning in &loaded.warnings {
+            web_sys::console::warn_1(&warning.into());
+        }
+        Ok(loaded.config)
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_json_string()
+    }
+
+    /// The height spline as JSON, or `undefined` if there is none.
+    pub fn height_spline_json(&self) -> Option<String> {
+        let spline = self.height_spline.as_ref()?;
+        Some(serde_json::to_string(spline).expect("HeightSpline always serializes to JSON"))
+    }
+
+    /// Set the height spline from JSON; `undefined` removes it.
+    pub fn set_height_spline_json(&mut self, json: Option<String>) -> Result<(), ConfigFormatError> {
+        self.height_spline = json.map(|json| serde_json::from_str(&json)).transpose()?;
+        Ok(())
+    }
+
+    /// The strata as JSON, or `undefined` if there are none.
+    pub fn strata_json(&self) -> Option<String> {
+        let strata = self.strata.as_ref()?;
+        Some(serde_json::to_string(strata).expect("Strata always serializes to JSON"))
+    }
+
+    /// Set the strata from JSON; `undefined` removes them.
+    pub fn set_strata_json(&mut self, json: Option<String>) -> Result<(), ConfigFormatError> {
+        self.strata = json.map(|json| serde_json::from_str(&json)).transpose()?;
+        Ok(())
+    }
+
+    /// The stamp library as JSON, or `undefined` if there is none.
+    pub fn stamps_json(&self) -> Option<String> {
+        let stamps = self.stamps.as_ref()?;
+        Some(serde_json::to_string(stamps).expect("StampLibrary always serializes to JSON"))
+    }
+
+    /// Set the stamp library from JSON; `undefined` removes it.
+    pub fn set_stamps_json(&mut self, json: Option<String>) -> Result<(), ConfigFormatError> {
+        self.stamps = json.map(|json| serde_json::from_str(&json)).transpose()?;
+        Ok(())
+    }
+
+    /// Load a row-major float heightmap into the stamp library as brush
+    /// `name`, replacing any brush of that name. Place it through
+    /// `set_stamps_json`.
+    pub fn add_stamp_brush(&mut self, name: String, width: u32, height: u32, data: Vec<f32>) {
+        self.stamps
+            .get_or_insert_with(StampLibrary::default)
+            .add_brush(Brush { name, width, height, data });
+    }
+}
+
+fn load_document(doc: Value) -> Result<LoadedConfig, ConfigFormatError> {
+    let Value::Object(mut fields) = doc else {
+        return Err(ConfigFormatError::NotATable);
+    };
+
+    let version = match fields.remove("version") {
+        None => 0,
+        Some(v) => v.as_u64().ok_or_else(|| ConfigFormatError::BadVersion(v.to_string()))?,
+    };
+    if version > CONFIG_VERSION as u64 {
+        return Err(ConfigFormatError::UnsupportedVersion(version));
+    }
+    if version == 0 {
+        fields = migrate_v0(fields);
+    }
+
+    let known = known_fields();
+    let mut warnings = Vec::new();
+    fields.retain(|key, _| {
+        let keep = known.contains(key);
+        if !keep {
+            warnings.push(format!("Ignoring unknown config field `{}`", key));
+        }
+        keep
+    });
+
+    let mut config: TerrainConfig = serde_json::from_value(Value::Object(fields))?;
+    // Re-apply setter clamping to fields that bypassed it
+    config.set_noise_type(config.noise_type());
+    config.set_warp_base(config.warp_base());
+    config.set_continent_mode(config.continent_mode());
+    config.set_cellular_return(config.cellular_return());
+    config.set_cellular_distance(config.cellular_distance());
+    Ok(LoadedConfig { config, warnings })
+}
+
+/// Version 0 is the web UI's camelCase `WorldConfig` export.
+fn migrate_v0(fields: Map<String, Value>) -> Map<String, Value> {
+    fields
+        .into_iter()
+        .map(|(key, value)| (camel_to_snake(&key), value))
+        .filter(|(key, _)| !VIEW_ONLY_FIELDS.contains(&key.as_str()))
+        .collect()
+}
+
+fn camel_to_snake(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn known_fields() -> Vec<String> {
+    match serde_json::to_value(TerrainConfig::default()) {
+        Ok(Value::Object(map)) => map.into_iter().map(|(key, _)| key).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(TerrainConfig::new(1).validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_field_specific_errors() {
+        let check = |edit: fn(&mut TerrainConfig)| {
+            let mut config = TerrainConfig::new(1);
+            edit(&mut config);
+            config.validate().unwrap_err()
+        };
+        assert_eq!(check(|c| c.chunk_size = 1), ConfigError::ChunkSize(1));
+        assert_eq!(check(|c| c.chunk_size = 0), ConfigError::ChunkSize(0));
+        assert_eq!(check(|c| c.persistence = -0.5), ConfigError::Persistence(-0.5));
+        assert_eq!(check(|c| c.lacunarity = 0.0), ConfigError::Lacunarity(0.0));
+        assert_eq!(check(|c| c.sea_level = 1.5), ConfigError::SeaLevel(1.5));
+        assert_eq!(check(|c| c.cellular_jitter = 1.5), ConfigError::CellularJitter(1.5));
+        assert_eq!(check(|c| c.multifractal_gain = -1.0), ConfigError::MultifractalGain(-1.0));
+        assert_eq!(check(|c| c.scale = f64::NAN).to_string(), "scale must be positive and finite, got NaN");
+        assert!(matches!(
+            check(|c| c.erosion_evaporation = 2.0),
+            ConfigError::Erosion { field: "evaporation", .. }
+        ));
+        assert_eq!(
+            check(|c| c.warp_depth = 0).to_string(),
+            "warp_depth must be in 1..=4, got 0"
+        );
+        assert!(matches!(
+            check(|c| c.ridged_exponent = 0.0),
+            ConfigError::Ridged { field: "exponent", .. }
+        ));
+        assert!(matches!(
+            check(|c| c.hardness_noise = 1.5),
+            ConfigError::Hardness { field: "noise", .. }
+        ));
+        assert_eq!(
+            check(|c| c.crater_max_radius = 4.0).to_string(),
+            "crater_max_radius must be in 8..=256, got 4"
+        );
+        assert_eq!(
+            check(|c| c.terrace_steps = 100).to_string(),
+            "terrace_steps must be in 0..=64, got 100"
+        );
+        assert_eq!(
+            check(|c| c.add_stamp_brush("empty".into(), 2, 2, vec![])),
+            ConfigError::Stamps("brush data must have width * height values")
+        );
+        assert_eq!(
+            check(|c| c.octaves = 0).to_string(),
+            "octaves must be in 1..=16, got 0"
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut config = TerrainConfig::new(7);
+        config.sea_level = 0.42;
+        config.set_noise_type(2);
+        let json = config.to_json_string();
+        assert!(json.contains("\"version\": 1"));
+
+        let loaded = TerrainConfig::from_json_str(&json).unwrap();
+        assert!(loaded.warnings.is_empty());
+        assert_eq!(loaded.config.seed, 7);
+        assert_eq!(loaded.config.sea_level, 0.42);
+        assert_eq!(loaded.config.noise_type(), 2);
+        assert_eq!(loaded.config.config_hash(), config.config_hash());
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let mut config = TerrainConfig::new(99);
+        config.erosion_enabled = true;
+        let text = config.to_toml_string().unwrap();
+        let loaded = TerrainConfig::from_toml_str(&text).unwrap();
+        assert_eq!(loaded.config.config_hash(), config.config_hash());
+    }
+
+    #[test]
+    fn height_spline_round_trips() {
+        let mut config = TerrainConfig::new(5);
+        config
+            .set_height_spline_json(Some(
+                r#"{"input": "erosion", "points": [
+                    {"location": 0.2, "value": 0.6, "derivative": -0.5},
+                    {"location": 0.8, "value": {"input": "height", "points": [
+                        {"location": 0.0, "value": 0.3}, {"location": 1.0, "value": 0.5}
+                    ]}}
+                ]}"#
+                .into(),
+            ))
+            .unwrap();
+        assert_eq!(config.validate(), Ok(()));
+        assert_ne!(config.config_hash(), TerrainConfig::new(5).config_hash());
+
+        let from_json = TerrainConfig::from_json_str(&config.to_json_string()).unwrap();
+        assert_eq!(from_json.config.height_spline, config.height_spline);
+        let from_toml = TerrainConfig::from_toml_str(&config.to_toml_string().unwrap()).unwrap();
+        assert_eq!(from_toml.config.height_spline, config.height_spline);
+        assert_eq!(from_toml.config.config_hash(), config.config_hash());
+    }
+
+    #[test]
+    fn migrates_web_settings_export() {
+        let json = r#"{
+            "seed": 1234, "chunkSize": 64, "seaLevel": 0.3, "noiseType": 3,
+            "erosionErosionRate": 0.5, "viewDistance": 6, "wireframe": false,
+            "sparkles": true
+        }"#;
+        let loaded = TerrainConfig::from_json_str(json).unwrap();
+        assert_eq!(loaded.config.seed, 1234);
+        assert_eq!(loaded.config.chunk_size, 64);
+        assert_eq!(loaded.config.sea_level, 0.3);
+        assert_eq!(loaded.config.noise_type(), 3);
+        assert_eq!(loaded.config.erosion_erosion_rate, 0.5);
+        // Unspecified fields fall back to defaults
+        assert_eq!(loaded.config.octaves, 6);
+        assert_eq!(loaded.warnings, vec!["Ignoring unknown config field `sparkles`"]);
+    }
+
+    #[test]
+    fn rejects_future_versions_and_bad_types() {
+        assert!(matches!(
+            TerrainConfig::from_json_str(r#"{"version": 99}"#),
+            Err(ConfigFormatError::UnsupportedVersion(99))
+        ));
+        assert!(TerrainConfig::from_json_str(r#"{"version": 1, "octaves": "six"}"#).is_err());
+        for version in [r#""2""#, "1.5", "-1"] {
+            let err = TerrainConfig::from_json_str(&format!(r#"{{"version": {}}}"#, version)).err();
+            assert!(
+                matches!(&err, Some(ConfigFormatError::BadVersion(v)) if v == version),
+                "{:?}",
+                err
+            );
+        }
+        assert!(matches!(
+            TerrainConfig::from_json_str("[1, 2]"),
+            Err(ConfigFormatError::NotATable)
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+//! Per-cell resistance to erosion, shared by the erosion models.
+//!
+//! Hardness is in `0..=1`: 0 erodes at the configured rate, 1 not at all.
+//! It combines a noise field fixed in world space with the strata layer
+//! exposed at each cell's current height, so it changes as erosion cuts
+//! down into harder or softer rock.
+
+use noise::{NoiseFn, Simplex};
+
+use crate::strata::Strata;
+use crate::TerrainConfig;
+
+/// World-space hardness noise, `0..=strength`.
+pub struct HardnessNoise {
+    noise: Simplex,
+    frequency: f64,
+    strength: f64,
+}
+
+impl HardnessNoise {
+    /// `frequency` is in cycles per world unit.
+    pub fn new(seed: u64, frequency: f64, strength: f64) -> Self {
+        let s = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            noise: Simplex::new(s.wrapping_add(8000)),
+            frequency,
+            strength,
+        }
+    }
+
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        let (x, z) = (world_x * self.frequency, world_z * self.frequency);
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..3 {
+            value += self.noise.get([x * frequency, z * frequency]) * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        ((value / max_amplitude + 1.0) * 0.5).clamp(0.0, 1.0) * self.strength
+    }
+}
+
+/// Hardness over one heightmap.
+#[derive(Clone, Default)]
+pub struct Hardness {
+    /// Noise hardness per cell, row-major like the heightmap.
+    base: Option<Vec<f32>>,
+    strata: Option<Strata>,
+}
+
+impl Hardness {
+    /// Hardness for a `width` x `height` heightmap whose first cell sits at
+    /// world `(origin_x, origin_z)`.
+    pub fn new(config: &TerrainConfig, origin_x: f64, origin_z: f64, width: u32, height: u32) -> Self {
+        let base = (config.hardness_noise > 0.0).then(|| {
+            let noise = HardnessNoise::new(config.seed, config.hardness_frequency, config.hardness_noise);
+            let (w, h) = (width as usize, height as usize);
+            let mut base = vec![0.0f32; w * h];
+            for (i, v) in base.iter_mut().enumerate() {
+                *v = noise.sample(origin_x + (i % w) as f64, origin_z + (i / w) as f64) as f32;
+            }
+            base
+        });
+        Self {
+            base,
+            strata: config.strata.clone(),
+        }
+    }
+
+    /// Hardness of cell `idx` at its current `height`.
+    pub fn at(&self, idx: usize, height: f32) -> f32 {
+        let base = self.base.as_ref().map_or(0.0, |base| base[idx]);
+        match &self.strata {
+            Some(strata) => {
+                let layer = strata.hardness_at(height as f64) as f32;
+                1.0 - (1.0 - base) * (1.0 - layer)
+            }
+            None => base,
+        }
+    }
+
+    /// Hardness of every cell of `heightmap`.
+    pub fn map(&self, heightmap: &[f32]) -> Vec<f32> {
+        heightmap.iter().enumerate().map(|(i, &h)| self.at(i, h)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strata::RockLayer;
+
+    #[test]
+    fn combines_noise_and_strata() {
+        let mut config = TerrainConfig::new(3);
+        assert!(Hardness::new(&config, 0.0, 0.0, 8, 8).map(&[0.5; 64]).iter().all(|&h| h == 0.0));
+
+        config.hardness_noise = 0.6;
+        let noise = Hardness::new(&config, 100.0, -40.0, 8, 8).map(&[0.5; 64]);
+        assert!(noise.iter().all(|&h| (0.0..=0.6).contains(&h)));
+        assert!(noise.iter().any(|&h| h != noise[0]));
+        // Fixed in world space: a shifted window sees the same values
+        let shifted = Hardness::new(&config, 101.0, -40.0, 8, 8).map(&[0.5; 64]);
+        assert_eq!(shifted[0], noise[1]);
+
+        config.strata = Some(Strata {
+            layers: vec![RockLayer { rock: 1, thickness: 1.0, hardness: 1.0 }],
+        });
+        let hard = Hardness::new(&config, 100.0, -40.0, 8, 8).map(&[0.5; 64]);
+        assert!(hard.iter().all(|&h| h == 1.0));
+    }
+}
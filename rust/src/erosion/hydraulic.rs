@@ -1,3 +1,4 @@
+use super::hardness::Hardness;
 use crate::TerrainConfig;
 
 struct Droplet {
@@ -15,23 +16,215 @@ const GRAVITY: f64 = 4.0;
 const MIN_SLOPE: f64 = 0.01;
 const EROSION_RADIUS: i32 = 3;
 
+/// Erosion parameters copied out of `TerrainConfig`.
+#[derive(Clone, Copy)]
+struct Params {
+    inertia: f64,
+    capacity: f64,
+    deposition: f64,
+    erosion_rate: f64,
+    evaporation: f64,
+}
+
+impl Params {
+    fn new(config: &TerrainConfig) -> Self {
+        Self {
+            inertia: config.erosion_inertia,
+            capacity: config.erosion_capacity,
+            deposition: config.erosion_deposition,
+            erosion_rate: config.erosion_erosion_rate,
+            evaporation: config.erosion_evaporation,
+        }
+    }
+}
+
+// Simple LCG random for reproducibility without pulling in rand
+#[derive(Clone)]
+struct Lcg(u64);
+
+impl Lcg {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(6364136223846793005).wrapping_add(1))
+    }
+
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as f64) / (u32::MAX as f64)
+    }
+}
+
+/// Rows a droplet can read or change on either side of the row it starts
+/// in: a full lifetime of unit moves plus the erosion radius and the
+/// bilinear neighbour.
+const DROPLET_REACH: usize = MAX_LIFETIME as usize + EROSION_RADIUS as usize + 2;
+/// Rows per band. Droplets starting in bands two apart never touch the same
+/// cells, so all even (or all odd) bands can run at once.
+const BAND_ROWS: usize = 2 * DROPLET_REACH;
+/// Droplets planned together; each round runs its even bands, then its odd.
+const ROUND: u32 = 4096;
+
+/// Erode a heightmap with its first cell at the world origin.
+///
+/// With the `parallel` feature the droplets of a round run band by band on
+/// the rayon pool. Each droplet has its own random stream and bands that run
+/// together can't interact, so the result is the same on every build.
 pub fn erode(heightmap: &mut [f32], width: u32, height: u32, config: &TerrainConfig) {
-    let w = width as usize;
-    let h = height as usize;
-    if w < 3 || h < 3 {
-        return;
+    let hardness = Hardness::new(config, 0.0, 0.0, width, height);
+    erode_with_hardness(heightmap, width, height, config, hardness);
+}
+
+pub fn erode_with_hardness(
+    heightmap: &mut [f32],
+    width: u32,
+    height: u32,
+    config: &TerrainConfig,
+    hardness: Hardness,
+) {
+    let mut sim = Simulation::new(width, height, config, hardness);
+    sim.run(heightmap, config.erosion_iterations);
+}
+
+/// Seed of droplet `index`'s random stream.
+fn droplet_seed(seed: u64, index: u32) -> u64 {
+    // splitmix64 finalizer, so neighbouring indices get unrelated streams
+    let mut z = seed ^ (index as u64 + 1).wrapping_mul(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// A droplet planned for the current round.
+#[derive(Clone)]
+struct Planned {
+    band: usize,
+    x: f64,
+    z: f64,
+    /// Its stream, past the start position draws.
+    rng: Lcg,
+}
+
+/// Droplet simulation state carried between batches, so a run split into
+/// several `run` calls matches a single `erode` exactly.
+#[derive(Clone)]
+pub(crate) struct Simulation {
+    w: usize,
+    h: usize,
+    params: Params,
+    hardness: Hardness,
+    seed: u64,
+    total: u32,
+    /// Droplets planned so far, over all rounds.
+    planned: u32,
+    /// The current round in run order: even bands, then odd, each in band
+    /// order.
+    round: Vec<Planned>,
+    next: usize,
+}
+
+impl Simulation {
+    pub(crate) fn new(width: u32, height: u32, config: &TerrainConfig, hardness: Hardness) -> Self {
+        Self {
+            w: width as usize,
+            h: height as usize,
+            params: Params::new(config),
+            hardness,
+            seed: config.seed,
+            total: config.erosion_iterations,
+            planned: 0,
+            round: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Simulate the next `droplets` droplets, at most up to the configured
+    /// iteration count.
+    pub(crate) fn run(&mut self, heightmap: &mut [f32], droplets: u32) {
+        if self.w < 3 || self.h < 3 {
+            return;
+        }
+        let mut left = droplets as usize;
+        while left > 0 {
+            if self.next == self.round.len() && !self.plan_round() {
+                return;
+            }
+            // Up to the end of the batch or of this parity, whichever is first
+            let parity = self.round[self.next].band % 2;
+            let end = (self.next + left).min(self.round.len());
+            let end = (self.next..end)
+                .find(|&i| self.round[i].band % 2 != parity)
+                .unwrap_or(end);
+            self.run_bands(heightmap, self.next, end);
+            left -= end - self.next;
+            self.next = end;
+        }
+    }
+
+    fn plan_round(&mut self) -> bool {
+        if self.planned == self.total {
+            return false;
+        }
+        let count = ROUND.min(self.total - self.planned);
+        let (w, h) = (self.w as f64, self.h as f64);
+        self.round = (self.planned..self.planned + count)
+            .map(|index| {
+                let mut rng = Lcg::new(droplet_seed(self.seed, index));
+                let x = rng.next() * (w - 2.0) + 1.0;
+                let z = rng.next() * (h - 2.0) + 1.0;
+                Planned { band: z as usize / BAND_ROWS, x, z, rng }
+            })
+            .collect();
+        // Stable, so each band keeps droplet order
+        self.round.sort_by_key(|d| (d.band % 2, d.band));
+        self.planned += count;
+        self.next = 0;
+        true
     }
 
-    // Simple LCG random for reproducibility without pulling in rand
-    let mut rng_state: u64 = config.seed.wrapping_mul(6364136223846793005).wrapping_add(1);
-    let mut next_random = || -> f64 {
-        rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
-        ((rng_state >> 33) as f64) / (u32::MAX as f64)
-    };
+    /// Run `round[start..end]`, which all share a band parity. Each band
+    /// gets its own rows of the heightmap, with `DROPLET_REACH` rows of
+    /// margin, so they can run in parallel.
+    fn run_bands(&self, heightmap: &mut [f32], start: usize, end: usize) {
+        let w = self.w;
+        let mut bands: Vec<(usize, &mut [f32], &[Planned])> = Vec::new();
+        let mut rest = heightmap;
+        let mut rest_row = 0;
+        let mut i = start;
+        while i < end {
+            let band = self.round[i].band;
+            let j = (i..end).find(|&j| self.round[j].band != band).unwrap_or(end);
+            let row0 = (band * BAND_ROWS).saturating_sub(DROPLET_REACH);
+            let row1 = ((band + 1) * BAND_ROWS + DROPLET_REACH).min(self.h);
+            let (_, tail) = std::mem::take(&mut rest).split_at_mut((row0 - rest_row) * w);
+            let (rows, tail) = tail.split_at_mut((row1 - row0) * w);
+            rest = tail;
+            rest_row = row1;
+            bands.push((row0, rows, &self.round[i..j]));
+            i = j;
+        }
 
-    for _ in 0..config.erosion_iterations {
-        let start_x = next_random() * (w as f64 - 2.0) + 1.0;
-        let start_z = next_random() * (h as f64 - 2.0) + 1.0;
+        let run = |(row0, rows, droplets): (usize, &mut [f32], &[Planned])| {
+            for droplet in droplets {
+                self.droplet(rows, row0, droplet);
+            }
+        };
+        #[cfg(feature = "parallel")]
+        if crate::threads::rayon_enabled() {
+            use rayon::prelude::*;
+            bands.into_par_iter().for_each(run);
+            return;
+        }
+        bands.into_iter().for_each(run);
+    }
+
+    /// Simulate one droplet on `rows`, the heightmap rows from `row0` on.
+    /// The band margins keep it off any edge of `rows` that isn't a
+    /// heightmap edge, so it behaves as on the full map.
+    fn droplet(&self, heightmap: &mut [f32], row0: usize, planned: &Planned) {
+        let w = self.w;
+        let h = heightmap.len() / w;
+        let config = self.params;
+        let mut rng = planned.rng.clone();
+        let (start_x, start_z) = (planned.x, planned.z - row0 as f64);
 
         let mut drop = Droplet {
             x: start_x,
@@ -66,14 +259,14 @@ pub fn erode(heightmap: &mut [f32], width: u32, height: u32, config: &TerrainCon
             let grad_z = (h01 - h00) * (1.0 - fx) + (h11 - h10) * fx;
 
             // Update direction with inertia
-            drop.dir_x = drop.dir_x * config.erosion_inertia - grad_x * (1.0 - config.erosion_inertia);
-            drop.dir_z = drop.dir_z * config.erosion_inertia - grad_z * (1.0 - config.erosion_inertia);
+            drop.dir_x = drop.dir_x * config.inertia - grad_x * (1.0 - config.inertia);
+            drop.dir_z = drop.dir_z * config.inertia - grad_z * (1.0 - config.inertia);
 
             // Normalize direction
             let len = (drop.dir_x * drop.dir_x + drop.dir_z * drop.dir_z).sqrt();
             if len < 1e-10 {
                 // Random direction if flat
-                let angle = next_random() * std::f64::consts::TAU;
+                let angle = rng.next() * std::f64::consts::TAU;
                 drop.dir_x = angle.cos();
                 drop.dir_z = angle.sin();
             } else {
@@ -108,14 +301,14 @@ pub fn erode(heightmap: &mut [f32], width: u32, height: u32, config: &TerrainCon
             let height_diff = new_height - old_height;
 
             // Calculate sediment capacity
-            let capacity = (-height_diff).max(MIN_SLOPE) * drop.speed * drop.water * config.erosion_capacity;
+            let capacity = (-height_diff).max(MIN_SLOPE) * drop.speed * drop.water * config.capacity;
 
             if drop.sediment > capacity || height_diff > 0.0 {
                 // Deposit sediment
                 let deposit_amount = if height_diff > 0.0 {
                     height_diff.min(drop.sediment)
                 } else {
-                    (drop.sediment - capacity) * config.erosion_deposition
+                    (drop.sediment - capacity) * config.deposition
                 };
 
                 drop.sediment -= deposit_amount;
@@ -127,25 +320,19 @@ pub fn erode(heightmap: &mut [f32], width: u32, height: u32, config: &TerrainCon
                 heightmap[idx + w + 1] += da * fx as f32 * fz as f32;
             } else {
                 // Erode terrain
-                let erode_amount = ((capacity - drop.sediment) * config.erosion_erosion_rate).min(-height_diff);
+                let erode_amount = ((capacity - drop.sediment) * config.erosion_rate).min(-height_diff);
 
                 // Erode in a radius for smoother results
-                let total_weight = apply_erosion_radius(
-                    heightmap,
-                    w,
-                    h,
-                    ix as i32,
-                    iz as i32,
-                    erode_amount as f32,
-                );
+                let (total_weight, resisted) =
+                    self.apply_erosion_radius(heightmap, row0, ix as i32, iz as i32, erode_amount as f32);
                 if total_weight > 0.0 {
-                    drop.sediment += erode_amount;
+                    drop.sediment += erode_amount * (1.0 - resisted);
                 }
             }
 
             // Update speed and water
             drop.speed = ((drop.speed * drop.speed + height_diff * GRAVITY).max(0.0)).sqrt();
-            drop.water *= 1.0 - config.erosion_evaporation;
+            drop.water *= 1.0 - config.evaporation;
             drop.x = new_x;
             drop.z = new_z;
 
@@ -154,44 +341,45 @@ pub fn erode(heightmap: &mut [f32], width: u32, height: u32, config: &TerrainCon
             }
         }
     }
-}
 
-fn apply_erosion_radius(
-    heightmap: &mut [f32],
-    w: usize,
-    h: usize,
-    cx: i32,
-    cz: i32,
-    amount: f32,
-) -> f32 {
-    let mut total_weight: f32 = 0.0;
-    let mut weights: Vec<(usize, f32)> = Vec::with_capacity((EROSION_RADIUS * 2 + 1).pow(2) as usize);
-
-    for dz in -EROSION_RADIUS..=EROSION_RADIUS {
-        for dx in -EROSION_RADIUS..=EROSION_RADIUS {
-            let px = cx + dx;
-            let pz = cz + dz;
-            if px < 0 || px >= w as i32 || pz < 0 || pz >= h as i32 {
-                continue;
-            }
-            let dist = ((dx * dx + dz * dz) as f32).sqrt();
-            if dist > EROSION_RADIUS as f32 {
-                continue;
+    /// Erode around (`cx`, `cz`) in `heightmap`, the rows from `row0` on.
+    /// Returns the total weight and the share of `amount` that hard cells
+    /// held back.
+    fn apply_erosion_radius(&self, heightmap: &mut [f32], row0: usize, cx: i32, cz: i32, amount: f32) -> (f32, f64) {
+        let (w, h) = (self.w, heightmap.len() / self.w);
+        let mut total_weight: f32 = 0.0;
+        let mut weights: Vec<(usize, f32)> = Vec::with_capacity((EROSION_RADIUS * 2 + 1).pow(2) as usize);
+
+        for dz in -EROSION_RADIUS..=EROSION_RADIUS {
+            for dx in -EROSION_RADIUS..=EROSION_RADIUS {
+                let px = cx + dx;
+                let pz = cz + dz;
+                if px < 0 || px >= w as i32 || pz < 0 || pz >= h as i32 {
+                    continue;
+                }
+                let dist = ((dx * dx + dz * dz) as f32).sqrt();
+                if dist > EROSION_RADIUS as f32 {
+                    continue;
+                }
+                let weight = (1.0 - dist / EROSION_RADIUS as f32).max(0.0);
+                total_weight += weight;
+                weights.push((pz as usize * w + px as usize, weight));
             }
-            let weight = (1.0 - dist / EROSION_RADIUS as f32).max(0.0);
-            total_weight += weight;
-            weights.push((pz as usize * w + px as usize, weight));
         }
-    }
 
-    if total_weight > 0.0 {
-        for (idx, weight) in weights {
-            let erode = amount * (weight / total_weight);
-            heightmap[idx] = (heightmap[idx] - erode).max(0.0);
+        let mut resisted = 0.0;
+        if total_weight > 0.0 {
+            for (idx, weight) in weights {
+                let share = weight / total_weight;
+                let hard = self.hardness.at(idx + row0 * w, heightmap[idx]);
+                let erode = amount * (share * (1.0 - hard));
+                heightmap[idx] = (heightmap[idx] - erode).max(0.0);
+                resisted += (share * hard) as f64;
+            }
         }
-    }
 
-    total_weight
+        (total_weight, resisted)
+    }
 }
 
 #[cfg(test)]
@@ -230,6 +418,56 @@ mod tests {
         assert!(changed, "Erosion should modify the heightmap");
     }
 
+    #[test]
+    fn hard_layers_resist_erosion() {
+        use crate::strata::{RockLayer, Strata};
+
+        let slope: Vec<f32> = (0..32 * 32).map(|i| (i / 32) as f32 / 32.0).collect();
+        let mut config = TerrainConfig::new(42);
+        config.erosion_iterations = 1000;
+        let layer = |rock, hardness| RockLayer { rock, thickness: 0.25, hardness };
+
+        // Soft rock below 0.5, unerodable above
+        config.strata = Some(Strata {
+            layers: vec![layer(1, 0.0), layer(1, 0.0), layer(2, 1.0), layer(2, 1.0)],
+        });
+        let mut heightmap = slope.clone();
+        erode(&mut heightmap, 32, 32, &config);
+        assert!(heightmap.iter().zip(&slope).any(|(a, b)| a != b));
+
+        config.strata = Some(Strata { layers: vec![layer(1, 1.0)] });
+        let mut heightmap = slope.clone();
+        erode(&mut heightmap, 32, 32, &config);
+        assert_eq!(heightmap, slope);
+    }
+
+    #[test]
+    fn bands_match_the_full_map() {
+        // Tall enough for several bands; more droplets than one round
+        let (w, h) = (40, 5 * BAND_ROWS + 17);
+        let terrain: Vec<f32> = (0..w * h)
+            .map(|i| (i / w) as f32 / h as f32 + ((i * 7919) % 13) as f32 * 0.004)
+            .collect();
+        let mut config = TerrainConfig::new(9);
+        config.erosion_iterations = ROUND + 1500;
+        config.hardness_noise = 0.5;
+        let mut banded = terrain.clone();
+        erode(&mut banded, w as u32, h as u32, &config);
+
+        // Every droplet on the whole map, one after another, in round order
+        let hardness = Hardness::new(&config, 0.0, 0.0, w as u32, h as u32);
+        let mut sim = Simulation::new(w as u32, h as u32, &config, hardness);
+        let mut full = terrain.clone();
+        while sim.plan_round() {
+            assert!(sim.round.windows(2).all(|p| (p[0].band % 2, p[0].band) <= (p[1].band % 2, p[1].band)));
+            for droplet in &sim.round {
+                sim.droplet(&mut full, 0, droplet);
+            }
+        }
+        assert_ne!(full, terrain);
+        assert_eq!(banded, full);
+    }
+
     #[test]
     fn erosion_preserves_bounds() {
         let mut config = TerrainConfig::new(42);
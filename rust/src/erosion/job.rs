@@ -0,0 +1,225 @@
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use super::hardness::Hardness;
+use super::hydraulic::Simulation;
+use crate::config::ConfigError;
+use crate::TerrainConfig;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ErosionError {
+    #[error("heightmap has {len} cells, expected {width}x{height}")]
+    SizeMismatch { width: u32, height: u32, len: usize },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl From<ErosionError> for JsValue {
+    fn from(err: ErosionError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+/// Erosion run in caller-sized batches so a worker can report progress,
+/// post snapshots and drop stale runs. Running every droplet gives the same
+/// heightmap as `erode_heightmap`, however the steps are split.
+#[wasm_bindgen]
+pub struct ErosionJob {
+    heightmap: Vec<f32>,
+    width: u32,
+    height: u32,
+    sim: Simulation,
+    total: u32,
+    done: u32,
+    cancelled: bool,
+}
+
+#[wasm_bindgen]
+impl ErosionJob {
+    /// `origin_x`/`origin_z` is the world position of the first cell, as
+    /// for `erode_heightmap`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        heightmap: Vec<f32>,
+        width: u32,
+        height: u32,
+        origin_x: f64,
+        origin_z: f64,
+        config: &TerrainConfig,
+    ) -> Result<ErosionJob, ErosionError> {
+        config.validate()?;
+        if heightmap.len() != width as usize * height as usize {
+            return Err(ErosionError::SizeMismatch {
+                width,
+                height,
+                len: heightmap.len(),
+            });
+        }
+        Ok(Self {
+            heightmap,
+            width,
+            height,
+            sim: Simulation::new(width, height, config, Hardness::new(config, origin_x, origin_z, width, height)),
+            total: config.erosion_iterations,
+            done: 0,
+            cancelled: false,
+        })
+    }
+
+    /// Simulate up to `droplets` more droplets and return the progress in
+    /// `0..=1`. Does nothing once finished or cancelled.
+    pub fn step(&mut self, droplets: u32) -> f64 {
+        if !self.cancelled {
+            let n = droplets.min(self.total - self.done);
+            self.sim.run(&mut self.heightmap, n);
+            self.done += n;
+        }
+        self.progress()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn iterations_done(&self) -> u32 {
+        self.done
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total_iterations(&self) -> u32 {
+        self.total
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done == self.total
+    }
+
+    /// Stop the run; later `step` calls are no-ops. The heightmap keeps the
+    /// droplets simulated so far.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Copy of the heightmap as eroded so far.
+    pub fn snapshot(&self) -> Vec<f32> {
+        self.heightmap.clone()
+    }
+
+    /// View of the heightmap in WASM linear memory, without copying.
+    ///
+    /// Invalid after `free()`, `into_heightmap()`, the next `step()`, or any
+    /// other WASM call that may grow memory. `.slice()` it before calling
+    /// into WASM again.
+    pub fn heightmap_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the JS caller keeps the contract documented above
+        unsafe { js_sys::Float32Array::view(&self.heightmap) }
+    }
+
+    /// Take the heightmap, consuming the job.
+    pub fn into_heightmap(self) -> Vec<f32> {
+        self.heightmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::erosion::erode;
+
+    fn slope() -> Vec<f32> {
+        (0..32 * 32).map(|i| (i / 32) as f32 / 32.0 + (i % 7) as f32 * 0.01).collect()
+    }
+
+    #[test]
+    fn batched_steps_match_erode() {
+        let mut config = TerrainConfig::new(42);
+        config.erosion_iterations = 1000;
+        let mut expected = slope();
+        erode(&mut expected, 32, 32, &config);
+
+        let mut job = ErosionJob::new(slope(), 32, 32, 0.0, 0.0, &config).unwrap();
+        assert_eq!(job.step(1), 0.001);
+        assert_eq!(job.step(333), 0.334);
+        while !job.is_finished() {
+            job.step(97);
+        }
+        assert_eq!(job.step(10), 1.0);
+        assert_eq!(job.into_heightmap(), expected);
+    }
+
+    #[test]
+    fn cancel_stops_progress() {
+        let mut config = TerrainConfig::new(7);
+        config.erosion_iterations = 500;
+        let mut job = ErosionJob::new(slope(), 32, 32, 0.0, 0.0, &config).unwrap();
+        job.step(100);
+        let snapshot = job.snapshot();
+        assert_ne!(snapshot, slope());
+
+        job.cancel();
+        assert_eq!(job.step(400), 0.2);
+        assert!(job.is_cancelled() && !job.is_finished());
+        assert_eq!(job.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn chunk_jobs_match_generated_chunks() {
+        let mut config = TerrainConfig::new(11);
+        config.chunk_size = 33;
+        config.erosion_iterations = 800;
+        config.hardness_noise = 0.8;
+        config.hardness_frequency = 0.05;
+        let (chunk_x, chunk_z) = (3, -2);
+
+        config.erosion_enabled = false;
+        let raw = crate::chunk::generate_chunk(&config, chunk_x, chunk_z).unwrap();
+        config.erosion_enabled = true;
+        let eroded = crate::chunk::generate_chunk(&config, chunk_x, chunk_z).unwrap();
+
+        let (x, z) = (chunk_x as f64 * 32.0, chunk_z as f64 * 32.0);
+        let mut job = ErosionJob::new(raw.heightmap.clone(), 33, 33, x, z, &config).unwrap();
+        job.step(u32::MAX);
+        assert_eq!(job.into_heightmap(), eroded.heightmap);
+
+        let mut heightmap = raw.heightmap.clone();
+        crate::chunk::erode_heightmap(&mut heightmap, 33, 33, x, z, &config).unwrap();
+        assert_eq!(heightmap, eroded.heightmap);
+        // The hardness field is in world space
+        let mut at_origin = raw.heightmap;
+        crate::chunk::erode_heightmap(&mut at_origin, 33, 33, 0.0, 0.0, &config).unwrap();
+        assert_ne!(at_origin, eroded.heightmap);
+    }
+
+    #[test]
+    fn rejects_mismatched_sizes() {
+        let config = TerrainConfig::new(1);
+        assert_eq!(
+            ErosionJob::new(vec![0.0; 10], 4, 4, 0.0, 0.0, &config).err(),
+            Some(ErosionError::SizeMismatch { width: 4, height: 4, len: 10 })
+        );
+        assert_eq!(
+            crate::chunk::erode_heightmap(&mut [0.0; 10], 4, 4, 0.0, 0.0, &config),
+            Err(ErosionError::SizeMismatch { width: 4, height: 4, len: 10 })
+        );
+    }
+}
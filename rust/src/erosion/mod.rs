@@ -1,3 +1,7 @@
+pub mod hardness;
 pub mod hydraulic;
+pub mod job;
 
-pub use hydraulic::erode;
+pub use hardness::{Hardness, HardnessNoise};
+pub use hydraulic::{erode, erode_with_hardness};
+pub use job::{ErosionError, ErosionJob};
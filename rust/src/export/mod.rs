@@ -0,0 +1,3 @@
+pub mod stl;
+
+pub use stl::{build_solid, export_stl, SolidMesh, StlError, StlOptions};
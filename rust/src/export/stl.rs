@@ -0,0 +1,373 @@
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::region::{generate_region, RegionError};
+use crate::TerrainConfig;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum StlError {
+    #[error("size_mm must be positive and finite, got {0}")]
+    Size(f64),
+    #[error("vertical_exaggeration must be positive and finite, got {0}")]
+    VerticalExaggeration(f64),
+    #[error("base_thickness_mm must be non-negative and finite, got {0}")]
+    BaseThickness(f64),
+    #[error("chunk range {x0},{z0}..={x1},{z1} is outside the world")]
+    OutOfRange { x0: i32, z0: i32, x1: i32, z1: i32 },
+    #[error(transparent)]
+    Region(#[from] RegionError),
+}
+
+impl From<StlError> for JsValue {
+    fn from(err: StlError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct StlOptions {
+    pub base_thickness_mm: f64,
+    pub vertical_exaggeration: f64,
+    pub size_mm: f64, // Physical length of the X extent
+    pub binary: bool,
+}
+
+#[wasm_bindgen]
+impl StlOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            base_thickness_mm: 3.0,
+            vertical_exaggeration: 1.0,
+            size_mm: 100.0,
+            binary: true,
+        }
+    }
+}
+
+impl StlOptions {
+    pub fn validate(&self) -> Result<(), StlError> {
+        if !(self.size_mm.is_finite() && self.size_mm > 0.0) {
+            return Err(StlError::Size(self.size_mm));
+        }
+        if !(self.vertical_exaggeration.is_finite() && self.vertical_exaggeration > 0.0) {
+            return Err(StlError::VerticalExaggeration(self.vertical_exaggeration));
+        }
+        if !(self.base_thickness_mm.is_finite() && self.base_thickness_mm >= 0.0) {
+            return Err(StlError::BaseThickness(self.base_thickness_mm));
+        }
+        Ok(())
+    }
+}
+
+impl Default for StlOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Indexed triangle mesh of a closed terrain solid, in millimetres (Z up).
+pub struct SolidMesh {
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Close a `width` x `height` heightmap into a watertight solid: top surface,
+/// four side walls down to z = 0 and a base plate fanned from its centre.
+/// `options` must be valid (see `StlOptions::validate`).
+pub fn build_solid(
+    heightmap: &[f32],
+    width: usize,
+    height: usize,
+    height_multiplier: f64,
+    options: &StlOptions,
+) -> SolidMesh {
+    assert!(width >= 2 && height >= 2, "heightmap must be at least 2x2");
+    assert_eq!(heightmap.len(), width * height);
+
+    let mm_per_unit = options.size_mm / (width - 1) as f64;
+    let z_scale = height_multiplier * options.vertical_exaggeration * mm_per_unit;
+    let base = options.base_thickness_mm;
+
+    let mut vertices = Vec::with_capacity(width * height + 2 * (width + height) + 1);
+    let mut triangles = Vec::new();
+
+    // Top surface: vertex index = z * width + x
+    for z in 0..height {
+        for x in 0..width {
+            let top = base + heightmap[z * width + x].max(0.0) as f64 * z_scale;
+            vertices.push([
+                (x as f64 * mm_per_unit) as f32,
+                (z as f64 * mm_per_unit) as f32,
+                top as f32,
+            ]);
+        }
+    }
+    for z in 0..height - 1 {
+        for x in 0..width - 1 {
+            let i00 = (z * width + x) as u32;
+            let i10 = i00 + 1;
+            let i01 = i00 + width as u32;
+            let i11 = i01 + 1;
+            triangles.push([i00, i10, i11]);
+            triangles.push([i00, i11, i01]);
+        }
+    }
+
+    // Boundary loop, counter-clockwise seen from above
+    let mut ring: Vec<u32> = Vec::with_capacity(2 * (width + height) - 4);
+    for x in 0..width - 1 {
+        ring.push(x as u32);
+    }
+    for z in 0..height - 1 {
+        ring.push((z * width + width - 1) as u32);
+    }
+    for x in (1..width).rev() {
+        ring.push(((height - 1) * width + x) as u32);
+    }
+    for z in (1..height).rev() {
+        ring.push((z * width) as u32);
+    }
+
+    // Bottom copy of every boundary vertex
+    let bottom_start = vertices.len() as u32;
+    for &top in &ring {
+        let [x, y, _] = vertices[top as usize];
+        vertices.push([x, y, 0.0]);
+    }
+
+    let n = ring.len();
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (t0, t1) = (ring[i], ring[j]);
+        let (b0, b1) = (bottom_start + i as u32, bottom_start + j as u32);
+        // Walls face outward: the loop runs CCW from above, so outside is on the right
+        triangles.push([t0, b0, b1]);
+        triangles.push([t0, b1, t1]);
+    }
+
+    // Base plate: fan around a centre vertex, facing down
+    let centre = vertices.len() as u32;
+    vertices.push([
+        (options.size_mm * 0.5) as f32,
+        ((height - 1) as f64 * mm_per_unit * 0.5) as f32,
+        0.0,
+    ]);
+    for i in 0..n {
+        let j = (i + 1) % n;
+        triangles.push([centre, bottom_start + j as u32, bottom_start + i as u32]);
+    }
+
+    SolidMesh { vertices, triangles }
+}
+
+fn facet_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len < 1e-12 {
+        [0.0, 0.0, 0.0]
+    } else {
+        [n[0] / len, n[1] / len, n[2] / len]
+    }
+}
+
+impl SolidMesh {
+    pub fn to_binary_stl(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(84 + self.triangles.len() * 50);
+        let mut header = [0u8; 80];
+        let name = b"TerraSynth terrain";
+        header[..name.len()].copy_from_slice(name);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+
+        for tri in &self.triangles {
+            let [a, b, c] = tri.map(|i| self.vertices[i as usize]);
+            for v in [facet_normal(a, b, c), a, b, c] {
+                for component in v {
+                    out.extend_from_slice(&component.to_le_bytes());
+                }
+            }
+            out.extend_from_slice(&0u16.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn to_ascii_stl(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::from("solid terrasynth\n");
+        for tri in &self.triangles {
+            let [a, b, c] = tri.map(|i| self.vertices[i as usize]);
+            let n = facet_normal(a, b, c);
+            let _ = writeln!(out, "  facet normal {:e} {:e} {:e}", n[0], n[1], n[2]);
+            out.push_str("    outer loop\n");
+            for v in [a, b, c] {
+                let _ = writeln!(out, "      vertex {:e} {:e} {:e}", v[0], v[1], v[2]);
+            }
+            out.push_str("    endloop\n  endfacet\n");
+        }
+        out.push_str("endsolid terrasynth\n");
+        out
+    }
+}
+
+/// Export a chunk range (inclusive) as a printable STL file.
+#[wasm_bindgen]
+pub fn export_stl(
+    config: &TerrainConfig,
+    chunk_x0: i32,
+    chunk_z0: i32,
+    chunk_x1: i32,
+    chunk_z1: i32,
+    options: &StlOptions,
+) -> Result<Vec<u8>, StlError> {
+    options.validate()?;
+    let edge = config.chunk_size as i64 - 1;
+    let bound = |chunk: i32, extra: i64| i32::try_from((chunk as i64 + extra) * edge + extra).ok();
+    let (Some(x0), Some(z0), Some(x1), Some(z1)) = (
+        bound(chunk_x0, 0),
+        bound(chunk_z0, 0),
+        bound(chunk_x1, 1),
+        bound(chunk_z1, 1),
+    ) else {
+        return Err(StlError::OutOfRange {
+            x0: chunk_x0,
+            z0: chunk_z0,
+            x1: chunk_x1,
+            z1: chunk_z1,
+        });
+    };
+    let region = generate_region(config, x0, z0, x1, z1)?;
+    let mesh = build_solid(
+        region.heightmap(),
+        region.width() as usize,
+        region.height() as usize,
+        config.height_multiplier,
+        options,
+    );
+    Ok(if options.binary {
+        mesh.to_binary_stl()
+    } else {
+        mesh.to_ascii_stl().into_bytes()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn slope(width: usize, height: usize) -> Vec<f32> {
+        (0..width * height)
+            .map(|i| ((i % width) + (i / width)) as f32 / (width + height) as f32)
+            .collect()
+    }
+
+    #[test]
+    fn solid_is_manifold() {
+        let mesh = build_solid(&slope(9, 6), 9, 6, 80.0, &StlOptions::new());
+
+        // Every directed edge must be matched by exactly one reversed edge
+        let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+        for &[a, b, c] in &mesh.triangles {
+            for (u, v) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((u, v)).or_insert(0) += 1;
+            }
+        }
+        for (&(u, v), &count) in &edges {
+            assert_eq!(count, 1, "Edge ({}, {}) used {} times", u, v, count);
+            assert_eq!(
+                edges.get(&(v, u)),
+                Some(&1),
+                "Edge ({}, {}) has no opposite half-edge",
+                u,
+                v
+            );
+        }
+
+        // Euler characteristic of a closed genus-0 surface
+        let v = mesh.vertices.len() as i64;
+        let e = edges.len() as i64 / 2;
+        let f = mesh.triangles.len() as i64;
+        assert_eq!(v - e + f, 2);
+    }
+
+    #[test]
+    fn solid_has_positive_volume() {
+        let options = StlOptions::new();
+        let mesh = build_solid(&slope(5, 5), 5, 5, 80.0, &options);
+        let volume: f64 = mesh
+            .triangles
+            .iter()
+            .map(|tri| {
+                let [a, b, c] = tri.map(|i| mesh.vertices[i as usize].map(|x| x as f64));
+                (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                    / 6.0
+            })
+            .sum();
+        let base_volume = options.size_mm * options.size_mm * options.base_thickness_mm;
+        assert!(volume > base_volume, "Outward normals give positive volume, got {}", volume);
+    }
+
+    #[test]
+    fn binary_and_ascii_agree_on_facet_count() {
+        let mesh = build_solid(&slope(4, 4), 4, 4, 10.0, &StlOptions::new());
+        let binary = mesh.to_binary_stl();
+        let count = u32::from_le_bytes(binary[80..84].try_into().unwrap()) as usize;
+        assert_eq!(count, mesh.triangles.len());
+        assert_eq!(binary.len(), 84 + count * 50);
+
+        let ascii = mesh.to_ascii_stl();
+        assert!(ascii.starts_with("solid terrasynth"));
+        assert_eq!(ascii.matches("endfacet").count(), count);
+    }
+
+    #[test]
+    fn chunk_range_export_is_stitched() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        let stl = export_stl(&config, -1, 0, 0, 0, &StlOptions::new()).unwrap();
+        let count = u32::from_le_bytes(stl[80..84].try_into().unwrap()) as usize;
+        // 33 x 17 top grid, 96-segment boundary walled and fanned
+        assert_eq!(count, 32 * 16 * 2 + 96 * 3);
+        assert_eq!(stl.len(), 84 + count * 50);
+    }
+
+    #[test]
+    fn rejects_bad_options_and_far_chunks() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        let export = |options: &StlOptions| export_stl(&config, 0, 0, 0, 0, options);
+        let bad = [
+            (0.0, 1.0, 3.0),
+            (f64::NAN, 1.0, 3.0),
+            (100.0, -1.0, 3.0),
+            (100.0, f64::INFINITY, 3.0),
+            (100.0, 1.0, -1.0),
+        ];
+        for (size, exaggeration, base) in bad {
+            let options = StlOptions {
+                size_mm: size,
+                vertical_exaggeration: exaggeration,
+                base_thickness_mm: base,
+                binary: true,
+            };
+            assert!(options.validate().is_err());
+            assert!(export(&options).is_err());
+        }
+
+        let far = i32::MAX / 16;
+        assert_eq!(
+            export_stl(&config, far, 0, far, 0, &StlOptions::new()).err(),
+            Some(StlError::OutOfRange { x0: far, z0: 0, x1: far, z1: 0 })
+        );
+    }
+}
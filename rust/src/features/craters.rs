@@ -0,0 +1,220 @@
+//! Impact craters scattered per world region.
+//!
+//! The world is cut into square regions. Each region's crater count is
+//! Poisson distributed and radii follow a truncated power law, all drawn
+//! from a generator seeded by the region's coordinates. A point gathers
+//! craters from every region close enough to reach it, so neighbouring
+//! chunks agree along their borders whatever order they are built in.
+
+use crate::noise::worley::cell_hash;
+
+/// Side of a crater region, in world units.
+pub const REGION_SIZE: f64 = 1024.0;
+pub const MAX_CRATER_RADIUS: f64 = 256.0;
+pub const MAX_CRATER_DENSITY: f64 = 64.0;
+/// Outer edge of the ejecta blanket, in crater radii.
+const EJECTA_EXTENT: f64 = 2.5;
+/// Rim height as a share of depth.
+const RIM_HEIGHT: f64 = 0.3;
+/// Central peak radius, in crater radii.
+const PEAK_RADIUS: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CraterParams {
+    /// Mean craters per region.
+    pub density: f64,
+    /// World units.
+    pub min_radius: f64,
+    pub max_radius: f64,
+    /// Exponent of the cumulative size distribution, `N(>r) ~ r^-exponent`.
+    pub size_exponent: f64,
+    /// Depth of a `max_radius` crater in normalized height; smaller
+    /// craters are proportionally shallower.
+    pub depth: f64,
+    /// Central peak height as a share of depth.
+    pub peak: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crater {
+    pub x: f64,
+    pub z: f64,
+    pub radius: f64,
+    pub depth: f64,
+    pub peak: f64,
+}
+
+impl Crater {
+    /// Farthest distance the crater changes the terrain at.
+    pub fn reach(&self) -> f64 {
+        self.radius * EJECTA_EXTENT
+    }
+
+    /// Height offset at a world position: a bowl up to a raised rim, a
+    /// central peak, and an ejecta blanket thinning outwards.
+    pub fn offset(&self, x: f64, z: f64) -> f64 {
+        let d = ((x - self.x).powi(2) + (z - self.z).powi(2)).sqrt() / self.radius;
+        if d >= EJECTA_EXTENT {
+            return 0.0;
+        }
+        let rim = RIM_HEIGHT * self.depth;
+        if d < 1.0 {
+            let mut h = -self.depth + (self.depth + rim) * d * d;
+            if d < PEAK_RADIUS {
+                let t = 1.0 - d / PEAK_RADIUS;
+                h += self.peak * t * t * (3.0 - 2.0 * t);
+            }
+            h
+        } else {
+            let t = (d - 1.0) / (EJECTA_EXTENT - 1.0);
+            rim / (d * d * d) * (1.0 - t * t * (3.0 - 2.0 * t))
+        }
+    }
+}
+
+/// SplitMix64, seeded per region.
+struct RegionRng(u64);
+
+impl RegionRng {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Knuth's method; fine for the small means allowed here.
+    fn poisson(&mut self, mean: f64) -> u32 {
+        let limit = (-mean).exp();
+        let mut product = self.next();
+        let mut count = 0;
+        while product > limit {
+            product *= self.next();
+            count += 1;
+        }
+        count
+    }
+}
+
+pub struct CraterField {
+    seed: u32,
+    params: CraterParams,
+}
+
+impl CraterField {
+    pub fn new(seed: u64, params: CraterParams) -> Self {
+        Self {
+            seed: ((seed & 0xFFFFFFFF) as u32).wrapping_add(9000),
+            params,
+        }
+    }
+
+    /// The craters centred in region (`rx`, `rz`).
+    pub fn region(&self, rx: i64, rz: i64) -> Vec<Crater> {
+        let p = &self.params;
+        let mut rng = RegionRng(cell_hash(self.seed, rx, rz));
+        let count = rng.poisson(p.density);
+        let (lo, hi) = (p.min_radius.powf(-p.size_exponent), p.max_radius.powf(-p.size_exponent));
+        (0..count)
+            .map(|_| {
+                let x = (rx as f64 + rng.next()) * REGION_SIZE;
+                let z = (rz as f64 + rng.next()) * REGION_SIZE;
+                // Inverse CDF of the power law truncated to min..=max
+                let u = rng.next();
+                let radius = (lo - u * (lo - hi)).powf(-1.0 / p.size_exponent);
+                let radius = radius.clamp(p.min_radius, p.max_radius);
+                let depth = p.depth * radius / p.max_radius;
+                Crater {
+                    x,
+                    z,
+                    radius,
+                    depth,
+                    peak: p.peak * depth,
+                }
+            })
+            .collect()
+    }
+
+    /// Craters reaching into the world box `x0..=x1` x `z0..=z1`, from its
+    /// own and neighbouring regions.
+    pub fn craters_in(&self, x0: f64, z0: f64, x1: f64, z1: f64) -> Vec<Crater> {
+        let reach = self.params.max_radius * EJECTA_EXTENT;
+        let region = |v: f64| (v / REGION_SIZE).floor() as i64;
+        let mut craters = Vec::new();
+        for rz in region(z0 - reach)..=region(z1 + reach) {
+            for rx in region(x0 - reach)..=region(x1 + reach) {
+                craters.extend(self.region(rx, rz).into_iter().filter(|c| {
+                    let r = c.reach();
+                    c.x + r > x0 && c.x - r < x1 && c.z + r > z0 && c.z - r < z1
+                }));
+            }
+        }
+        craters
+    }
+
+    /// `height` with `craters` (from `craters_in`) stamped on.
+    pub fn apply(craters: &[Crater], x: f64, z: f64, height: f64) -> f64 {
+        let offset: f64 = craters.iter().map(|c| c.offset(x, z)).sum();
+        (height + offset).clamp(0.0, 1.0)
+    }
+
+    pub fn sample(&self, x: f64, z: f64, height: f64) -> f64 {
+        Self::apply(&self.craters_in(x, z, x, z), x, z, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> CraterParams {
+        CraterParams {
+            density: 12.0,
+            min_radius: 8.0,
+            max_radius: 200.0,
+            size_exponent: 2.0,
+            depth: 0.2,
+            peak: 0.4,
+        }
+    }
+
+    #[test]
+    fn profile_has_bowl_rim_peak_and_ejecta() {
+        let c = Crater { x: 0.0, z: 0.0, radius: 100.0, depth: 0.2, peak: 0.08 };
+        assert!((c.offset(0.0, 0.0) - (-0.2 + 0.08)).abs() < 1e-12);
+        assert!(c.offset(50.0, 0.0) < 0.0);
+        assert!((c.offset(100.0, 0.0) - 0.06).abs() < 1e-12);
+        assert!((c.offset(100.0 + 1e-9, 0.0) - 0.06).abs() < 1e-9, "ejecta meets the rim");
+        assert!(c.offset(0.0, 150.0) > 0.0 && c.offset(0.0, 150.0) < 0.06);
+        assert_eq!(c.offset(0.0, 250.0), 0.0);
+    }
+
+    #[test]
+    fn regions_are_deterministic_and_power_law_sized() {
+        let field = CraterField::new(5, params());
+        assert_eq!(field.region(3, -7), CraterField::new(5, params()).region(3, -7));
+        assert_ne!(field.region(3, -7), field.region(4, -7));
+
+        let radii: Vec<f64> = (0..200).flat_map(|i| field.region(i, 0)).map(|c| c.radius).collect();
+        // Mean of 12 per region
+        assert!((radii.len() as f64 / 200.0 - 12.0).abs() < 1.0, "{} craters", radii.len());
+        assert!(radii.iter().all(|r| (8.0..=200.0).contains(r)));
+        // With exponent 2, a quarter of the craters are over twice the minimum
+        let large = radii.iter().filter(|&&r| r > 16.0).count() as f64 / radii.len() as f64;
+        assert!((large - 0.25).abs() < 0.05, "{} over 16", large);
+    }
+
+    #[test]
+    fn boxes_see_craters_across_region_borders() {
+        let field = CraterField::new(11, params());
+        // A point just inside a region border sees the same craters whether
+        // queried alone or as part of a wider box
+        for i in 0..50 {
+            let (x, z) = (REGION_SIZE - 0.5 + i as f64 * 0.02, 3.0 * REGION_SIZE + i as f64 * 37.0);
+            let alone = field.sample(x, z, 0.5);
+            let wide = CraterField::apply(&field.craters_in(x - 300.0, z, x + 300.0, z), x, z, 0.5);
+            assert_eq!(alone, wide);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Discrete features stamped onto the shaped height field.
+
+pub mod craters;
+pub mod stamps;
+
+pub use craters::{Crater, CraterField, CraterParams};
+pub use stamps::{BlendMode, Brush, StampField, StampLibrary, StampPlacement};
@@ -0,0 +1,309 @@
+//! Hand-authored heightmap stamps.
+//!
+//! A library holds small float heightmaps (brushes) and placements that put
+//! a brush at a world position with a rotation, a scale, a blend mode and an
+//! edge falloff. Stamps are applied last in the height chain, in placement
+//! order, to every cell they cover.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::hash::Fnv64;
+
+pub const MAX_BRUSH_SIZE: u32 = 1024;
+pub const MAX_STAMPS: usize = 256;
+/// Largest world units per brush texel.
+pub const MAX_STAMP_SCALE: f64 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlendMode {
+    /// Raise the terrain by the brush value.
+    Add,
+    Max,
+    Min,
+    /// Set the terrain to the brush value.
+    Replace,
+}
+
+/// A `width` x `height` row-major heightmap in normalized height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Brush {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StampPlacement {
+    /// Name of the brush to stamp.
+    pub brush: String,
+    /// World position of the brush centre.
+    pub x: f64,
+    pub z: f64,
+    /// Degrees; positive turns the brush's x axis towards +z.
+    #[serde(default)]
+    pub rotation: f64,
+    /// World units per brush texel.
+    pub scale: f64,
+    pub blend: BlendMode,
+    /// Share of the way from the brush edge to its centre over which the
+    /// stamp fades in; 0 gives a hard edge.
+    #[serde(default)]
+    pub falloff: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StampLibrary {
+    pub brushes: Vec<Brush>,
+    pub stamps: Vec<StampPlacement>,
+}
+
+impl Brush {
+    /// Bilinear sample at fractional texel coordinates inside the brush.
+    fn sample(&self, u: f64, v: f64) -> f64 {
+        let w = self.width as usize;
+        let (x, z) = ((u.floor() as usize).min(w - 2), (v.floor() as usize).min(self.height as usize - 2));
+        let (fx, fz) = (u - x as f64, v - z as f64);
+        let i = z * w + x;
+        let at = |i: usize| self.data[i] as f64;
+        let top = at(i) + (at(i + 1) - at(i)) * fx;
+        let bottom = at(i + w) + (at(i + w + 1) - at(i + w)) * fx;
+        top + (bottom - top) * fz
+    }
+}
+
+impl StampLibrary {
+    fn brush(&self, name: &str) -> Option<&Brush> {
+        self.brushes.iter().find(|b| b.name == name)
+    }
+
+    /// Add a brush, replacing any with the same name.
+    pub fn add_brush(&mut self, brush: Brush) {
+        match self.brushes.iter_mut().find(|b| b.name == brush.name) {
+            Some(existing) => *existing = brush,
+            None => self.brushes.push(brush),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for brush in &self.brushes {
+            let size = 2..=MAX_BRUSH_SIZE;
+            if !(size.contains(&brush.width) && size.contains(&brush.height)) {
+                return Err(ConfigError::Stamps("brush sides must be in 2..=1024"));
+            }
+            if brush.data.len() != brush.width as usize * brush.height as usize {
+                return Err(ConfigError::Stamps("brush data must have width * height values"));
+            }
+            if !brush.data.iter().all(|v| v.is_finite()) {
+                return Err(ConfigError::Stamps("brush values must be finite"));
+            }
+        }
+        if self.stamps.len() > MAX_STAMPS {
+            return Err(ConfigError::Stamps("can place at most 256 stamps"));
+        }
+        for stamp in &self.stamps {
+            if self.brush(&stamp.brush).is_none() {
+                return Err(ConfigError::Stamps("place a brush that isn't in the library"));
+            }
+            if !(stamp.x.is_finite() && stamp.z.is_finite() && stamp.rotation.is_finite()) {
+                return Err(ConfigError::Stamps("positions and rotations must be finite"));
+            }
+            if !(stamp.scale > 0.0 && stamp.scale <= MAX_STAMP_SCALE) {
+                return Err(ConfigError::Stamps("scale must be in (0, 64]"));
+            }
+            if !(0.0..=1.0).contains(&stamp.falloff) {
+                return Err(ConfigError::Stamps("falloff must be in 0..=1"));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn hash_into(&self, h: &mut Fnv64) {
+        h.write_u32(self.brushes.len() as u32);
+        for brush in &self.brushes {
+            h.write(brush.name.as_bytes());
+            h.write_u32(brush.width);
+            h.write_u32(brush.height);
+            for v in &brush.data {
+                h.write_u32(v.to_bits());
+            }
+        }
+        h.write_u32(self.stamps.len() as u32);
+        for stamp in &self.stamps {
+            h.write(stamp.brush.as_bytes());
+            h.write_f64(stamp.x);
+            h.write_f64(stamp.z);
+            h.write_f64(stamp.rotation);
+            h.write_f64(stamp.scale);
+            h.write(&[stamp.blend as u8]);
+            h.write_f64(stamp.falloff);
+        }
+    }
+}
+
+/// A placement resolved against its brush, with its world bounds.
+struct PlacedStamp {
+    brush: usize,
+    x: f64,
+    z: f64,
+    cos: f64,
+    sin: f64,
+    inv_scale: f64,
+    blend: BlendMode,
+    falloff: f64,
+    /// Axis-aligned half-size in world units.
+    reach: f64,
+}
+
+/// Stamps ready to apply; built from a validated library.
+pub struct StampField {
+    brushes: Vec<Brush>,
+    stamps: Vec<PlacedStamp>,
+}
+
+impl StampField {
+    pub fn new(library: &StampLibrary) -> Self {
+        let stamps = library
+            .stamps
+            .iter()
+            .filter_map(|stamp| {
+                let brush = library.brushes.iter().position(|b| b.name == stamp.brush)?;
+                let b = &library.brushes[brush];
+                let (sin, cos) = stamp.rotation.to_radians().sin_cos();
+                let half_diagonal = ((b.width - 1) as f64).hypot((b.height - 1) as f64) * 0.5;
+                Some(PlacedStamp {
+                    brush,
+                    x: stamp.x,
+                    z: stamp.z,
+                    cos,
+                    sin,
+                    inv_scale: 1.0 / stamp.scale,
+                    blend: stamp.blend,
+                    falloff: stamp.falloff,
+                    reach: half_diagonal * stamp.scale,
+                })
+            })
+            .collect();
+        Self {
+            brushes: library.brushes.clone(),
+            stamps,
+        }
+    }
+
+    /// Indices of the stamps that may cover the world box `x0..=x1` x `z0..=z1`.
+    pub fn stamps_in(&self, x0: f64, z0: f64, x1: f64, z1: f64) -> Vec<usize> {
+        (0..self.stamps.len())
+            .filter(|&i| {
+                let s = &self.stamps[i];
+                s.x + s.reach >= x0 && s.x - s.reach <= x1 && s.z + s.reach >= z0 && s.z - s.reach <= z1
+            })
+            .collect()
+    }
+
+    /// `height` with the stamps `indices` (from `stamps_in`) applied in order.
+    pub fn apply(&self, indices: &[usize], x: f64, z: f64, mut height: f64) -> f64 {
+        for &i in indices {
+            let stamp = &self.stamps[i];
+            let brush = &self.brushes[stamp.brush];
+            let (half_w, half_h) = ((brush.width - 1) as f64 * 0.5, (brush.height - 1) as f64 * 0.5);
+            // World offset rotated back into brush space
+            let (dx, dz) = (x - stamp.x, z - stamp.z);
+            let u = (dx * stamp.cos + dz * stamp.sin) * stamp.inv_scale + half_w;
+            let v = (-dx * stamp.sin + dz * stamp.cos) * stamp.inv_scale + half_h;
+            if !(0.0..=2.0 * half_w).contains(&u) || !(0.0..=2.0 * half_h).contains(&v) {
+                continue;
+            }
+
+            let edge = (u.min(2.0 * half_w - u) / half_w).min(v.min(2.0 * half_h - v) / half_h);
+            let weight = if stamp.falloff > 0.0 {
+                let t = (edge / stamp.falloff).min(1.0);
+                t * t * (3.0 - 2.0 * t)
+            } else {
+                1.0
+            };
+            let value = brush.sample(u, v);
+            let target = match stamp.blend {
+                BlendMode::Add => height + value,
+                BlendMode::Max => height.max(value),
+                BlendMode::Min => height.min(value),
+                BlendMode::Replace => value,
+            };
+            height += (target - height) * weight;
+        }
+        height.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(blend: BlendMode, rotation: f64, falloff: f64) -> StampLibrary {
+        // A 3x2 ramp: 0.0, 0.5, 1.0 along u, both rows alike
+        StampLibrary {
+            brushes: vec![Brush {
+                name: "ramp".into(),
+                width: 3,
+                height: 2,
+                data: vec![0.0, 0.5, 1.0, 0.0, 0.5, 1.0],
+            }],
+            stamps: vec![StampPlacement {
+                brush: "ramp".into(),
+                x: 100.0,
+                z: 50.0,
+                rotation,
+                scale: 10.0,
+                blend,
+                falloff,
+            }],
+        }
+    }
+
+    fn stamped(library: &StampLibrary, x: f64, z: f64, height: f64) -> f64 {
+        let field = StampField::new(library);
+        field.apply(&field.stamps_in(x, z, x, z), x, z, height)
+    }
+
+    #[test]
+    fn blend_modes() {
+        let at = |blend, height| stamped(&library(blend, 0.0, 0.0), 105.0, 50.0, height);
+        // 5 units right of centre is u = 1.5, value 0.75
+        assert_eq!(at(BlendMode::Replace, 0.2), 0.75);
+        assert_eq!(at(BlendMode::Add, 0.2), 0.95);
+        assert_eq!(at(BlendMode::Max, 0.2), 0.75);
+        assert_eq!(at(BlendMode::Min, 0.2), 0.2);
+        // Outside the brush nothing changes
+        assert_eq!(stamped(&library(BlendMode::Replace, 0.0, 0.0), 121.0, 50.0, 0.2), 0.2);
+    }
+
+    #[test]
+    fn rotation_and_falloff() {
+        // Rotated a quarter turn, the ramp runs along z
+        let rotated = library(BlendMode::Replace, 90.0, 0.0);
+        assert!((stamped(&rotated, 100.0, 55.0, 0.2) - 0.75).abs() < 1e-12);
+        assert!((stamped(&rotated, 100.0, 45.0, 0.2) - 0.25).abs() < 1e-12);
+
+        // Falloff fades to the terrain at the brush edge
+        let soft = library(BlendMode::Replace, 0.0, 1.0);
+        assert_eq!(stamped(&soft, 110.0, 50.0, 0.2), 0.2);
+        // Halfway in from the right edge the weight is a half
+        assert!((stamped(&soft, 105.0, 50.0, 0.2) - 0.475).abs() < 1e-12);
+    }
+
+    #[test]
+    fn validation() {
+        let mut lib = library(BlendMode::Add, 0.0, 0.5);
+        assert_eq!(lib.validate(), Ok(()));
+        lib.stamps[0].brush = "missing".into();
+        assert!(lib.validate().is_err());
+
+        let mut lib = library(BlendMode::Add, 0.0, 0.5);
+        lib.brushes[0].data.pop();
+        assert_eq!(
+            lib.validate(),
+            Err(ConfigError::Stamps("brush data must have width * height values"))
+        );
+    }
+}
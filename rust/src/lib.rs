@@ -1,13 +1,45 @@
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 pub mod biome;
 pub mod chunk;
+pub mod codec;
+pub mod config;
 pub mod erosion;
+pub mod export;
+pub mod features;
+pub mod hash;
 pub mod noise;
+pub mod pipeline;
+pub mod planner;
+pub mod raycast;
+pub mod region;
+pub mod sample;
+pub mod spline;
+pub mod strata;
+#[cfg(feature = "parallel")]
+pub mod threads;
+pub mod world;
 
-pub use chunk::{generate_chunk, ChunkData};
+pub use chunk::{chunk_hardness_map, generate_cell_mask, generate_chunk, ChunkBuffers, ChunkData};
+pub use codec::{decode_chunk, encode_chunk, CodecError};
+pub use features::stamps::{BlendMode, Brush, StampLibrary, StampPlacement};
+pub use config::{ConfigError, ConfigFormatError, LoadedConfig};
+pub use erosion::{ErosionError, ErosionJob};
+pub use pipeline::{update_chunk, LayerError, Stage, StageHashes};
+pub use planner::{ChunkPlan, ChunkPlanner, ChunkRequest};
+pub use raycast::{raycast, raycast_terrain, HeightQuadtree, RayHit};
+pub use region::{generate_region, RegionData, RegionError};
+pub use sample::{
+    sample_biome, sample_continentalness, sample_height, sample_normal, ChunkSource, TerrainSampler,
+};
+pub use spline::{HeightSpline, SplineInput, SplinePoint, SplineValue};
+pub use strata::{RockLayer, Strata};
+pub use world::World;
 
 #[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TerrainConfig {
     pub seed: u64,
     pub chunk_size: u32,
@@ -23,10 +55,49 @@ pub struct TerrainConfig {
     pub erosion_deposition: f64,
     pub erosion_erosion_rate: f64,
     pub erosion_evaporation: f64,
+    pub hardness_noise: f64, // 0 = no noise hardness
+    pub hardness_frequency: f64,
     pub moisture_enabled: bool,
     pub erosion_enabled: bool,
-    noise_type: u8, // 0=Simplex, 1=Perlin, 2=Ridged, 3=Warped
+    noise_type: u8, // 0=Simplex, 1=Perlin, 2=Ridged, 3=Warped, 4=Cellular, 5=Billow, 6=Hybrid, 7=Hetero
     pub warp_strength: f64,
+    warp_base: u8, // noise_type of the warped base, never 3
+    pub warp_depth: u32,
+    pub warp_octaves: u32,
+    pub warp_frequency: f64,
+    pub warp_level_gain: f64,
+    continent_mode: u8, // 0=Off, 1=Noise, 2=Plates
+    pub continent_frequency: f64,
+    pub continent_threshold: f64,
+    pub continent_strength: f64,
+    /// Remaps noise heights before erosion; `None` keeps them as is.
+    #[wasm_bindgen(skip)]
+    pub height_spline: Option<HeightSpline>,
+    pub spline_erosion_frequency: f64,
+    pub terrace_steps: u32, // 0 = no terracing
+    pub terrace_smoothness: f64,
+    pub terrace_jitter: f64,
+    /// Rock layers by altitude for the material map and erosion hardness.
+    #[wasm_bindgen(skip)]
+    pub strata: Option<Strata>,
+    pub crater_density: f64, // mean craters per region, 0 = none
+    pub crater_min_radius: f64,
+    pub crater_max_radius: f64,
+    pub crater_size_exponent: f64,
+    pub crater_depth: f64,
+    pub crater_peak: f64,
+    /// Hand-authored heightmaps placed over the shaped terrain.
+    #[wasm_bindgen(skip)]
+    pub stamps: Option<StampLibrary>,
+    cellular_return: u8,   // 0=F1, 1=F2, 2=F2-F1, 3=CellId
+    cellular_distance: u8, // 0=Euclidean, 1=Manhattan, 2=Chebyshev
+    pub cellular_jitter: f64,
+    pub multifractal_offset: f64,
+    pub multifractal_gain: f64,
+    pub ridged_offset: f64,
+    pub ridged_gain: f64,
+    pub ridged_exponent: f64,
+    pub ridged_weight_frequency: f64, // 0 = no weight field
 }
 
 #[wasm_bindgen]
@@ -48,10 +119,43 @@ impl TerrainConfig {
             erosion_deposition: 0.3,
             erosion_erosion_rate: 0.3,
             erosion_evaporation: 0.01,
+            hardness_noise: 0.0,
+            hardness_frequency: 0.01,
             moisture_enabled: true,
             erosion_enabled: false,
             noise_type: 0,
             warp_strength: 0.5,
+            warp_base: 0,
+            warp_depth: 1,
+            warp_octaves: 1,
+            warp_frequency: 0.003,
+            warp_level_gain: 1.0,
+            continent_mode: 0,
+            continent_frequency: 0.0005,
+            continent_threshold: 0.5,
+            continent_strength: 1.0,
+            height_spline: None,
+            spline_erosion_frequency: 0.001,
+            terrace_steps: 0,
+            terrace_smoothness: 0.2,
+            terrace_jitter: 0.0,
+            strata: None,
+            crater_density: 0.0,
+            crater_min_radius: 8.0,
+            crater_max_radius: 120.0,
+            crater_size_exponent: 2.0,
+            crater_depth: 0.15,
+            crater_peak: 0.3,
+            stamps: None,
+            cellular_return: 0,
+            cellular_distance: 0,
+            cellular_jitter: 1.0,
+            multifractal_offset: 0.7,
+            multifractal_gain: 1.0,
+            ridged_offset: 1.0,
+            ridged_gain: 2.0,
+            ridged_exponent: 2.0,
+            ridged_weight_frequency: 0.0,
         }
     }
 
@@ -62,7 +166,119 @@ impl TerrainConfig {
 
     #[wasm_bindgen(setter)]
     pub fn set_noise_type(&mut self, val: u8) {
-        self.noise_type = val.min(3);
+        self.noise_type = val.min(7);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn warp_base(&self) -> u8 {
+        self.warp_base
+    }
+
+    /// Noise type to warp; Warped itself falls back to Simplex.
+    #[wasm_bindgen(setter)]
+    pub fn set_warp_base(&mut self, val: u8) {
+        self.warp_base = if val == 3 { 0 } else { val.min(7) };
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn continent_mode(&self) -> u8 {
+        self.continent_mode
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_continent_mode(&mut self, val: u8) {
+        self.continent_mode = val.min(2);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cellular_return(&self) -> u8 {
+        self.cellular_return
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_cellular_return(&mut self, val: u8) {
+        self.cellular_return = val.min(3);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cellular_distance(&self) -> u8 {
+        self.cellular_distance
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_cellular_distance(&mut self, val: u8) {
+        self.cellular_distance = val.min(2);
+    }
+
+    /// Stable hash of every generation parameter, used to key cached chunks.
+    /// Viewer-only settings (`height_multiplier`) are left out.
+    pub fn config_hash(&self) -> u64 {
+        let mut h = hash::Fnv64::new();
+        h.write_u64(self.seed);
+        h.write_u32(self.chunk_size);
+        h.write_f64(self.scale);
+        h.write_u32(self.octaves);
+        h.write_f64(self.persistence);
+        h.write_f64(self.lacunarity);
+        h.write_f64(self.sea_level);
+        h.write_u32(self.erosion_iterations);
+        h.write_f64(self.erosion_inertia);
+        h.write_f64(self.erosion_capacity);
+        h.write_f64(self.erosion_deposition);
+        h.write_f64(self.erosion_erosion_rate);
+        h.write_f64(self.erosion_evaporation);
+        h.write_f64(self.hardness_noise);
+        h.write_f64(self.hardness_frequency);
+        h.write_bool(self.moisture_enabled);
+        h.write_bool(self.erosion_enabled);
+        h.write(&[self.noise_type]);
+        h.write_f64(self.warp_strength);
+        h.write(&[self.warp_base]);
+        h.write_u32(self.warp_depth);
+        h.write_u32(self.warp_octaves);
+        h.write_f64(self.warp_frequency);
+        h.write_f64(self.warp_level_gain);
+        h.write(&[self.continent_mode]);
+        h.write_f64(self.continent_frequency);
+        h.write_f64(self.continent_threshold);
+        h.write_f64(self.continent_strength);
+        h.write_bool(self.height_spline.is_some());
+        if let Some(spline) = &self.height_spline {
+            spline.hash_into(&mut h);
+        }
+        h.write_f64(self.spline_erosion_frequency);
+        h.write_u32(self.terrace_steps);
+        h.write_f64(self.terrace_smoothness);
+        h.write_f64(self.terrace_jitter);
+        h.write_bool(self.strata.is_some());
+        if let Some(strata) = &self.strata {
+            strata.hash_into(&mut h);
+        }
+        h.write_f64(self.crater_density);
+        h.write_f64(self.crater_min_radius);
+        h.write_f64(self.crater_max_radius);
+        h.write_f64(self.crater_size_exponent);
+        h.write_f64(self.crater_depth);
+        h.write_f64(self.crater_peak);
+        h.write_bool(self.stamps.is_some());
+        if let Some(stamps) = &self.stamps {
+            stamps.hash_into(&mut h);
+        }
+        h.write(&[self.cellular_return, self.cellular_distance]);
+        h.write_f64(self.cellular_jitter);
+        h.write_f64(self.multifractal_offset);
+        h.write_f64(self.multifractal_gain);
+        h.write_f64(self.ridged_offset);
+        h.write_f64(self.ridged_gain);
+        h.write_f64(self.ridged_exponent);
+        h.write_f64(self.ridged_weight_frequency);
+        h.finish()
+    }
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self::new(42)
     }
 }
 
@@ -72,6 +288,10 @@ pub enum NoiseType {
     Perlin,
     Ridged,
     Warped,
+    Cellular,
+    Billow,
+    HybridMultifractal,
+    HeteroMultifractal,
 }
 
 impl From<u8> for NoiseType {
@@ -80,6 +300,10 @@ impl From<u8> for NoiseType {
             1 => NoiseType::Perlin,
             2 => NoiseType::Ridged,
             3 => NoiseType::Warped,
+            4 => NoiseType::Cellular,
+            5 => NoiseType::Billow,
+            6 => NoiseType::HybridMultifractal,
+            7 => NoiseType::HeteroMultifractal,
             _ => NoiseType::Simplex,
         }
     }
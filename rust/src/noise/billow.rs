@@ -0,0 +1,79 @@
+use noise::{NoiseFn, Simplex};
+
+use super::NoiseField;
+
+/// fBm of folded simplex (`|n|`): rounded bumps meeting in creases, for
+/// rolling hills and dunes.
+pub struct BillowNoise {
+    simplex: Simplex,
+    octaves: u32,
+    persistence: f64,
+    lacunarity: f64,
+    scale: f64,
+}
+
+impl BillowNoise {
+    pub fn new(seed: u64, octaves: u32, persistence: f64, lacunarity: f64, scale: f64) -> Self {
+        let seed_u32 = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            simplex: Simplex::new(seed_u32),
+            octaves: octaves.max(1),
+            persistence,
+            lacunarity,
+            scale,
+        }
+    }
+
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sample_at(world_x * self.scale * 0.005, world_z * self.scale * 0.005)
+    }
+}
+
+impl NoiseField for BillowNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..self.octaves {
+            let raw = self.simplex.get([sx * frequency, sz * frequency]);
+            value += raw.abs() * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        (value / max_amplitude).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn billow_in_range() {
+        let noise = BillowNoise::new(42, 6, 0.5, 2.0, 1.0);
+        for x in 0..50 {
+            for z in 0..50 {
+                let v = noise.sample(x as f64 * 3.0, z as f64 * 3.0);
+                assert!(
+                    (0.0..=1.0).contains(&v),
+                    "Billow value {} out of range at ({}, {})",
+                    v,
+                    x,
+                    z
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn billow_deterministic() {
+        let a = BillowNoise::new(7, 6, 0.5, 2.0, 1.0);
+        let b = BillowNoise::new(7, 6, 0.5, 2.0, 1.0);
+        assert_eq!(a.sample(123.0, -45.0), b.sample(123.0, -45.0));
+        assert_ne!(a.sample(123.0, -45.0), BillowNoise::new(8, 6, 0.5, 2.0, 1.0).sample(123.0, -45.0));
+    }
+}
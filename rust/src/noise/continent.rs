@@ -0,0 +1,201 @@
+use noise::{NoiseFn, Simplex};
+
+use super::worley::feature_point;
+
+const FIELD_OCTAVES: u32 = 4;
+/// Plate-border blend width, in plate cells.
+const PLATE_BLEND: f64 = 0.35;
+/// Share of the plate field taken by fBm, so coasts don't follow plate edges.
+const PLATE_NOISE_MIX: f64 = 0.3;
+/// Detail amplitude left on the deep ocean floor.
+const OCEAN_DETAIL: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContinentMode {
+    Off,
+    /// Low-frequency fBm.
+    Noise,
+    /// Voronoi plates, each randomly continental or oceanic, blended across
+    /// their borders.
+    Plates,
+}
+
+impl From<u8> for ContinentMode {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => ContinentMode::Noise,
+            2 => ContinentMode::Plates,
+            _ => ContinentMode::Off,
+        }
+    }
+}
+
+/// World-scale continentalness: low values are ocean basins, values above
+/// `threshold` are land. It sets the macro elevation the height noise rides
+/// on and scales that noise down offshore, so shelves and abyssal plains
+/// fall out of the same field as coastlines.
+pub struct ContinentField {
+    mode: ContinentMode,
+    noise: Simplex,
+    plate_seed: u32,
+    frequency: f64,
+    threshold: f64,
+    strength: f64,
+    sea_level: f64,
+}
+
+impl ContinentField {
+    /// `frequency` is in cycles per world unit.
+    pub fn new(
+        seed: u64,
+        mode: ContinentMode,
+        frequency: f64,
+        threshold: f64,
+        strength: f64,
+        sea_level: f64,
+    ) -> Self {
+        let s = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            mode,
+            noise: Simplex::new(s.wrapping_add(4000)),
+            plate_seed: s.wrapping_add(5000),
+            frequency,
+            threshold,
+            strength,
+            sea_level,
+        }
+    }
+
+    pub fn mode(&self) -> ContinentMode {
+        self.mode
+    }
+
+    fn fbm(&self, x: f64, z: f64) -> f64 {
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..FIELD_OCTAVES {
+            value += self.noise.get([x * frequency, z * frequency]) * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        ((value / max_amplitude + 1.0) * 0.5).clamp(0.0, 1.0)
+    }
+
+    fn plates(&self, x: f64, z: f64) -> f64 {
+        let (cx, cz) = (x.floor() as i64, z.floor() as i64);
+        let mut plates = [(0.0, 0.0); 25];
+        let mut nearest = f64::MAX;
+        for (i, plate) in plates.iter_mut().enumerate() {
+            let (nx, nz) = (cx + (i % 5) as i64 - 2, cz + (i / 5) as i64 - 2);
+            let (px, pz, id) = feature_point(self.plate_seed, nx, nz, 1.0);
+            let d = ((px - x).powi(2) + (pz - z).powi(2)).sqrt();
+            nearest = nearest.min(d);
+            *plate = (d, id as f64 / u32::MAX as f64);
+        }
+        // Weight plates by how much farther than the nearest they are, so
+        // values cross borders smoothly
+        let (mut sum, mut weights) = (0.0, 0.0);
+        for (d, value) in plates {
+            let t = ((d - nearest) / PLATE_BLEND).clamp(0.0, 1.0);
+            let w = 1.0 - t * t * (3.0 - 2.0 * t);
+            sum += value * w;
+            weights += w;
+        }
+        sum / weights
+    }
+
+    /// Continentalness in `0..=1`; 0.5 everywhere when off.
+    pub fn continentalness(&self, world_x: f64, world_z: f64) -> f64 {
+        let (x, z) = (world_x * self.frequency, world_z * self.frequency);
+        match self.mode {
+            ContinentMode::Off => 0.5,
+            ContinentMode::Noise => self.fbm(x, z),
+            ContinentMode::Plates => {
+                let mix = self.fbm(x * 2.0, z * 2.0);
+                self.plates(x, z) * (1.0 - PLATE_NOISE_MIX) + mix * PLATE_NOISE_MIX
+            }
+        }
+    }
+
+    /// Reshape a detail height (`0..=1`) at a point with continentalness `c`.
+    pub fn apply(&self, c: f64, detail: f64) -> f64 {
+        if self.mode == ContinentMode::Off {
+            return detail;
+        }
+        // Macro elevation: the threshold maps to sea level, 0 and 1 to the
+        // bottom and top of the range
+        let elevation = if c < self.threshold {
+            self.sea_level * c / self.threshold
+        } else {
+            self.sea_level + (1.0 - self.sea_level) * (c - self.threshold) / (1.0 - self.threshold)
+        };
+        // Full relief from just offshore inland, flattening towards the abyss
+        let t = ((c - self.threshold + 0.15) / 0.3).clamp(0.0, 1.0);
+        let amplitude = OCEAN_DETAIL + (1.0 - OCEAN_DETAIL) * t * t * (3.0 - 2.0 * t);
+        let shaped = (elevation + (detail - 0.5) * amplitude).clamp(0.0, 1.0);
+        detail + (shaped - detail) * self.strength
+    }
+
+    pub fn shape(&self, world_x: f64, world_z: f64, detail: f64) -> f64 {
+        self.apply(self.continentalness(world_x, world_z), detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(mode: ContinentMode) -> ContinentField {
+        ContinentField::new(42, mode, 0.0005, 0.5, 1.0, 0.35)
+    }
+
+    #[test]
+    fn continentalness_in_range_and_continuous() {
+        for mode in [ContinentMode::Noise, ContinentMode::Plates] {
+            let field = field(mode);
+            let mut prev = field.continentalness(-5000.0, 1200.0);
+            for i in 1..4000 {
+                let c = field.continentalness(-5000.0 + i as f64 * 4.0, 1200.0);
+                assert!((0.0..=1.0).contains(&c), "{:?} continentalness {} out of range", mode, c);
+                // No jumps at plate borders
+                assert!((c - prev).abs() < 0.02, "{:?} jumps from {} to {}", mode, prev, c);
+                prev = c;
+            }
+        }
+    }
+
+    #[test]
+    fn decides_land_and_ocean() {
+        let field = field(ContinentMode::Plates);
+        let (mut land, mut ocean) = (0, 0);
+        for i in 0..2500 {
+            let (x, z) = ((i % 50) as f64 * 400.0, (i / 50) as f64 * 400.0);
+            // Even the highest detail stays underwater deep offshore
+            let c = field.continentalness(x, z);
+            if c < 0.2 {
+                let h = field.apply(c, 1.0);
+                assert!(h < 0.35, "deep ocean at {} rises to {}", c, h);
+                ocean += 1;
+            }
+            if c > 0.7 && field.apply(c, 0.5) > 0.35 {
+                land += 1;
+            }
+        }
+        assert!(land > 0 && ocean > 0, "land {} ocean {}", land, ocean);
+    }
+
+    #[test]
+    fn off_and_zero_strength_keep_detail() {
+        let off = field(ContinentMode::Off);
+        let weak = ContinentField::new(42, ContinentMode::Noise, 0.0005, 0.5, 0.0, 0.35);
+        for d in [0.0, 0.3, 0.77, 1.0] {
+            assert_eq!(off.shape(10.0, 20.0, d), d);
+            assert_eq!(weak.shape(10.0, 20.0, d), d);
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use noise::{NoiseFn, Perlin, Simplex};
 
+use super::simd::{BatchNoise, F64x4, LANES};
+use super::NoiseField;
 use crate::NoiseType;
 
 pub struct LayeredNoise {
     simplex: Simplex,
     perlin: Perlin,
+    batch: BatchNoise,
     noise_type: NoiseType,
     octaves: u32,
     persistence: f64,
@@ -25,6 +28,7 @@ impl LayeredNoise {
         Self {
             simplex: Simplex::new(seed_u32),
             perlin: Perlin::new(seed_u32),
+            batch: BatchNoise::new(seed_u32),
             noise_type,
             octaves: octaves.max(1),
             persistence,
@@ -35,22 +39,72 @@ impl LayeredNoise {
 
     fn base_sample(&self, x: f64, y: f64) -> f64 {
         match self.noise_type {
-            NoiseType::Simplex | NoiseType::Ridged | NoiseType::Warped => {
-                self.simplex.get([x, y])
-            }
             NoiseType::Perlin => self.perlin.get([x, y]),
+            _ => self.simplex.get([x, y]),
+        }
+    }
+
+    fn base_sample4(&self, x: F64x4, y: F64x4) -> F64x4 {
+        match self.noise_type {
+            NoiseType::Perlin => self.batch.perlin4(x, y),
+            _ => self.batch.simplex4(x, y),
         }
     }
 
     pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sample_at(world_x * self.scale * 0.005, world_z * self.scale * 0.005)
+    }
+
+    /// Sample `out.len()` consecutive cells starting at `world_x0`, four at a
+    /// time. Matches `sample` within `simd::BATCH_TOLERANCE`.
+    pub fn sample_row(&self, world_x0: f64, world_z: f64, out: &mut [f64]) {
+        let sz = F64x4::splat(world_z * self.scale * 0.005);
+
+        for (block, values) in out.chunks_mut(LANES).enumerate() {
+            let first = block * LANES;
+            // Short tail blocks repeat their last cell in the spare lanes
+            let sx = F64x4::from_array(std::array::from_fn(|lane| {
+                let x = world_x0 + (first + lane.min(values.len() - 1)) as f64;
+                x * self.scale * 0.005
+            }));
+
+            let mut amplitude = 1.0;
+            let mut frequency = 1.0;
+            let mut value = F64x4::splat(0.0);
+            let mut max_amplitude = 0.0;
+
+            for _ in 0..self.octaves {
+                let f = F64x4::splat(frequency);
+                let sample = self.base_sample4(sx * f, sz * f);
+                value = value + sample * F64x4::splat(amplitude);
+                max_amplitude += amplitude;
+                amplitude *= self.persistence;
+                frequency *= self.lacunarity;
+            }
+
+            let value = value.to_array();
+            for (lane, out) in values.iter_mut().enumerate() {
+                *out = ((value[lane] / max_amplitude + 1.0) * 0.5).clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// Sample a `width`-wide grid of rows starting at (`world_x0`, `world_z0`)
+    /// into `out`, which holds `out.len() / width` rows.
+    pub fn sample_grid(&self, world_x0: f64, world_z0: f64, width: usize, out: &mut [f64]) {
+        for (z, row) in out.chunks_mut(width).enumerate() {
+            self.sample_row(world_x0, world_z0 + z as f64, row);
+        }
+    }
+}
+
+impl NoiseField for LayeredNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
         let mut amplitude = 1.0;
         let mut frequency = 1.0;
         let mut value = 0.0;
         let mut max_amplitude = 0.0;
 
-        let sx = world_x * self.scale * 0.005;
-        let sz = world_z * self.scale * 0.005;
-
         for _ in 0..self.octaves {
             let sample = self.base_sample(sx * frequency, sz * frequency);
             value += sample * amplitude;
@@ -94,6 +148,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn batch_matches_scalar() {
+        use crate::noise::simd::BATCH_TOLERANCE;
+
+        for noise_type in [NoiseType::Simplex, NoiseType::Perlin] {
+            let noise = LayeredNoise::new(42, noise_type, 6, 0.5, 2.0, 1.0);
+            let (width, rows) = (37, 5);
+            let mut grid = vec![0.0; width * rows];
+            noise.sample_grid(-18.0, -3.0, width, &mut grid);
+            for (i, &v) in grid.iter().enumerate() {
+                let x = -18.0 + (i % width) as f64;
+                let z = -3.0 + (i / width) as f64;
+                let expected = noise.sample(x, z);
+                assert!(
+                    (v - expected).abs() <= BATCH_TOLERANCE,
+                    "Batch value {} != {} at ({}, {})",
+                    v,
+                    expected,
+                    x,
+                    z
+                );
+            }
+        }
+    }
+
     #[test]
     fn different_seeds_differ() {
         let a = LayeredNoise::new(1, NoiseType::Simplex, 6, 0.5, 2.0, 1.0);
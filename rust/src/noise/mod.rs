@@ -1,7 +1,24 @@
+pub mod billow;
+pub mod continent;
 pub mod layers;
+pub mod multifractal;
 pub mod ridged;
+pub mod simd;
 pub mod warped;
+pub mod worley;
 
+pub use billow::BillowNoise;
+pub use continent::{ContinentField, ContinentMode};
 pub use layers::LayeredNoise;
-pub use ridged::RidgedNoise;
-pub use warped::WarpedNoise;
+pub use multifractal::{Multifractal, MultifractalNoise};
+pub use ridged::{RidgeShape, RidgedNoise};
+pub use warped::{WarpParams, WarpedNoise};
+pub use worley::WorleyNoise;
+
+/// A height field in noise space, where coordinates are world units times
+/// `scale * 0.005`. Lets warping offset lookups without a round trip
+/// through world units.
+pub trait NoiseField: Send + Sync {
+    /// Height in `0..=1`.
+    fn sample_at(&self, sx: f64, sz: f64) -> f64;
+}
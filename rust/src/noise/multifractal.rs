@@ -0,0 +1,125 @@
+use noise::{NoiseFn, Simplex};
+
+use super::NoiseField;
+
+/// Musgrave's multifractals: octaves add detail in proportion to what is
+/// already there, so lowlands stay smooth and highlands get rough.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multifractal {
+    /// Each octave is weighted by the product of the previous signals.
+    Hybrid,
+    /// Each octave is weighted by the height accumulated so far.
+    Hetero,
+}
+
+pub struct MultifractalNoise {
+    simplex: Simplex,
+    kind: Multifractal,
+    offset: f64,
+    gain: f64,
+    octaves: u32,
+    persistence: f64,
+    lacunarity: f64,
+    scale: f64,
+}
+
+impl MultifractalNoise {
+    /// `offset` lifts each octave's signal (`n + offset`); `gain` scales the
+    /// weight carried to the next octave, which saturates at 1.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        seed: u64,
+        kind: Multifractal,
+        offset: f64,
+        gain: f64,
+        octaves: u32,
+        persistence: f64,
+        lacunarity: f64,
+        scale: f64,
+    ) -> Self {
+        let seed_u32 = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            simplex: Simplex::new(seed_u32),
+            kind,
+            offset,
+            gain,
+            octaves: octaves.max(1),
+            persistence,
+            lacunarity,
+            scale,
+        }
+    }
+
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sample_at(world_x * self.scale * 0.005, world_z * self.scale * 0.005)
+    }
+}
+
+impl NoiseField for MultifractalNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut weight = 1.0;
+        let mut max_amplitude = 0.0;
+
+        for octave in 0..self.octaves {
+            let signal = self.simplex.get([sx * frequency, sz * frequency]) + self.offset;
+            match self.kind {
+                Multifractal::Hybrid => {
+                    value += weight * signal * amplitude;
+                    weight = (weight * self.gain * signal).clamp(0.0, 1.0);
+                }
+                Multifractal::Hetero => {
+                    // The first octave sets the base the others build on
+                    let weight = if octave == 0 { 1.0 } else { (value * self.gain).clamp(0.0, 1.0) };
+                    value += weight * signal * amplitude;
+                }
+            }
+            max_amplitude += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        // Every octave contributes at most (1 + offset) * amplitude
+        (value / (max_amplitude * (1.0 + self.offset))).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multifractals_in_range() {
+        for kind in [Multifractal::Hybrid, Multifractal::Hetero] {
+            for (offset, gain) in [(0.7, 1.0), (0.0, 4.0), (2.0, 0.5)] {
+                let noise = MultifractalNoise::new(42, kind, offset, gain, 6, 0.5, 2.0, 1.0);
+                for x in 0..50 {
+                    for z in 0..50 {
+                        let v = noise.sample(x as f64 * 3.0, z as f64 * 3.0);
+                        assert!(
+                            (0.0..=1.0).contains(&v),
+                            "{:?} value {} out of range at ({}, {})",
+                            kind,
+                            v,
+                            x,
+                            z
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn multifractals_deterministic() {
+        for kind in [Multifractal::Hybrid, Multifractal::Hetero] {
+            let a = MultifractalNoise::new(7, kind, 0.7, 1.0, 6, 0.5, 2.0, 1.0);
+            let b = MultifractalNoise::new(7, kind, 0.7, 1.0, 6, 0.5, 2.0, 1.0);
+            let c = MultifractalNoise::new(7, kind, 0.9, 1.0, 6, 0.5, 2.0, 1.0);
+            assert_eq!(a.sample(321.0, 55.0), b.sample(321.0, 55.0));
+            assert_ne!(a.sample(321.0, 55.0), c.sample(321.0, 55.0));
+        }
+    }
+}
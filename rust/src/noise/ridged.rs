@@ -1,7 +1,38 @@
 use noise::{NoiseFn, Simplex};
 
+use super::NoiseField;
+
+/// How each ridged octave is shaped and weighted. The default is the
+/// classic `(1 - |n|)^2` with `weight = 2 * signal`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RidgeShape {
+    /// Ridge crest height; lower values flatten crests and widen valleys.
+    pub offset: f64,
+    /// How strongly an octave's signal lets detail through to the next.
+    pub gain: f64,
+    /// Sharpness of the crests.
+    pub exponent: f64,
+    /// Frequency of the weight field relative to the base octave, or 0 for
+    /// none. The field seeds the octave weights, so ranges fade out where
+    /// it is low.
+    pub weight_frequency: f64,
+}
+
+impl Default for RidgeShape {
+    fn default() -> Self {
+        Self {
+            offset: 1.0,
+            gain: 2.0,
+            exponent: 2.0,
+            weight_frequency: 0.0,
+        }
+    }
+}
+
 pub struct RidgedNoise {
     simplex: Simplex,
+    weight_field: Simplex,
+    shape: RidgeShape,
     octaves: u32,
     persistence: f64,
     lacunarity: f64,
@@ -9,10 +40,19 @@ pub struct RidgedNoise {
 }
 
 impl RidgedNoise {
-    pub fn new(seed: u64, octaves: u32, persistence: f64, lacunarity: f64, scale: f64) -> Self {
+    pub fn new(
+        seed: u64,
+        shape: RidgeShape,
+        octaves: u32,
+        persistence: f64,
+        lacunarity: f64,
+        scale: f64,
+    ) -> Self {
         let seed_u32 = (seed & 0xFFFFFFFF) as u32;
         Self {
             simplex: Simplex::new(seed_u32),
+            weight_field: Simplex::new(seed_u32.wrapping_add(3000)),
+            shape,
             octaves: octaves.max(1),
             persistence,
             lacunarity,
@@ -21,24 +61,35 @@ impl RidgedNoise {
     }
 
     pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sample_at(world_x * self.scale * 0.005, world_z * self.scale * 0.005)
+    }
+}
+
+impl NoiseField for RidgedNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
         let mut amplitude = 1.0;
         let mut frequency = 1.0;
         let mut value = 0.0;
-        let mut weight = 1.0;
         let mut max_amplitude = 0.0;
+        let RidgeShape { offset, gain, exponent, weight_frequency } = self.shape;
 
-        let sx = world_x * self.scale * 0.005;
-        let sz = world_z * self.scale * 0.005;
+        let mut weight = if weight_frequency > 0.0 {
+            let field = self.weight_field.get([sx * weight_frequency, sz * weight_frequency]);
+            let t = ((field + 1.0) * 0.5).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        } else {
+            1.0
+        };
 
         for _ in 0..self.octaves {
             let raw = self.simplex.get([sx * frequency, sz * frequency]);
             // Ridged: invert absolute value → sharp ridges
-            let mut signal = 1.0 - raw.abs();
-            // Square for sharper ridges
-            signal *= signal;
+            let mut signal = (offset - raw.abs()).max(0.0);
+            // Sharpen; squaring stays exact for the default shape
+            signal = if exponent == 2.0 { signal * signal } else { signal.powf(exponent) };
             // Weight by previous octave
             signal *= weight;
-            weight = (signal * 2.0).clamp(0.0, 1.0);
+            weight = (signal * gain).clamp(0.0, 1.0);
 
             value += signal * amplitude;
             max_amplitude += amplitude;
@@ -46,7 +97,8 @@ impl RidgedNoise {
             frequency *= self.lacunarity;
         }
 
-        (value / max_amplitude).clamp(0.0, 1.0)
+        // A crest peaks at offset^exponent
+        (value / (max_amplitude * offset.powf(exponent))).clamp(0.0, 1.0)
     }
 }
 
@@ -56,7 +108,7 @@ mod tests {
 
     #[test]
     fn ridged_in_range() {
-        let noise = RidgedNoise::new(42, 6, 0.5, 2.0, 1.0);
+        let noise = RidgedNoise::new(42, RidgeShape::default(), 6, 0.5, 2.0, 1.0);
         for x in 0..50 {
             for z in 0..50 {
                 let v = noise.sample(x as f64 * 3.0, z as f64 * 3.0);
@@ -70,4 +122,40 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn shaped_ridges_in_range() {
+        let shape = RidgeShape {
+            offset: 0.8,
+            gain: 4.0,
+            exponent: 3.0,
+            weight_frequency: 0.2,
+        };
+        let noise = RidgedNoise::new(42, shape, 6, 0.5, 2.0, 1.0);
+        for x in 0..50 {
+            for z in 0..50 {
+                let v = noise.sample(x as f64 * 9.0, z as f64 * 9.0);
+                assert!((0.0..=1.0).contains(&v), "Ridged value {} out of range", v);
+            }
+        }
+    }
+
+    #[test]
+    fn weight_field_fades_ranges() {
+        let plain = RidgedNoise::new(5, RidgeShape::default(), 6, 0.5, 2.0, 1.0);
+        let shape = RidgeShape {
+            weight_frequency: 0.25,
+            ..RidgeShape::default()
+        };
+        let faded = RidgedNoise::new(5, shape, 6, 0.5, 2.0, 1.0);
+        let mut lower = 0;
+        for i in 0..400 {
+            let (x, z) = ((i % 20) as f64 * 40.0, (i / 20) as f64 * 40.0);
+            let (p, f) = (plain.sample(x, z), faded.sample(x, z));
+            // The field only ever removes detail
+            assert!(f <= p + 1e-12);
+            lower += (f < p - 0.05) as u32;
+        }
+        assert!(lower > 40, "weight field barely changed the output");
+    }
 }
@@ -0,0 +1,398 @@
+//! Four-lane batch evaluation of 2D simplex and Perlin noise.
+//!
+//! The kernels mirror `noise::core::simplex::simplex_2d` and `perlin_2d`
+//! operation for operation, using the same permutation table, so batch
+//! output matches `NoiseFn::get` to within `BATCH_TOLERANCE` (the same IEEE
+//! operations run in the same order, so in practice results are identical).
+//! Lattice flooring and hashing stay scalar per lane; the arithmetic runs on
+//! `F64x4`, backed by AVX, SSE2 or wasm simd128 depending on the target
+//! features, with a plain array fallback elsewhere.
+
+use std::ops::{Add, Mul, Sub};
+
+use noise::permutationtable::{NoiseHasher, PermutationTable};
+
+/// Largest difference from the scalar `noise` crate output.
+pub const BATCH_TOLERANCE: f64 = 1e-12;
+pub const LANES: usize = 4;
+
+#[derive(Clone, Copy)]
+pub struct F64x4(Inner);
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+type Inner = std::arch::x86_64::__m256d;
+
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+type Inner = [std::arch::x86_64::__m128d; 2];
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+type Inner = [std::arch::wasm32::v128; 2];
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    all(target_arch = "wasm32", target_feature = "simd128")
+)))]
+type Inner = [f64; 4];
+
+// Intrinsics are safe to call when their feature is enabled at compile time
+// on newer toolchains; keep the blocks for older ones.
+#[allow(unused_unsafe)]
+impl F64x4 {
+    #[inline(always)]
+    pub fn splat(v: f64) -> Self {
+        Self::from_array([v; 4])
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+    #[inline(always)]
+    pub fn from_array(a: [f64; 4]) -> Self {
+        use std::arch::x86_64::*;
+        Self(unsafe { _mm256_loadu_pd(a.as_ptr()) })
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 4] {
+        use std::arch::x86_64::*;
+        let mut out = [0.0; 4];
+        unsafe { _mm256_storeu_pd(out.as_mut_ptr(), self.0) };
+        out
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+    #[inline(always)]
+    pub fn from_array(a: [f64; 4]) -> Self {
+        use std::arch::x86_64::*;
+        Self(unsafe { [_mm_loadu_pd(a.as_ptr()), _mm_loadu_pd(a[2..].as_ptr())] })
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 4] {
+        use std::arch::x86_64::*;
+        let mut out = [0.0; 4];
+        unsafe {
+            _mm_storeu_pd(out.as_mut_ptr(), self.0[0]);
+            _mm_storeu_pd(out[2..].as_mut_ptr(), self.0[1]);
+        }
+        out
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    #[inline(always)]
+    pub fn from_array(a: [f64; 4]) -> Self {
+        use std::arch::wasm32::*;
+        Self([f64x2(a[0], a[1]), f64x2(a[2], a[3])])
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 4] {
+        use std::arch::wasm32::*;
+        [
+            f64x2_extract_lane::<0>(self.0[0]),
+            f64x2_extract_lane::<1>(self.0[0]),
+            f64x2_extract_lane::<0>(self.0[1]),
+            f64x2_extract_lane::<1>(self.0[1]),
+        ]
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    #[inline(always)]
+    pub fn from_array(a: [f64; 4]) -> Self {
+        Self(a)
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )))]
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 4] {
+        self.0
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+        return Self(unsafe { std::arch::x86_64::_mm256_max_pd(self.0, other.0) });
+        #[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+        return Self(unsafe {
+            use std::arch::x86_64::_mm_max_pd;
+            [_mm_max_pd(self.0[0], other.0[0]), _mm_max_pd(self.0[1], other.0[1])]
+        });
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        return Self({
+            use std::arch::wasm32::f64x2_pmax;
+            [f64x2_pmax(self.0[0], other.0[0]), f64x2_pmax(self.0[1], other.0[1])]
+        });
+        #[cfg(not(any(
+            target_arch = "x86_64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        )))]
+        return Self(std::array::from_fn(|i| if self.0[i] > other.0[i] { self.0[i] } else { other.0[i] }));
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+        return Self(unsafe { std::arch::x86_64::_mm256_min_pd(self.0, other.0) });
+        #[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+        return Self(unsafe {
+            use std::arch::x86_64::_mm_min_pd;
+            [_mm_min_pd(self.0[0], other.0[0]), _mm_min_pd(self.0[1], other.0[1])]
+        });
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        return Self({
+            use std::arch::wasm32::f64x2_pmin;
+            [f64x2_pmin(self.0[0], other.0[0]), f64x2_pmin(self.0[1], other.0[1])]
+        });
+        #[cfg(not(any(
+            target_arch = "x86_64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        )))]
+        return Self(std::array::from_fn(|i| if self.0[i] < other.0[i] { self.0[i] } else { other.0[i] }));
+    }
+}
+
+macro_rules! lane_op {
+    ($trait:ident, $method:ident, $avx:ident, $sse:ident, $wasm:ident, $op:tt) => {
+        impl $trait for F64x4 {
+            type Output = Self;
+
+            #[inline(always)]
+            #[allow(unused_unsafe)]
+            fn $method(self, rhs: Self) -> Self {
+                #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+                return Self(unsafe { std::arch::x86_64::$avx(self.0, rhs.0) });
+                #[cfg(all(target_arch = "x86_64", not(target_feature = "avx")))]
+                return Self(unsafe {
+                    use std::arch::x86_64::$sse;
+                    [$sse(self.0[0], rhs.0[0]), $sse(self.0[1], rhs.0[1])]
+                });
+                #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+                return Self({
+                    use std::arch::wasm32::$wasm;
+                    [$wasm(self.0[0], rhs.0[0]), $wasm(self.0[1], rhs.0[1])]
+                });
+                #[cfg(not(any(
+                    target_arch = "x86_64",
+                    all(target_arch = "wasm32", target_feature = "simd128")
+                )))]
+                return Self(std::array::from_fn(|i| self.0[i] $op rhs.0[i]));
+            }
+        }
+    };
+}
+
+lane_op!(Add, add, _mm256_add_pd, _mm_add_pd, f64x2_add, +);
+lane_op!(Sub, sub, _mm256_sub_pd, _mm_sub_pd, f64x2_sub, -);
+lane_op!(Mul, mul, _mm256_mul_pd, _mm_mul_pd, f64x2_mul, *);
+
+// Matches `noise`'s `floor_to_isize`, including its quirk of mapping
+// non-positive integers one cell lower (so -1.0 -> -2, 0.0 -> -1).
+#[inline(always)]
+fn lattice(v: f64) -> isize {
+    if v <= 0.0 {
+        v as isize - 1
+    } else {
+        v as isize
+    }
+}
+
+#[inline(always)]
+fn grad2(index: usize) -> [f64; 2] {
+    const DIAG: f64 = std::f64::consts::FRAC_1_SQRT_2;
+    match index % 8 {
+        0 => [1.0, 0.0],
+        1 => [-1.0, 0.0],
+        2 => [0.0, 1.0],
+        3 => [0.0, -1.0],
+        4 => [DIAG, DIAG],
+        5 => [-DIAG, DIAG],
+        6 => [DIAG, -DIAG],
+        _ => [-DIAG, -DIAG],
+    }
+}
+
+/// Batch 2D simplex/Perlin kernels sharing `noise::Simplex::new(seed)` and
+/// `noise::Perlin::new(seed)`'s permutation table.
+pub struct BatchNoise {
+    perm: [u8; 256],
+    skew: f64,
+    unskew: f64,
+}
+
+impl BatchNoise {
+    pub fn new(seed: u32) -> Self {
+        let table = PermutationTable::new(seed);
+        let mut perm = [0u8; 256];
+        for (i, p) in perm.iter_mut().enumerate() {
+            // A one-element hash is a plain table lookup
+            *p = table.hash(&[i as isize]) as u8;
+        }
+        Self {
+            perm,
+            skew: ((2.0f64 + 1.0).sqrt() - 1.0) / 2.0,
+            unskew: (1.0 - (1.0 / (2.0f64 + 1.0).sqrt())) / 2.0,
+        }
+    }
+
+    #[inline(always)]
+    fn hash(&self, x: isize, z: isize) -> usize {
+        let index = self.perm[(x & 0xff) as usize] as usize ^ (z & 0xff) as usize;
+        self.perm[index] as usize
+    }
+
+    pub fn simplex4(&self, x: F64x4, z: F64x4) -> F64x4 {
+        let skew = (x + z) * F64x4::splat(self.skew);
+        let (sx, sz) = ((x + skew).to_array(), (z + skew).to_array());
+        let cx: [isize; 4] = std::array::from_fn(|i| lattice(sx[i]));
+        let cz: [isize; 4] = std::array::from_fn(|i| lattice(sz[i]));
+        let fx = F64x4::from_array(cx.map(|c| c as f64));
+        let fz = F64x4::from_array(cz.map(|c| c as f64));
+
+        let g = F64x4::splat(self.unskew);
+        let unskew = (fx + fz) * g;
+        let x0 = x - (fx - unskew);
+        let z0 = z - (fz - unskew);
+
+        let (ax0, az0) = (x0.to_array(), z0.to_array());
+        let lower: [bool; 4] = std::array::from_fn(|i| ax0[i] > az0[i]);
+        let ox = F64x4::from_array(lower.map(|l| if l { 1.0 } else { 0.0 }));
+        let oz = F64x4::from_array(lower.map(|l| if l { 0.0 } else { 1.0 }));
+
+        let x1 = x0 - ox + g;
+        let z1 = z0 - oz + g;
+        let two_g = F64x4::splat(2.0 * self.unskew);
+        let one = F64x4::splat(1.0);
+        let x2 = x0 - one + two_g;
+        let z2 = z0 - one + two_g;
+
+        let mut grads = [[[0.0; 4]; 2]; 3];
+        for i in 0..4 {
+            let (ix, iz) = if lower[i] { (1, 0) } else { (0, 1) };
+            let corners = [
+                self.hash(cx[i], cz[i]),
+                self.hash(cx[i] + ix, cz[i] + iz),
+                self.hash(cx[i] + 1, cz[i] + 1),
+            ];
+            for (corner, &gi) in corners.iter().enumerate() {
+                let [gx, gz] = grad2(gi);
+                grads[corner][0][i] = gx;
+                grads[corner][1][i] = gz;
+            }
+        }
+
+        let surflet = |px: F64x4, pz: F64x4, grad: [[f64; 4]; 2]| {
+            let gx = F64x4::from_array(grad[0]);
+            let gz = F64x4::from_array(grad[1]);
+            // t <= 0 means no influence: clamping makes the weight exactly zero
+            let t = (one - (px * px + pz * pz) * F64x4::splat(2.0)).max(F64x4::splat(0.0));
+            let t2 = t * t;
+            let t4 = t2 * t2;
+            (F64x4::splat(2.0) * t2 + t4) * (px * gx + pz * gz)
+        };
+
+        surflet(x0, z0, grads[0]) + surflet(x1, z1, grads[1]) + surflet(x2, z2, grads[2])
+    }
+
+    pub fn perlin4(&self, x: F64x4, z: F64x4) -> F64x4 {
+        const SCALE_FACTOR: f64 = 2.0 / std::f64::consts::SQRT_2;
+
+        let (ax, az) = (x.to_array(), z.to_array());
+        let cx: [isize; 4] = std::array::from_fn(|i| lattice(ax[i]));
+        let cz: [isize; 4] = std::array::from_fn(|i| lattice(az[i]));
+        let dx = x - F64x4::from_array(cx.map(|c| c as f64));
+        let dz = z - F64x4::from_array(cz.map(|c| c as f64));
+
+        let one = F64x4::splat(1.0);
+        let zero = F64x4::splat(0.0);
+        let gradient = |ox: isize, oz: isize| {
+            let mut sign_x = [0.0; 4];
+            let mut sign_z = [0.0; 4];
+            for i in 0..4 {
+                let h = self.hash(cx[i] + ox, cz[i] + oz) & 0b11;
+                sign_x[i] = if h & 1 == 0 { 1.0 } else { -1.0 };
+                sign_z[i] = if h & 2 == 0 { 1.0 } else { -1.0 };
+            }
+            let px = dx - F64x4::splat(ox as f64);
+            let pz = dz - F64x4::splat(oz as f64);
+            F64x4::from_array(sign_x) * px + F64x4::from_array(sign_z) * pz
+        };
+
+        let g00 = gradient(0, 0);
+        let g10 = gradient(1, 0);
+        let g01 = gradient(0, 1);
+        let g11 = gradient(1, 1);
+
+        let quintic = |d: F64x4| {
+            let t = d.max(zero).min(one);
+            t * t * t * (t * (t * F64x4::splat(6.0) - F64x4::splat(15.0)) + F64x4::splat(10.0))
+        };
+        let (ux, uz) = (quintic(dx), quintic(dz));
+        let linear = |a: F64x4, b: F64x4, alpha: F64x4| b * alpha + a * (one - alpha);
+
+        let result = linear(linear(g00, g01, uz), linear(g10, g11, uz), ux) * F64x4::splat(SCALE_FACTOR);
+        result.max(F64x4::splat(-1.0)).min(one)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::{NoiseFn, Perlin, Simplex};
+
+    fn points() -> impl Iterator<Item = [f64; 4]> {
+        // Includes negative and exact-integer coordinates
+        (-40..40).map(|i| {
+            let b = i as f64 * 0.37;
+            [b, b + 0.5, -b * 1.3, (i / 4) as f64]
+        })
+    }
+
+    #[test]
+    fn simplex_batch_matches_scalar() {
+        let scalar = Simplex::new(42);
+        let batch = BatchNoise::new(42);
+        for xs in points() {
+            let zs = xs.map(|v| v * 0.61 - 3.0);
+            let out = batch.simplex4(F64x4::from_array(xs), F64x4::from_array(zs)).to_array();
+            for i in 0..4 {
+                let expected = scalar.get([xs[i], zs[i]]);
+                assert!(
+                    (out[i] - expected).abs() <= BATCH_TOLERANCE,
+                    "Simplex mismatch at ({}, {}): {} vs {}",
+                    xs[i],
+                    zs[i],
+                    out[i],
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn perlin_batch_matches_scalar() {
+        let scalar = Perlin::new(7);
+        let batch = BatchNoise::new(7);
+        for xs in points() {
+            let zs = xs.map(|v| 2.5 - v * 0.83);
+            let out = batch.perlin4(F64x4::from_array(xs), F64x4::from_array(zs)).to_array();
+            for i in 0..4 {
+                let expected = scalar.get([xs[i], zs[i]]);
+                assert!(
+                    (out[i] - expected).abs() <= BATCH_TOLERANCE,
+                    "Perlin mismatch at ({}, {}): {} vs {}",
+                    xs[i],
+                    zs[i],
+                    out[i],
+                    expected
+                );
+            }
+        }
+    }
+}
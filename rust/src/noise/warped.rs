@@ -1,64 +1,192 @@
 use noise::{NoiseFn, Simplex};
 
+use super::NoiseField;
+
+/// Recursive domain warp settings: `base(p + w0(p + w1(p + ...)))`, where
+/// each level `wk` is an fBm vector field scaled by its strength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarpParams {
+    /// Strength of the outermost level, in noise-space units.
+    pub strength: f64,
+    /// Number of nested warp levels.
+    pub depth: u32,
+    /// fBm octaves per warp level.
+    pub octaves: u32,
+    /// Warp noise frequency in cycles per world unit.
+    pub frequency: f64,
+    /// Strength multiplier from each level to the next one in.
+    pub level_gain: f64,
+}
+
+impl Default for WarpParams {
+    fn default() -> Self {
+        Self {
+            strength: 0.5,
+            depth: 1,
+            octaves: 1,
+            frequency: 0.003,
+            level_gain: 1.0,
+        }
+    }
+}
+
+struct WarpLevel {
+    x: Simplex,
+    z: Simplex,
+    strength: f64,
+}
+
 pub struct WarpedNoise {
-    primary: Simplex,
-    warp_x: Simplex,
-    warp_z: Simplex,
-    warp_strength: f64,
+    base: Box<dyn NoiseField>,
+    // Outermost level first
+    levels: Vec<WarpLevel>,
     octaves: u32,
+    frequency: f64,
     persistence: f64,
     lacunarity: f64,
     scale: f64,
 }
 
 impl WarpedNoise {
+    /// Warp `base`, which must use the same `scale`. Warp octaves follow
+    /// `persistence` and `lacunarity` like the base.
     pub fn new(
         seed: u64,
-        warp_strength: f64,
-        octaves: u32,
+        base: Box<dyn NoiseField>,
+        params: WarpParams,
         persistence: f64,
         lacunarity: f64,
         scale: f64,
     ) -> Self {
         let s = (seed & 0xFFFFFFFF) as u32;
+        let levels = (0..params.depth.max(1))
+            .map(|level| WarpLevel {
+                x: Simplex::new(s.wrapping_add(1000 + 100 * level)),
+                z: Simplex::new(s.wrapping_add(2000 + 100 * level)),
+                strength: params.strength * params.level_gain.powi(level as i32),
+            })
+            .collect();
         Self {
-            primary: Simplex::new(s),
-            warp_x: Simplex::new(s.wrapping_add(1000)),
-            warp_z: Simplex::new(s.wrapping_add(2000)),
-            warp_strength,
-            octaves: octaves.max(1),
+            base,
+            levels,
+            octaves: params.octaves.max(1),
+            frequency: params.frequency,
             persistence,
             lacunarity,
             scale,
         }
     }
 
-    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
-        let sx = world_x * self.scale * 0.005;
-        let sz = world_z * self.scale * 0.005;
-
-        // Domain warping: offset coordinates by noise
-        let warp_scale = 0.003;
-        let wx = self.warp_x.get([world_x * warp_scale, world_z * warp_scale]) * self.warp_strength;
-        let wz = self.warp_z.get([world_x * warp_scale, world_z * warp_scale]) * self.warp_strength;
-
-        let warped_x = sx + wx;
-        let warped_z = sz + wz;
-
+    // fBm in -1..=1
+    fn warp_fbm(&self, noise: &Simplex, x: f64, z: f64) -> f64 {
         let mut amplitude = 1.0;
         let mut frequency = 1.0;
         let mut value = 0.0;
         let mut max_amplitude = 0.0;
 
         for _ in 0..self.octaves {
-            let sample = self.primary.get([warped_x * frequency, warped_z * frequency]);
-            value += sample * amplitude;
+            value += noise.get([x * frequency, z * frequency]) * amplitude;
             max_amplitude += amplitude;
             amplitude *= self.persistence;
             frequency *= self.lacunarity;
         }
 
-        let normalized = (value / max_amplitude + 1.0) * 0.5;
-        normalized.clamp(0.0, 1.0)
+        value / max_amplitude
+    }
+
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        let sx = world_x * self.scale * 0.005;
+        let sz = world_z * self.scale * 0.005;
+
+        // Domain warping, innermost level first: each level's offset shifts
+        // where the next one out is looked up
+        let (px, pz) = (world_x * self.frequency, world_z * self.frequency);
+        let (mut wx, mut wz) = (0.0, 0.0);
+        for level in self.levels.iter().rev() {
+            let (qx, qz) = (px + wx, pz + wz);
+            wx = self.warp_fbm(&level.x, qx, qz) * level.strength;
+            wz = self.warp_fbm(&level.z, qx, qz) * level.strength;
+        }
+
+        self.base.sample_at(sx + wx, sz + wz)
+    }
+}
+
+impl NoiseField for WarpedNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
+        let to_world = 1.0 / (self.scale * 0.005);
+        self.sample(sx * to_world, sz * to_world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::worley::{CellularReturn, DistanceMetric};
+    use crate::noise::{LayeredNoise, RidgeShape, RidgedNoise, WorleyNoise};
+    use crate::NoiseType;
+
+    fn simplex_base() -> Box<dyn NoiseField> {
+        Box::new(LayeredNoise::new(42, NoiseType::Simplex, 6, 0.5, 2.0, 1.0))
+    }
+
+    #[test]
+    fn single_level_is_a_plain_offset() {
+        let noise = WarpedNoise::new(42, simplex_base(), WarpParams::default(), 0.5, 2.0, 1.0);
+        let base = LayeredNoise::new(42, NoiseType::Simplex, 6, 0.5, 2.0, 1.0);
+        let (warp_x, warp_z) = (Simplex::new(1042), Simplex::new(2042));
+        for i in 0..200 {
+            let (x, z) = (i as f64 * 13.7 - 900.0, i as f64 * -4.1 + 50.0);
+            let wx = warp_x.get([x * 0.003, z * 0.003]) * 0.5;
+            let wz = warp_z.get([x * 0.003, z * 0.003]) * 0.5;
+            let expected = base.sample_at(x * 0.005 + wx, z * 0.005 + wz);
+            assert_eq!(noise.sample(x, z).to_bits(), expected.to_bits());
+        }
+    }
+
+    #[test]
+    fn recursive_warps_in_range_for_any_base() {
+        let params = WarpParams {
+            strength: 1.5,
+            depth: 3,
+            octaves: 4,
+            frequency: 0.004,
+            level_gain: 0.8,
+        };
+        let bases: [Box<dyn NoiseField>; 3] = [
+            simplex_base(),
+            Box::new(RidgedNoise::new(42, RidgeShape::default(), 6, 0.5, 2.0, 1.0)),
+            Box::new(WorleyNoise::new(
+                42,
+                CellularReturn::F2MinusF1,
+                DistanceMetric::Euclidean,
+                1.0,
+                3,
+                0.5,
+                2.0,
+                1.0,
+            )),
+        ];
+        for base in bases {
+            let noise = WarpedNoise::new(42, base, params, 0.5, 2.0, 1.0);
+            for x in 0..40 {
+                for z in 0..40 {
+                    let v = noise.sample(x as f64 * 11.0, z as f64 * 11.0);
+                    assert!((0.0..=1.0).contains(&v), "Warped value {} out of range", v);
+                    assert_eq!(v, noise.sample(x as f64 * 11.0, z as f64 * 11.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn depth_changes_output() {
+        let shallow = WarpedNoise::new(7, simplex_base(), WarpParams::default(), 0.5, 2.0, 1.0);
+        let params = WarpParams { depth: 2, ..WarpParams::default() };
+        let deep = WarpedNoise::new(7, simplex_base(), params, 0.5, 2.0, 1.0);
+        let differ = (0..100)
+            .filter(|&i| shallow.sample(i as f64 * 17.0, 3.0) != deep.sample(i as f64 * 17.0, 3.0))
+            .count();
+        assert!(differ > 90);
     }
 }
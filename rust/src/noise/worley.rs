@@ -0,0 +1,247 @@
+use super::NoiseField;
+
+/// Which Worley quantity a sample returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellularReturn {
+    /// Distance to the nearest feature point.
+    F1,
+    /// Distance to the second nearest feature point.
+    F2,
+    /// `F2 - F1`: zero along cell borders, for cracks and ridges.
+    F2MinusF1,
+    /// A per-cell random value, constant over each cell.
+    CellId,
+}
+
+impl From<u8> for CellularReturn {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => CellularReturn::F2,
+            2 => CellularReturn::F2MinusF1,
+            3 => CellularReturn::CellId,
+            _ => CellularReturn::F1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceMetric {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl From<u8> for DistanceMetric {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => DistanceMetric::Manhattan,
+            2 => DistanceMetric::Chebyshev,
+            _ => DistanceMetric::Euclidean,
+        }
+    }
+}
+
+impl DistanceMetric {
+    fn distance(self, dx: f64, dz: f64) -> f64 {
+        match self {
+            DistanceMetric::Euclidean => (dx * dx + dz * dz).sqrt(),
+            DistanceMetric::Manhattan => dx.abs() + dz.abs(),
+            DistanceMetric::Chebyshev => dx.abs().max(dz.abs()),
+        }
+    }
+
+    // Typical F1 upper bound at full jitter, used to normalize to 0..1
+    fn range(self) -> f64 {
+        match self {
+            DistanceMetric::Euclidean => 1.0,
+            DistanceMetric::Manhattan => 1.4,
+            DistanceMetric::Chebyshev => 0.9,
+        }
+    }
+}
+
+/// Nearest two feature distances and the id of the nearest cell.
+#[derive(Debug, Clone, Copy)]
+pub struct WorleySample {
+    pub f1: f64,
+    pub f2: f64,
+    pub cell_id: u32,
+}
+
+pub(crate) fn cell_hash(seed: u32, x: i64, z: i64) -> u64 {
+    let mut h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (seed as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^ (h >> 33)
+}
+
+/// Feature point of cell (`x`, `z`) and the cell's id.
+pub(crate) fn feature_point(seed: u32, x: i64, z: i64, jitter: f64) -> (f64, f64, u32) {
+    let h = cell_hash(seed, x, z);
+    let ox = (h & 0xFFFF_FFFF) as f64 / 4_294_967_296.0;
+    let oz = (h >> 32) as f64 / 4_294_967_296.0;
+    (
+        x as f64 + 0.5 + (ox - 0.5) * jitter,
+        z as f64 + 0.5 + (oz - 0.5) * jitter,
+        (h >> 16) as u32,
+    )
+}
+
+/// Single-octave Worley noise at `(x, z)` in cell units. Each cell holds
+/// one feature point, offset from its centre by up to `jitter / 2`.
+pub fn worley_2d(seed: u32, x: f64, z: f64, jitter: f64, metric: DistanceMetric) -> WorleySample {
+    let (cx, cz) = (x.floor() as i64, z.floor() as i64);
+    let mut best = WorleySample {
+        f1: f64::MAX,
+        f2: f64::MAX,
+        cell_id: 0,
+    };
+
+    // Full jitter can put the nearest point two cells away
+    for nz in cz - 2..=cz + 2 {
+        for nx in cx - 2..=cx + 2 {
+            let (px, pz, id) = feature_point(seed, nx, nz, jitter);
+            let d = metric.distance(px - x, pz - z);
+            if d < best.f1 {
+                best.f2 = best.f1;
+                best.f1 = d;
+                best.cell_id = id;
+            } else if d < best.f2 {
+                best.f2 = d;
+            }
+        }
+    }
+    best
+}
+
+pub struct WorleyNoise {
+    seed: u32,
+    output: CellularReturn,
+    metric: DistanceMetric,
+    jitter: f64,
+    octaves: u32,
+    persistence: f64,
+    lacunarity: f64,
+    scale: f64,
+}
+
+impl WorleyNoise {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        seed: u64,
+        output: CellularReturn,
+        metric: DistanceMetric,
+        jitter: f64,
+        octaves: u32,
+        persistence: f64,
+        lacunarity: f64,
+        scale: f64,
+    ) -> Self {
+        Self {
+            seed: (seed & 0xFFFFFFFF) as u32,
+            output,
+            metric,
+            jitter: jitter.clamp(0.0, 1.0),
+            octaves: octaves.max(1),
+            persistence,
+            lacunarity,
+            scale,
+        }
+    }
+
+    fn octave_value(&self, sample: WorleySample) -> f64 {
+        let range = self.metric.range();
+        let v = match self.output {
+            CellularReturn::F1 => sample.f1 / range,
+            CellularReturn::F2 => sample.f2 / (range * 1.5),
+            CellularReturn::F2MinusF1 => (sample.f2 - sample.f1) / range,
+            CellularReturn::CellId => sample.cell_id as f64 / u32::MAX as f64,
+        };
+        v.clamp(0.0, 1.0)
+    }
+
+    /// fBm-layered cellular value in `0..=1`; each octave uses its own
+    /// feature points.
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sample_at(world_x * self.scale * 0.005, world_z * self.scale * 0.005)
+    }
+
+    /// Id of the base-octave cell containing the point, for region masks.
+    pub fn cell_id(&self, world_x: f64, world_z: f64) -> u32 {
+        let sx = world_x * self.scale * 0.005;
+        let sz = world_z * self.scale * 0.005;
+        worley_2d(self.seed, sx, sz, self.jitter, self.metric).cell_id
+    }
+}
+
+impl NoiseField for WorleyNoise {
+    fn sample_at(&self, sx: f64, sz: f64) -> f64 {
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut max_amplitude = 0.0;
+
+        for octave in 0..self.octaves {
+            let seed = self.seed.wrapping_add(octave);
+            let sample = worley_2d(seed, sx * frequency, sz * frequency, self.jitter, self.metric);
+            value += self.octave_value(sample) * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        (value / max_amplitude).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cellular_in_range() {
+        for output in 0..4 {
+            for metric in 0..3 {
+                let noise = WorleyNoise::new(42, output.into(), metric.into(), 1.0, 4, 0.5, 2.0, 1.0);
+                for x in 0..40 {
+                    for z in 0..40 {
+                        let v = noise.sample(x as f64 * 7.0 - 100.0, z as f64 * 5.0);
+                        assert!(
+                            (0.0..=1.0).contains(&v),
+                            "Cellular value {} out of range at ({}, {})",
+                            v,
+                            x,
+                            z
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distances_are_ordered_and_zero_at_features() {
+        for i in 0..200 {
+            let (x, z) = (i as f64 * 0.173 - 17.0, i as f64 * -0.291 + 3.0);
+            let s = worley_2d(9, x, z, 0.8, DistanceMetric::Euclidean);
+            assert!(s.f1 <= s.f2);
+        }
+        // With no jitter, feature points sit at cell centres
+        let s = worley_2d(9, 3.5, -2.5, 0.0, DistanceMetric::Euclidean);
+        assert!(s.f1 < 1e-12);
+        assert!((s.f2 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cell_ids_are_constant_per_cell() {
+        let noise = WorleyNoise::new(3, CellularReturn::CellId, DistanceMetric::Euclidean, 0.0, 1, 0.5, 2.0, 1.0);
+        // Scale 1: cells are 200 world units across
+        assert_eq!(noise.cell_id(10.0, 10.0), noise.cell_id(190.0, 150.0));
+        assert_ne!(noise.cell_id(10.0, 10.0), noise.cell_id(210.0, 10.0));
+        assert_eq!(noise.cell_id(55.0, 66.0), noise.cell_id(55.0, 66.0));
+    }
+}
@@ -0,0 +1,408 @@
+//! Chunk generation as explicit stages, each keyed by a hash of the config
+//! fields it reads (chained with the hashes of the stages it consumes).
+//! A chunk records the hashes it was built with, so after a config change
+//! only stages whose hash moved are rerun on the cached layers.
+//!
+//! Erosion works in place on the heightmap, so rerunning it also reruns
+//! the height stage rather than keeping an un-eroded copy per chunk.
+
+use std::sync::OnceLock;
+
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::chunk::height_range;
+use crate::config::ConfigError;
+use crate::hash::Fnv64;
+use crate::sample::TerrainSampler;
+use crate::{ChunkData, TerrainConfig};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Stage {
+    /// Raw noise heightmap.
+    Height = 0,
+    /// Hydraulic erosion of the heightmap.
+    Erosion = 1,
+    Moisture = 2,
+    /// Biome map from height, moisture and sea level; material map from
+    /// height and strata.
+    Classification = 3,
+    /// Min/max heights and the raycast quadtree.
+    Derived = 4,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Height,
+        Stage::Erosion,
+        Stage::Moisture,
+        Stage::Classification,
+        Stage::Derived,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageHashes {
+    pub height: u64,
+    pub erosion: u64,
+    pub moisture: u64,
+    pub classification: u64,
+    pub derived: u64,
+}
+
+impl StageHashes {
+    pub fn new(config: &TerrainConfig) -> Self {
+        let mut h = Fnv64::new();
+        h.write_u64(config.seed);
+        h.write_u32(config.chunk_size);
+        h.write_f64(config.scale);
+        h.write_u32(config.octaves);
+        h.write_f64(config.persistence);
+        h.write_f64(config.lacunarity);
+        h.write(&[config.noise_type()]);
+        h.write_f64(config.warp_strength);
+        h.write(&[config.warp_base()]);
+        h.write_u32(config.warp_depth);
+        h.write_u32(config.warp_octaves);
+        h.write_f64(config.warp_frequency);
+        h.write_f64(config.warp_level_gain);
+        h.write(&[config.continent_mode()]);
+        if config.continent_mode() != 0 {
+            // Sea level anchors the continental shelf
+            h.write_f64(config.continent_frequency);
+            h.write_f64(config.continent_threshold);
+            h.write_f64(config.continent_strength);
+            h.write_f64(config.sea_level);
+        }
+        if let Some(spline) = &config.height_spline {
+            spline.hash_into(&mut h);
+            h.write_f64(config.spline_erosion_frequency);
+        }
+        h.write_u32(config.terrace_steps);
+        if config.terrace_steps > 0 {
+            h.write_f64(config.terrace_smoothness);
+            h.write_f64(config.terrace_jitter);
+        }
+        h.write_f64(config.crater_density);
+        if config.crater_density > 0.0 {
+            h.write_f64(config.crater_min_radius);
+            h.write_f64(config.crater_max_radius);
+            h.write_f64(config.crater_size_exponent);
+            h.write_f64(config.crater_depth);
+            h.write_f64(config.crater_peak);
+        }
+        h.write_bool(config.stamps.is_some());
+        if let Some(stamps) = &config.stamps {
+            stamps.hash_into(&mut h);
+        }
+        h.write(&[config.cellular_return(), config.cellular_distance()]);
+        h.write_f64(config.cellular_jitter);
+        h.write_f64(config.multifractal_offset);
+        h.write_f64(config.multifractal_gain);
+        h.write_f64(config.ridged_offset);
+        h.write_f64(config.ridged_gain);
+        h.write_f64(config.ridged_exponent);
+        h.write_f64(config.ridged_weight_frequency);
+        let height = h.finish();
+
+        let mut h = Fnv64::new();
+        h.write_u64(height);
+        h.write_bool(config.erosion_enabled);
+        if config.erosion_enabled {
+            h.write_u32(config.erosion_iterations);
+            h.write_f64(config.erosion_inertia);
+            h.write_f64(config.erosion_capacity);
+            h.write_f64(config.erosion_deposition);
+            h.write_f64(config.erosion_erosion_rate);
+            h.write_f64(config.erosion_evaporation);
+            // Hardness scales erosion
+            h.write_f64(config.hardness_noise);
+            if config.hardness_noise > 0.0 {
+                h.write_f64(config.hardness_frequency);
+            }
+            if let Some(strata) = &config.strata {
+                strata.hash_into(&mut h);
+            }
+        }
+        let erosion = h.finish();
+
+        let mut h = Fnv64::new();
+        h.write_u32(config.chunk_size);
+        h.write_bool(config.moisture_enabled);
+        if config.moisture_enabled {
+            h.write_u64(config.seed);
+            h.write_f64(config.scale);
+        }
+        let moisture = h.finish();
+
+        let mut h = Fnv64::new();
+        h.write_u64(erosion);
+        h.write_u64(moisture);
+        h.write_f64(config.sea_level);
+        h.write_bool(config.strata.is_some());
+        if let Some(strata) = &config.strata {
+            strata.hash_into(&mut h);
+        }
+        let classification = h.finish();
+
+        let mut h = Fnv64::new();
+        h.write_u64(erosion);
+        let derived = h.finish();
+
+        Self {
+            height,
+            erosion,
+            moisture,
+            classification,
+            derived,
+        }
+    }
+
+    /// Stages to rerun to bring layers built with `self` up to `current`.
+    pub fn stale(&self, current: &StageHashes) -> Vec<Stage> {
+        let mut stages = Vec::new();
+        if self.height != current.height || self.erosion != current.erosion {
+            stages.extend([Stage::Height, Stage::Erosion]);
+        }
+        if self.moisture != current.moisture {
+            stages.push(Stage::Moisture);
+        }
+        if self.classification != current.classification {
+            stages.push(Stage::Classification);
+        }
+        if self.derived != current.derived {
+            stages.push(Stage::Derived);
+        }
+        stages
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LayerError {
+    #[error("{layer} has {len} cells, expected {expected}")]
+    Size {
+        layer: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl From<LayerError> for JsValue {
+    fn from(err: LayerError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+impl ChunkData {
+    /// Rebuild a chunk from layers that were generated with `config`, e.g.
+    /// ones the viewer kept after `into_buffers`, so `update_chunk` can
+    /// bring them up to a newer config.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_layers(
+        config: &TerrainConfig,
+        chunk_x: i32,
+        chunk_z: i32,
+        heightmap: Vec<f32>,
+        moisture_map: Vec<f32>,
+        biome_map: Vec<u8>,
+        material_map: Vec<u8>,
+    ) -> Result<ChunkData, LayerError> {
+        config.validate()?;
+        let expected = (config.chunk_size * config.chunk_size) as usize;
+        for (layer, len) in [
+            ("heightmap", heightmap.len()),
+            ("moisture map", moisture_map.len()),
+            ("biome map", biome_map.len()),
+            ("material map", material_map.len()),
+        ] {
+            if len != expected {
+                return Err(LayerError::Size { layer, len, expected });
+            }
+        }
+
+        let (min_height, max_height) = height_range(&heightmap);
+        Ok(ChunkData {
+            heightmap,
+            moisture_map,
+            biome_map,
+            material_map,
+            chunk_x,
+            chunk_z,
+            width: config.chunk_size,
+            height: config.chunk_size,
+            min_height,
+            max_height,
+            quadtree: OnceLock::new(),
+            stages: StageHashes::new(config),
+        })
+    }
+}
+
+impl ChunkData {
+    /// Hashes of the configs this chunk's layers were built from.
+    pub fn stage_hashes(&self) -> StageHashes {
+        self.stages
+    }
+}
+
+/// Bring a chunk built with an older config up to date in place, rerunning
+/// only the stages whose inputs changed. Returns the stage ids that ran.
+#[wasm_bindgen]
+pub fn update_chunk(config: &TerrainConfig, chunk: &mut ChunkData) -> Result<Vec<u8>, ConfigError> {
+    let sampler = TerrainSampler::new(config)?;
+    let stages = sampler.update_chunk(config, chunk);
+    Ok(stages.into_iter().map(|s| s as u8).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_chunk;
+
+    fn config() -> TerrainConfig {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        config.erosion_enabled = true;
+        config.erosion_iterations = 500;
+        config
+    }
+
+    fn assert_same(a: &ChunkData, b: &ChunkData) {
+        assert_eq!(a.heightmap, b.heightmap);
+        assert_eq!(a.moisture_map, b.moisture_map);
+        assert_eq!(a.biome_map, b.biome_map);
+        assert_eq!(a.material_map, b.material_map);
+        assert_eq!((a.min_height, a.max_height), (b.min_height, b.max_height));
+        assert_eq!(a.stages, b.stages);
+    }
+
+    #[test]
+    fn sea_level_only_reclassifies() {
+        let mut config = config();
+        let mut chunk = generate_chunk(&config, 1, -1).unwrap();
+        config.sea_level = 0.6;
+        let ran = update_chunk(&config, &mut chunk).unwrap();
+        assert_eq!(ran, vec![Stage::Classification as u8]);
+        assert_same(&chunk, &generate_chunk(&config, 1, -1).unwrap());
+    }
+
+    #[test]
+    fn stages_follow_their_inputs() {
+        let base = config();
+        let hashes = StageHashes::new(&base);
+        let stale = |edit: fn(&mut TerrainConfig)| {
+            let mut c = base.clone();
+            edit(&mut c);
+            hashes.stale(&StageHashes::new(&c))
+        };
+        use Stage::*;
+        assert_eq!(stale(|_| {}), vec![]);
+        // Only the viewer uses height_multiplier
+        assert_eq!(stale(|c| c.height_multiplier = 10.0), vec![]);
+        assert_eq!(stale(|c| c.moisture_enabled = false), vec![Moisture, Classification]);
+        assert_eq!(stale(|c| c.erosion_capacity = 8.0), vec![Height, Erosion, Classification, Derived]);
+        assert_eq!(stale(|c| c.set_continent_mode(2)), vec![Height, Erosion, Classification, Derived]);
+        assert_eq!(stale(|c| c.terrace_steps = 6), vec![Height, Erosion, Classification, Derived]);
+        assert_eq!(stale(|c| c.hardness_noise = 0.5), vec![Height, Erosion, Classification, Derived]);
+        assert_eq!(
+            stale(|c| c.add_stamp_brush("flat".into(), 2, 2, vec![0.0; 4])),
+            vec![Height, Erosion, Classification, Derived]
+        );
+        // Unused while terracing is off
+        assert_eq!(stale(|c| c.terrace_jitter = 0.5), vec![]);
+        assert_eq!(stale(|c| c.seed = 7), vec![Height, Erosion, Moisture, Classification, Derived]);
+    }
+
+    #[test]
+    fn every_field_reaches_a_stage() {
+        // Every optional feature on, so no field is skipped for being unused
+        let mut base = config();
+        base.set_continent_mode(1);
+        base.terrace_steps = 4;
+        base.crater_density = 1.0;
+        base.hardness_noise = 0.5;
+        let mut base = serde_json::to_value(&base).unwrap();
+        base["height_spline"] = serde_json::json!({
+            "input": "height",
+            "points": [{ "location": 0.0, "value": 0.0 }, { "location": 1.0, "value": 1.0 }],
+        });
+        let parse = |value: &serde_json::Value| -> TerrainConfig {
+            serde_json::from_value(value.clone()).unwrap()
+        };
+        let base_config = parse(&base);
+        let hashes = StageHashes::new(&base_config);
+
+        let fields = base.as_object().unwrap().clone();
+        for (field, value) in fields {
+            let changed = if let Some(b) = value.as_bool() {
+                serde_json::json!(!b)
+            } else if let Some(n) = value.as_u64() {
+                serde_json::json!(n + 1)
+            } else if let Some(f) = value.as_f64() {
+                serde_json::json!(f + 0.25)
+            } else {
+                // Nested objects hash through their own hash_into in both
+                continue;
+            };
+            let mut edited = base.clone();
+            edited[&field] = changed;
+            let edited = parse(&edited);
+
+            let config_changed = edited.config_hash() != base_config.config_hash();
+            let stages_changed = !hashes.stale(&StageHashes::new(&edited)).is_empty();
+            // Only the viewer uses height_multiplier
+            let expected = field != "height_multiplier";
+            assert_eq!(config_changed, expected, "config_hash for {field}");
+            assert_eq!(stages_changed, expected, "stage hashes for {field}");
+        }
+    }
+
+    #[test]
+    fn cached_layers_update_like_fresh_chunks() {
+        let mut config = config();
+        let chunk = generate_chunk(&config, 2, 1).unwrap();
+        let mut rebuilt = ChunkData::from_layers(
+            &config,
+            2,
+            1,
+            chunk.heightmap.clone(),
+            chunk.moisture_map.clone(),
+            chunk.biome_map.clone(),
+            chunk.material_map.clone(),
+        )
+        .unwrap();
+        assert_same(&rebuilt, &chunk);
+
+        config.sea_level = 0.6;
+        update_chunk(&config, &mut rebuilt).unwrap();
+        assert_same(&rebuilt, &generate_chunk(&config, 2, 1).unwrap());
+
+        assert_eq!(
+            ChunkData::from_layers(&config, 0, 0, vec![0.0; 289], vec![0.0; 289], vec![0; 288], vec![0; 289])
+                .err(),
+            Some(LayerError::Size { layer: "biome map", len: 288, expected: 289 })
+        );
+    }
+
+    #[test]
+    fn incremental_updates_match_full_generation() {
+        let edits: [fn(&mut TerrainConfig); 4] = [
+            |c| c.moisture_enabled = false,
+            |c| c.erosion_enabled = false,
+            |c| c.octaves = 3,
+            |c| c.chunk_size = 9,
+        ];
+        for edit in edits {
+            let mut config = config();
+            let mut chunk = generate_chunk(&config, -2, 3).unwrap();
+            edit(&mut config);
+            update_chunk(&config, &mut chunk).unwrap();
+            assert_same(&chunk, &generate_chunk(&config, -2, 3).unwrap());
+        }
+    }
+}
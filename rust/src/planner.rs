@@ -0,0 +1,358 @@
+//! Chunk request planning around a moving camera.
+//!
+//! The planner remembers which chunks it has handed out and at what LOD,
+//! and each `plan` call returns the next chunks to load (nearest first,
+//! favouring the view direction), chunks to drop and LOD changes. Unloads
+//! and LOD switches use hysteresis so a camera hovering on a boundary
+//! doesn't make chunks flip back and forth.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+/// How much a chunk straight behind the camera is deprioritized: its
+/// effective distance is `1 + 2 * FACING_WEIGHT` times the real one.
+const FACING_WEIGHT: f64 = 0.5;
+/// Largest view distance, in chunks; each plan scans a square this wide.
+pub const MAX_VIEW_DISTANCE: f64 = 64.0;
+/// Camera coordinates are clamped to this many chunks from the origin so
+/// the scanned square stays inside `i32`.
+const MAX_CAMERA_CHUNK: f64 = 1e9;
+
+/// `chunks` clamped to `0..=MAX_VIEW_DISTANCE`; NaN becomes 0.
+fn clamp_view_distance(chunks: f64) -> f64 {
+    if chunks.is_nan() {
+        0.0
+    } else {
+        chunks.clamp(0.0, MAX_VIEW_DISTANCE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkRequest {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub lod: u8,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Default, PartialEq)]
+pub struct ChunkPlan {
+    /// New chunks to generate, highest priority first.
+    pub(crate) load: Vec<ChunkRequest>,
+    /// Loaded chunks to drop.
+    pub(crate) unload: Vec<(i32, i32)>,
+    /// Loaded chunks whose LOD should change.
+    pub(crate) relod: Vec<ChunkRequest>,
+}
+
+fn flatten_requests(requests: &[ChunkRequest]) -> Vec<i32> {
+    requests
+        .iter()
+        .flat_map(|r| [r.chunk_x, r.chunk_z, r.lod as i32])
+        .collect()
+}
+
+#[wasm_bindgen]
+impl ChunkPlan {
+    /// Chunks to generate as flat `[x, z, lod, ...]` triples.
+    pub fn load_list(&self) -> Vec<i32> {
+        flatten_requests(&self.load)
+    }
+
+    /// Chunks to drop as flat `[x, z, ...]` pairs.
+    pub fn unload_list(&self) -> Vec<i32> {
+        self.unload.iter().flat_map(|&(x, z)| [x, z]).collect()
+    }
+
+    /// LOD changes as flat `[x, z, lod, ...]` triples.
+    pub fn relod_list(&self) -> Vec<i32> {
+        flatten_requests(&self.relod)
+    }
+}
+
+impl ChunkPlan {
+    pub fn load(&self) -> &[ChunkRequest] {
+        &self.load
+    }
+
+    pub fn unload(&self) -> &[(i32, i32)] {
+        &self.unload
+    }
+
+    pub fn relod(&self) -> &[ChunkRequest] {
+        &self.relod
+    }
+}
+
+#[wasm_bindgen]
+pub struct ChunkPlanner {
+    edge: f64,
+    view_distance: f64,
+    unload_margin: f64,
+    lod_distances: [f64; 2],
+    lod_margin: f64,
+    max_loads: u32,
+    loaded: HashMap<(i32, i32), u8>,
+}
+
+#[wasm_bindgen]
+impl ChunkPlanner {
+    /// `view_distance` is in chunks, clamped to `0..=MAX_VIEW_DISTANCE`.
+    /// LOD switches default to 3 and 6 chunks, matching the mesh LOD levels.
+    #[wasm_bindgen(constructor)]
+    pub fn new(chunk_size: u32, view_distance: f64) -> ChunkPlanner {
+        Self {
+            edge: chunk_size.max(2) as f64 - 1.0,
+            view_distance: clamp_view_distance(view_distance),
+            unload_margin: 2.0,
+            lod_distances: [3.0, 6.0],
+            lod_margin: 0.5,
+            max_loads: 4,
+            loaded: HashMap::new(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn view_distance(&self) -> f64 {
+        self.view_distance
+    }
+
+    /// Clamped to `0..=MAX_VIEW_DISTANCE`; NaN becomes 0.
+    #[wasm_bindgen(setter)]
+    pub fn set_view_distance(&mut self, chunks: f64) {
+        self.view_distance = clamp_view_distance(chunks);
+    }
+
+    /// Extra chunks beyond the view distance before a chunk is dropped.
+    #[wasm_bindgen(setter)]
+    pub fn set_unload_margin(&mut self, chunks: f64) {
+        self.unload_margin = chunks.max(0.0);
+    }
+
+    /// Distances (in chunks) where LOD 1 and LOD 2 start.
+    pub fn set_lod_distances(&mut self, lod1: f64, lod2: f64) {
+        self.lod_distances = [lod1, lod2.max(lod1)];
+    }
+
+    /// How far (in chunks) past a LOD boundary a loaded chunk must move
+    /// before it switches.
+    #[wasm_bindgen(setter)]
+    pub fn set_lod_margin(&mut self, chunks: f64) {
+        self.lod_margin = chunks.max(0.0);
+    }
+
+    /// Most new chunks returned by one `plan` call; 0 means no limit.
+    #[wasm_bindgen(setter)]
+    pub fn set_max_loads(&mut self, count: u32) {
+        self.max_loads = count;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn loaded_count(&self) -> usize {
+        self.loaded.len()
+    }
+
+    /// Forget a chunk (e.g. its generation failed) so it's planned again.
+    pub fn forget(&mut self, chunk_x: i32, chunk_z: i32) {
+        self.loaded.remove(&(chunk_x, chunk_z));
+    }
+
+    /// Forget everything, e.g. after a config change cleared the scene.
+    pub fn reset(&mut self) {
+        self.loaded.clear();
+    }
+
+    /// Plan for a camera at (`camera_x`, `camera_z`) looking along
+    /// (`dir_x`, `dir_z`), all in world units. Chunks returned for loading
+    /// are assumed loaded from then on. A non-finite camera position plans
+    /// nothing.
+    pub fn plan(&mut self, camera_x: f64, camera_z: f64, dir_x: f64, dir_z: f64) -> ChunkPlan {
+        if !(camera_x.is_finite() && camera_z.is_finite()) {
+            return ChunkPlan::default();
+        }
+        let clamp = |v: f64| (v / self.edge).clamp(-MAX_CAMERA_CHUNK, MAX_CAMERA_CHUNK);
+        let cam = (clamp(camera_x), clamp(camera_z));
+        let dir_len = (dir_x * dir_x + dir_z * dir_z).sqrt();
+        let dir = if dir_len > 1e-9 {
+            (dir_x / dir_len, dir_z / dir_len)
+        } else {
+            (0.0, 0.0)
+        };
+        let mut plan = ChunkPlan::default();
+
+        let (view, margin, lods) = (self.view_distance, self.lod_margin, self.lod_distances);
+        let mut drops: Vec<((i32, i32), f64)> = Vec::new();
+        for (&key, lod) in self.loaded.iter_mut() {
+            let d = chunk_distance(cam, key);
+            if d > view + self.unload_margin {
+                drops.push((key, d));
+                continue;
+            }
+            let target = lod_for(lods, d);
+            let switch = if target > *lod {
+                lod_for(lods, d - margin) > *lod
+            } else {
+                target < *lod && lod_for(lods, d + margin) < *lod
+            };
+            if switch {
+                *lod = target;
+                plan.relod.push(ChunkRequest {
+                    chunk_x: key.0,
+                    chunk_z: key.1,
+                    lod: target,
+                });
+            }
+        }
+        // Farthest first, so memory is freed where it matters least
+        drops.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (key, _) in drops {
+            self.loaded.remove(&key);
+            plan.unload.push(key);
+        }
+
+        let reach = self.view_distance.ceil() as i32 + 1;
+        let (ccx, ccz) = (cam.0.floor() as i32, cam.1.floor() as i32);
+        let mut wanted: Vec<(f64, ChunkRequest)> = Vec::new();
+        for cz in ccz - reach..=ccz + reach {
+            for cx in ccx - reach..=ccx + reach {
+                if self.loaded.contains_key(&(cx, cz)) {
+                    continue;
+                }
+                let d = chunk_distance(cam, (cx, cz));
+                if d > view {
+                    continue;
+                }
+                let offset = (cx as f64 + 0.5 - cam.0, cz as f64 + 0.5 - cam.1);
+                let cos = if d > 1e-9 {
+                    (offset.0 * dir.0 + offset.1 * dir.1) / d
+                } else {
+                    1.0
+                };
+                let priority = d * (1.0 + FACING_WEIGHT * (1.0 - cos));
+                let lod = lod_for(lods, d);
+                wanted.push((priority, ChunkRequest { chunk_x: cx, chunk_z: cz, lod }));
+            }
+        }
+        wanted.sort_by(|a, b| {
+            a.0.total_cmp(&b.0)
+                .then((a.1.chunk_z, a.1.chunk_x).cmp(&(b.1.chunk_z, b.1.chunk_x)))
+        });
+        let limit = if self.max_loads == 0 { usize::MAX } else { self.max_loads as usize };
+        for (_, request) in wanted.into_iter().take(limit) {
+            self.loaded.insert((request.chunk_x, request.chunk_z), request.lod);
+            plan.load.push(request);
+        }
+
+        plan.relod.sort_by_key(|r| (r.chunk_z, r.chunk_x));
+        plan
+    }
+
+    pub fn is_loaded(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.loaded.contains_key(&(chunk_x, chunk_z))
+    }
+}
+
+/// LOD level at `distance`: the number of thresholds it has passed.
+fn lod_for(thresholds: [f64; 2], distance: f64) -> u8 {
+    thresholds.iter().filter(|&&d| distance >= d).count() as u8
+}
+
+/// Distance in chunks from the camera to a chunk's centre.
+fn chunk_distance(cam: (f64, f64), (cx, cz): (i32, i32)) -> f64 {
+    let dx = cx as f64 + 0.5 - cam.0;
+    let dz = cz as f64 + 0.5 - cam.1;
+    (dx * dx + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planner() -> ChunkPlanner {
+        let mut planner = ChunkPlanner::new(17, 4.0);
+        planner.set_max_loads(0);
+        planner
+    }
+
+    #[test]
+    fn nearest_and_facing_chunks_first() {
+        let mut planner = planner();
+        // Camera at the centre of chunk (0, 0), looking along +x
+        let plan = planner.plan(8.0, 8.0, 1.0, 0.0);
+        assert_eq!(plan.load[0], ChunkRequest { chunk_x: 0, chunk_z: 0, lod: 0 });
+        assert_eq!((plan.load[1].chunk_x, plan.load[1].chunk_z), (1, 0));
+        let pos = |x, z| plan.load.iter().position(|r| (r.chunk_x, r.chunk_z) == (x, z)).unwrap();
+        assert!(pos(2, 0) < pos(-2, 0));
+        assert!(plan.load.iter().all(|r| chunk_distance((0.5, 0.5), (r.chunk_x, r.chunk_z)) <= 4.0));
+        assert!(plan.load.iter().any(|r| r.lod == 1));
+
+        // Everything is handed out once
+        let again = planner.plan(8.0, 8.0, 1.0, 0.0);
+        assert_eq!(again, ChunkPlan::default());
+    }
+
+    #[test]
+    fn max_loads_spreads_requests() {
+        let mut planner = planner();
+        planner.set_max_loads(5);
+        let first = planner.plan(0.0, 0.0, 0.0, 1.0);
+        let second = planner.plan(0.0, 0.0, 0.0, 1.0);
+        assert_eq!((first.load.len(), second.load.len()), (5, 5));
+        assert!(second.load.iter().all(|r| !first.load.contains(r)));
+    }
+
+    #[test]
+    fn unload_uses_hysteresis() {
+        let mut planner = planner();
+        planner.plan(8.0, 8.0, 0.0, 0.0);
+        assert!(planner.is_loaded(-3, 0));
+
+        // One chunk east: (-3, 0) is now 4.0 away, inside view distance anyway
+        let plan = planner.plan(8.0 + 16.0 * 1.5, 8.0, 0.0, 0.0);
+        assert!(plan.unload.is_empty());
+        assert!(planner.is_loaded(-3, 0));
+
+        // Far enough past view distance + margin
+        let plan = planner.plan(8.0 + 16.0 * 3.5, 8.0, 0.0, 0.0);
+        assert!(plan.unload.contains(&(-3, 0)));
+        assert!(!planner.is_loaded(-3, 0));
+    }
+
+    #[test]
+    fn view_distance_and_camera_are_bounded() {
+        for view in [f64::NAN, f64::INFINITY, 1e12] {
+            let mut unbounded = ChunkPlanner::new(17, view);
+            unbounded.set_max_loads(0);
+            assert!(unbounded.view_distance() <= MAX_VIEW_DISTANCE);
+            let side = 2.0 * unbounded.view_distance() + 3.0;
+            assert!(unbounded.plan(8.0, 8.0, 1.0, 0.0).load.len() as f64 <= side * side);
+        }
+        let mut p = planner();
+        p.set_view_distance(-3.0);
+        assert_eq!(p.view_distance(), 0.0);
+
+        let mut p = planner();
+        assert_eq!(p.plan(f64::NAN, 0.0, 1.0, 0.0), ChunkPlan::default());
+        assert!(!p.plan(1e300, -1e300, 1.0, 0.0).load.is_empty());
+    }
+
+    #[test]
+    fn lod_changes_do_not_thrash() {
+        let mut planner = planner();
+        planner.plan(8.0, 8.0, 0.0, 0.0);
+        // Chunk (3, 0) sits exactly 3 chunks out: LOD 1
+        assert_eq!(planner.loaded[&(3, 0)], 1);
+
+        // Small moves around the boundary keep its LOD
+        for step in 0..6 {
+            let x = 8.0 + if step % 2 == 0 { 3.0 } else { -3.0 };
+            let plan = planner.plan(x, 8.0, 0.0, 0.0);
+            assert!(!plan.relod.iter().any(|r| (r.chunk_x, r.chunk_z) == (3, 0)));
+        }
+
+        // A decisive move switches it
+        let plan = planner.plan(8.0 + 16.0, 8.0, 0.0, 0.0);
+        assert!(plan.relod.contains(&ChunkRequest { chunk_x: 3, chunk_z: 0, lod: 0 }));
+    }
+}
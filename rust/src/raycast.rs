@@ -0,0 +1,499 @@
+//! Ray vs heightfield intersection.
+//!
+//! The ray is walked through the chunk grid with a 2D DDA. Cached chunks are
+//! searched through a min/max height pyramid (`HeightQuadtree`), skipping
+//! any node the ray passes entirely above, and leaf cells are intersected
+//! exactly against the bilinear surface `TerrainSampler` interpolates.
+//! Chunks that aren't cached are ray-marched against the noise in
+//! `MARCH_STEP` increments and refined by bisection, so features thinner
+//! than a step can be missed there. Craters and stamps are looked up once
+//! per chunk the march crosses.
+
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::config::ConfigError;
+use crate::sample::{ChunkSource, TerrainSampler};
+use crate::{ChunkData, TerrainConfig};
+
+/// Ray-march step through uncached chunks, in world units.
+pub const MARCH_STEP: f64 = 0.5;
+/// Longest ray traced, in world units; longer `max_distance`s are clamped.
+pub const MAX_RAY_DISTANCE: f64 = 65536.0;
+const BISECT_STEPS: u32 = 24;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub normal_x: f64,
+    pub normal_y: f64,
+    pub normal_z: f64,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    /// `None` if a cached chunk stores an unknown biome id at the hit.
+    pub biome: Option<Biome>,
+    /// Distance along the (normalized) ray direction.
+    pub distance: f64,
+}
+
+/// Min/max heights over power-of-two blocks of cells, finest level first.
+#[derive(Clone)]
+pub struct HeightQuadtree {
+    levels: Vec<Level>,
+}
+
+#[derive(Clone)]
+struct Level {
+    cells_x: usize,
+    cells_z: usize,
+    bounds: Vec<(f32, f32)>,
+}
+
+impl HeightQuadtree {
+    pub fn build(chunk: &ChunkData) -> Self {
+        let width = chunk.width as usize;
+        let cells_x = width.saturating_sub(1).max(1);
+        let cells_z = (chunk.height as usize).saturating_sub(1).max(1);
+        let h = |x: usize, z: usize| {
+            let x = x.min(width - 1);
+            let z = z.min(chunk.height as usize - 1);
+            chunk.heightmap[z * width + x]
+        };
+
+        let mut bounds = Vec::with_capacity(cells_x * cells_z);
+        for z in 0..cells_z {
+            for x in 0..cells_x {
+                let c = [h(x, z), h(x + 1, z), h(x, z + 1), h(x + 1, z + 1)];
+                bounds.push((
+                    c.iter().copied().fold(f32::MAX, f32::min),
+                    c.iter().copied().fold(f32::MIN, f32::max),
+                ));
+            }
+        }
+        let mut levels = vec![Level { cells_x, cells_z, bounds }];
+
+        while let Some(prev) = levels.last().filter(|l| l.cells_x > 1 || l.cells_z > 1) {
+            let (cells_x, cells_z) = (prev.cells_x.div_ceil(2), prev.cells_z.div_ceil(2));
+            let mut bounds = vec![(f32::MAX, f32::MIN); cells_x * cells_z];
+            for z in 0..prev.cells_z {
+                for x in 0..prev.cells_x {
+                    let (lo, hi) = prev.bounds[z * prev.cells_x + x];
+                    let b = &mut bounds[(z / 2) * cells_x + x / 2];
+                    *b = (b.0.min(lo), b.1.max(hi));
+                }
+            }
+            levels.push(Level { cells_x, cells_z, bounds });
+        }
+        Self { levels }
+    }
+
+    /// Bytes held by the pyramid's levels.
+    pub fn byte_size(&self) -> usize {
+        self.levels.iter().map(|l| l.bounds.len() * std::mem::size_of::<(f32, f32)>()).sum()
+    }
+
+    /// (min, max) height over the whole chunk.
+    pub fn root(&self) -> (f32, f32) {
+        self.levels.last().map_or((0.0, 0.0), |l| l.bounds[0])
+    }
+}
+
+/// A ray in chunk-local coordinates (x, z in cells, y in world units).
+struct LocalRay {
+    origin: [f64; 3],
+    dir: [f64; 3],
+}
+
+impl LocalRay {
+    fn at(&self, t: f64) -> [f64; 3] {
+        std::array::from_fn(|i| self.origin[i] + self.dir[i] * t)
+    }
+
+    /// Clip `[t0, t1]` to the ray's span over the xz rectangle.
+    fn clip(&self, x: (f64, f64), z: (f64, f64), t0: f64, t1: f64) -> Option<(f64, f64)> {
+        let (mut ta, mut tb) = (t0, t1);
+        for (axis, (lo, hi)) in [(0, x), (2, z)] {
+            let (o, d) = (self.origin[axis], self.dir[axis]);
+            if d == 0.0 {
+                if o < lo || o > hi {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((lo - o) / d, (hi - o) / d);
+            ta = ta.max(a.min(b));
+            tb = tb.min(a.max(b));
+        }
+        (ta <= tb).then_some((ta, tb))
+    }
+}
+
+struct ChunkTrace<'a> {
+    chunk: &'a ChunkData,
+    tree: &'a HeightQuadtree,
+    ray: LocalRay,
+    height_multiplier: f64,
+}
+
+impl ChunkTrace<'_> {
+    fn node(&self, level: usize, ix: usize, iz: usize, t0: f64, t1: f64) -> Option<f64> {
+        let base = &self.tree.levels[0];
+        let size = 1usize << level;
+        let x = ((ix * size) as f64, ((ix + 1) * size).min(base.cells_x) as f64);
+        let z = ((iz * size) as f64, ((iz + 1) * size).min(base.cells_z) as f64);
+        let (ta, tb) = self.ray.clip(x, z, t0, t1)?;
+
+        let l = &self.tree.levels[level];
+        let top = l.bounds[iz * l.cells_x + ix].1 as f64 * self.height_multiplier;
+        let (ya, yb) = (self.ray.at(ta)[1], self.ray.at(tb)[1]);
+        if ya.min(yb) > top {
+            return None;
+        }
+        if level == 0 {
+            return self.cell(ix, iz, ta, tb);
+        }
+
+        let below = &self.tree.levels[level - 1];
+        let mut children: Vec<(f64, usize, usize)> = Vec::with_capacity(4);
+        for cz in [iz * 2, iz * 2 + 1] {
+            for cx in [ix * 2, ix * 2 + 1] {
+                if cx >= below.cells_x || cz >= below.cells_z {
+                    continue;
+                }
+                let child = 1usize << (level - 1);
+                let x = ((cx * child) as f64, ((cx + 1) * child).min(base.cells_x) as f64);
+                let z = ((cz * child) as f64, ((cz + 1) * child).min(base.cells_z) as f64);
+                if let Some((entry, _)) = self.ray.clip(x, z, ta, tb) {
+                    children.push((entry, cx, cz));
+                }
+            }
+        }
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        children
+            .into_iter()
+            .find_map(|(_, cx, cz)| self.node(level - 1, cx, cz, ta, tb))
+    }
+
+    /// Exact hit against one cell's bilinear patch. Along a line the patch
+    /// height is quadratic in `t`, so three samples determine it.
+    fn cell(&self, ix: usize, iz: usize, ta: f64, tb: f64) -> Option<f64> {
+        let width = self.chunk.width as usize;
+        let i = iz * width + ix;
+        let hm = &self.chunk.heightmap;
+        let c = [hm[i], hm[i + 1], hm[i + width], hm[i + width + 1]].map(f64::from);
+        let gap = |t: f64| {
+            let [x, y, z] = self.ray.at(t);
+            let (fx, fz) = ((x - ix as f64).clamp(0.0, 1.0), (z - iz as f64).clamp(0.0, 1.0));
+            let top = c[0] + (c[1] - c[0]) * fx;
+            let bottom = c[2] + (c[3] - c[2]) * fx;
+            y - (top + (bottom - top) * fz) * self.height_multiplier
+        };
+
+        let (f0, f1, f2) = (gap(ta), gap((ta + tb) * 0.5), gap(tb));
+        if f0 <= 0.0 {
+            return Some(ta);
+        }
+        let u = smallest_root(2.0 * f0 - 4.0 * f1 + 2.0 * f2, -3.0 * f0 + 4.0 * f1 - f2, f0)?;
+        Some(ta + (tb - ta) * u)
+    }
+}
+
+/// Smallest root of `a u^2 + b u + c` in `0..=1`.
+fn smallest_root(a: f64, b: f64, c: f64) -> Option<f64> {
+    let in_range = |u: f64| (0.0..=1.0).contains(&u).then_some(u);
+    if a.abs() < 1e-12 {
+        return if b == 0.0 { None } else { in_range(-c / b) };
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sq = disc.sqrt();
+    let (r0, r1) = ((-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a));
+    match (in_range(r0.min(r1)), in_range(r0.max(r1))) {
+        (Some(u), _) | (None, Some(u)) => Some(u),
+        _ => None,
+    }
+}
+
+/// First intersection of a ray with the terrain within `max_distance`,
+/// which must be finite and is clamped to `MAX_RAY_DISTANCE`.
+pub fn raycast(
+    sampler: &TerrainSampler,
+    chunks: &(impl ChunkSource + ?Sized),
+    origin: [f64; 3],
+    direction: [f64; 3],
+    max_distance: f64,
+) -> Option<RayHit> {
+    let len = direction.iter().map(|d| d * d).sum::<f64>().sqrt();
+    let finite = origin.iter().all(|v| v.is_finite()) && max_distance.is_finite();
+    if !(len > 0.0 && len.is_finite() && finite && max_distance > 0.0) {
+        return None;
+    }
+    // A ray skimming below the ceiling could otherwise be traced for ever
+    let max_distance = max_distance.min(MAX_RAY_DISTANCE);
+    let dir = direction.map(|d| d / len);
+    let edge = sampler.edge;
+    let ceiling = sampler.height_multiplier;
+
+    // DDA over chunk columns
+    let mut cell = [(origin[0] / edge).floor(), (origin[2] / edge).floor()];
+    let mut step = [0.0; 2];
+    let mut t_next = [f64::INFINITY; 2];
+    let mut t_delta = [f64::INFINITY; 2];
+    for (i, axis) in [0, 2].into_iter().enumerate() {
+        if dir[axis] != 0.0 {
+            step[i] = dir[axis].signum();
+            let boundary = (cell[i] + step[i].max(0.0)) * edge;
+            t_next[i] = (boundary - origin[axis]) / dir[axis];
+            t_delta[i] = edge / dir[axis].abs();
+        }
+    }
+
+    let mut t = 0.0;
+    while t < max_distance {
+        let t_exit = t_next[0].min(t_next[1]).min(max_distance);
+        let (chunk_x, chunk_z) = (cell[0] as i32, cell[1] as i32);
+
+        // Nothing to hit once the ray is above all terrain and not descending
+        if dir[1] >= 0.0 && origin[1] + dir[1] * t > ceiling {
+            return None;
+        }
+
+        let hit = match sampler.cached(chunks, chunk_x, chunk_z) {
+            Some(chunk) => {
+                let trace = ChunkTrace {
+                    chunk,
+                    tree: chunk.quadtree(),
+                    ray: LocalRay {
+                        origin: [origin[0] - cell[0] * edge, origin[1], origin[2] - cell[1] * edge],
+                        dir,
+                    },
+                    height_multiplier: ceiling,
+                };
+                trace.node(trace.tree.levels.len() - 1, 0, 0, t, t_exit)
+            }
+            None => march(sampler, chunks, origin, dir, t, t_exit),
+        };
+        if let Some(t_hit) = hit {
+            return Some(make_hit(sampler, chunks, origin, dir, t_hit));
+        }
+
+        t = t_exit;
+        let axis = if t_next[0] <= t_next[1] { 0 } else { 1 };
+        cell[axis] += step[axis];
+        t_next[axis] += t_delta[axis];
+    }
+    None
+}
+
+fn march(
+    sampler: &TerrainSampler,
+    chunks: &(impl ChunkSource + ?Sized),
+    origin: [f64; 3],
+    dir: [f64; 3],
+    t0: f64,
+    t1: f64,
+) -> Option<f64> {
+    let ceiling = sampler.height_multiplier;
+    if (origin[1] + dir[1] * t0).min(origin[1] + dir[1] * t1) > ceiling {
+        return None;
+    }
+    // The segment's xz box, padded against rounding in `origin + dir * t`
+    let (xa, xb) = (origin[0] + dir[0] * t0, origin[0] + dir[0] * t1);
+    let (za, zb) = (origin[2] + dir[2] * t0, origin[2] + dir[2] * t1);
+    let height = sampler.height_in(chunks, xa.min(xb) - 1.0, za.min(zb) - 1.0, xa.max(xb) + 1.0, za.max(zb) + 1.0);
+    let gap = |t: f64| {
+        let y = origin[1] + dir[1] * t;
+        y - height(origin[0] + dir[0] * t, origin[2] + dir[2] * t)
+    };
+
+    let mut prev = t0;
+    if gap(prev) <= 0.0 {
+        return Some(prev);
+    }
+    while prev < t1 {
+        let next = (prev + MARCH_STEP).min(t1);
+        if gap(next) <= 0.0 {
+            let (mut lo, mut hi) = (prev, next);
+            for _ in 0..BISECT_STEPS {
+                let mid = (lo + hi) * 0.5;
+                if gap(mid) <= 0.0 {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            return Some(hi);
+        }
+        prev = next;
+    }
+    None
+}
+
+fn make_hit(
+    sampler: &TerrainSampler,
+    chunks: &(impl ChunkSource + ?Sized),
+    origin: [f64; 3],
+    dir: [f64; 3],
+    t: f64,
+) -> RayHit {
+    let [x, y, z] = std::array::from_fn(|i| origin[i] + dir[i] * t);
+    let [normal_x, normal_y, normal_z] = sampler.normal(chunks, x, z);
+    RayHit {
+        x,
+        y,
+        z,
+        normal_x,
+        normal_y,
+        normal_z,
+        chunk_x: (x / sampler.edge).floor() as i32,
+        chunk_z: (z / sampler.edge).floor() as i32,
+        biome: sampler.biome(chunks, x, z),
+        distance: t,
+    }
+}
+
+/// Raycast against the un-eroded terrain (no cached chunks).
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn raycast_terrain(
+    config: &TerrainConfig,
+    origin_x: f64,
+    origin_y: f64,
+    origin_z: f64,
+    dir_x: f64,
+    dir_y: f64,
+    dir_z: f64,
+    max_distance: f64,
+) -> Result<Option<RayHit>, ConfigError> {
+    let sampler = TerrainSampler::new(config)?;
+    Ok(raycast(
+        &sampler,
+        &(),
+        [origin_x, origin_y, origin_z],
+        [dir_x, dir_y, dir_z],
+        max_distance,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_chunk;
+    use std::collections::HashMap;
+
+    fn setup() -> (TerrainConfig, TerrainSampler, HashMap<(i32, i32), ChunkData>) {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config.erosion_enabled = true;
+        config.erosion_iterations = 3000;
+        let sampler = TerrainSampler::new(&config).unwrap();
+        let mut chunks = HashMap::new();
+        for cz in -1..=1 {
+            for cx in -1..=1 {
+                chunks.insert((cx, cz), generate_chunk(&config, cx, cz).unwrap());
+            }
+        }
+        (config, sampler, chunks)
+    }
+
+    // First crossing found by fine stepping, for reference
+    fn brute_force(sampler: &TerrainSampler, chunks: &HashMap<(i32, i32), ChunkData>, o: [f64; 3], d: [f64; 3]) -> Option<f64> {
+        let len = d.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let d = d.map(|v| v / len);
+        (0..20000).map(|i| i as f64 * 0.005).find(|&t| {
+            o[1] + d[1] * t <= sampler.height(chunks, o[0] + d[0] * t, o[2] + d[2] * t)
+        })
+    }
+
+    #[test]
+    fn quadtree_root_matches_chunk_range() {
+        let (_, _, chunks) = setup();
+        let chunk = &chunks[&(0, 0)];
+        assert_eq!(chunk.quadtree().root(), (chunk.min_height, chunk.max_height));
+    }
+
+    #[test]
+    fn vertical_ray_hits_surface() {
+        let (config, sampler, chunks) = setup();
+        for (x, z) in [(3.25, 7.5), (-10.0, 20.75), (31.9, -0.1)] {
+            let hit = raycast(&sampler, &chunks, [x, 200.0, z], [0.0, -1.0, 0.0], 500.0).unwrap();
+            let ground = sampler.height(&chunks, x, z);
+            assert!((hit.y - ground).abs() < 1e-6, "{} vs {}", hit.y, ground);
+            assert_eq!((hit.chunk_x, hit.chunk_z), ((x / 32.0).floor() as i32, (z / 32.0).floor() as i32));
+            assert!(hit.normal_y > 0.0);
+            assert!(config.height_multiplier >= hit.y);
+        }
+    }
+
+    #[test]
+    fn oblique_rays_match_brute_force() {
+        let (config, sampler, chunks) = setup();
+        let y = config.height_multiplier * 0.9;
+        for i in 0..12 {
+            let angle = i as f64 * 0.5;
+            let o = [-20.0 + i as f64 * 3.0, y, -15.0 + i as f64 * 2.0];
+            let d = [angle.cos(), -0.35, angle.sin()];
+            let hit = raycast(&sampler, &chunks, o, d, 80.0).map(|h| h.distance);
+            match (hit, brute_force(&sampler, &chunks, o, d)) {
+                (Some(a), Some(b)) if b < 80.0 => assert!((a - b).abs() < 0.01, "ray {}: {} vs {}", i, a, b),
+                (None, None) => {}
+                (None, Some(b)) => assert!(b >= 80.0, "ray {} missed a hit at {}", i, b),
+                (Some(a), b) => panic!("ray {} hit at {} but reference {:?}", i, a, b),
+            }
+        }
+    }
+
+    #[test]
+    fn uncached_regions_fall_back_to_sampling() {
+        let (config, sampler, _) = setup();
+        let (x, z) = (500.5, -300.25);
+        let hit = raycast(&sampler, &(), [x, 200.0, z], [0.0, -1.0, 0.0], 500.0).unwrap();
+        assert!((hit.y - sampler.height(&(), x, z)).abs() < 1e-4);
+        let hit = raycast_terrain(&config, x, 200.0, z, 0.0, -1.0, 0.0, 500.0).unwrap();
+        assert!(hit.is_some());
+    }
+
+    #[test]
+    fn marching_craters_matches_point_heights() {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 33;
+        config.crater_density = 64.0;
+        config.crater_min_radius = 20.0;
+        let sampler = TerrainSampler::new(&config).unwrap();
+
+        // Box lookups agree with per-point ones
+        let height = sampler.height_in(&(), 900.0, 100.0, 1100.0, 300.0);
+        for i in 0..200 {
+            let (x, z) = (900.0 + i as f64 * 1.003, 100.0 + i as f64 * 0.97);
+            assert_eq!(height(x, z), sampler.height(&(), x, z));
+        }
+
+        let hit = raycast(&sampler, &(), [900.0, 100.0, 100.0], [1.0, -0.3, 0.8], 2000.0).unwrap();
+        assert!((hit.y - sampler.height(&(), hit.x, hit.z)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn unbounded_rays_terminate() {
+        let (config, sampler, chunks) = setup();
+        let origin = [0.5, config.height_multiplier * 0.9, 0.5];
+        for distance in [f64::INFINITY, f64::NAN] {
+            assert_eq!(raycast(&sampler, &chunks, origin, [1.0, 0.0, 0.0], distance), None);
+        }
+        // Level with the ceiling, a horizontal ray never hits; a huge
+        // distance is traced only as far as the clamp
+        let skim = [0.5, config.height_multiplier, 0.5];
+        assert_eq!(raycast(&sampler, &(), skim, [1.0, 0.0, 0.3], 1e300), None);
+    }
+
+    #[test]
+    fn upward_rays_miss() {
+        let (_, sampler, chunks) = setup();
+        assert_eq!(raycast(&sampler, &chunks, [0.0, 100.0, 0.0], [0.3, 1.0, 0.2], 1000.0), None);
+        assert_eq!(raycast(&sampler, &chunks, [0.0, 100.0, 0.0], [0.0, 0.0, 0.0], 1000.0), None);
+    }
+}
@@ -0,0 +1,271 @@
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::config::ConfigError;
+use crate::sample::TerrainSampler;
+use crate::{ChunkData, TerrainConfig};
+
+/// Upper bound on cells in a single region (4096 x 4096, ~150 MB of layers).
+pub const MAX_REGION_CELLS: u64 = 4096 * 4096;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RegionError {
+    #[error("region is empty: x {x0}..{x1}, z {z0}..{z1}")]
+    Empty { x0: i32, z0: i32, x1: i32, z1: i32 },
+    #[error("region of {cells} cells exceeds the limit of {max} cells")]
+    TooLarge { cells: u64, max: u64 },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl From<RegionError> for JsValue {
+    fn from(err: RegionError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+pub struct RegionData {
+    heightmap: Vec<f32>,
+    moisture_map: Vec<f32>,
+    biome_map: Vec<u8>,
+    material_map: Vec<u8>,
+    origin_x: i32,
+    origin_z: i32,
+    width: u32,
+    height: u32,
+    min_height: f32,
+    max_height: f32,
+}
+
+#[wasm_bindgen]
+impl RegionData {
+    #[wasm_bindgen(getter)]
+    pub fn origin_x(&self) -> i32 {
+        self.origin_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn origin_z(&self) -> i32 {
+        self.origin_z
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_height(&self) -> f32 {
+        self.min_height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_height(&self) -> f32 {
+        self.max_height
+    }
+
+    pub fn get_heightmap(&self) -> Vec<f32> {
+        self.heightmap.clone()
+    }
+
+    pub fn get_moisture_map(&self) -> Vec<f32> {
+        self.moisture_map.clone()
+    }
+
+    pub fn get_biome_map(&self) -> Vec<u8> {
+        self.biome_map.clone()
+    }
+
+    pub fn get_material_map(&self) -> Vec<u8> {
+        self.material_map.clone()
+    }
+}
+
+impl RegionData {
+    pub fn heightmap(&self) -> &[f32] {
+        &self.heightmap
+    }
+
+    pub fn moisture_map(&self) -> &[f32] {
+        &self.moisture_map
+    }
+
+    pub fn biome_map(&self) -> &[u8] {
+        &self.biome_map
+    }
+
+    pub fn material_map(&self) -> &[u8] {
+        &self.material_map
+    }
+}
+
+/// Generate the world cells `x0..x1` x `z0..z1` as one contiguous map.
+///
+/// Covering chunks are generated from one shared `TerrainSampler` and the
+/// duplicated shared edge (`edge = chunk_size - 1`) is taken from one chunk
+/// only, so cell `(wx, wz)` lands at `(wz - z0) * width + (wx - x0)`.
+#[wasm_bindgen]
+pub fn generate_region(
+    config: &TerrainConfig,
+    x0: i32,
+    z0: i32,
+    x1: i32,
+    z1: i32,
+) -> Result<RegionData, RegionError> {
+    if x1 <= x0 || z1 <= z0 {
+        return Err(RegionError::Empty { x0, z0, x1, z1 });
+    }
+    // Validates the config; built once and shared by every covering chunk
+    let sampler = TerrainSampler::new(config)?;
+
+    let width = (x1 as i64 - x0 as i64) as u64;
+    let height = (z1 as i64 - z0 as i64) as u64;
+    let cells = width * height;
+    if cells > MAX_REGION_CELLS {
+        return Err(RegionError::TooLarge {
+            cells,
+            max: MAX_REGION_CELLS,
+        });
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let size = config.chunk_size as usize;
+    let edge = config.chunk_size as i64 - 1;
+    let (x0, z0, x1, z1) = (x0 as i64, z0 as i64, x1 as i64, z1 as i64);
+
+    // The last cell may sit on a shared edge; reuse the previous chunk for it
+    let cx0 = x0.div_euclid(edge);
+    let cz0 = z0.div_euclid(edge);
+    let cx1 = (x1 - 2).div_euclid(edge).max(cx0);
+    let cz1 = (z1 - 2).div_euclid(edge).max(cz0);
+
+    let mut heightmap = vec![0.0f32; cells as usize];
+    let mut moisture_map = vec![0.0f32; cells as usize];
+    let mut biome_map = vec![0u8; cells as usize];
+    let mut material_map = vec![0u8; cells as usize];
+
+    let generate = |cx: i64, cz: i64| sampler.generate_chunk(config, cx as i32, cz as i32);
+
+    // Chunks are stitched a row at a time, so peak memory stays close to the
+    // output: with a rayon pool one row of chunks is generated in parallel,
+    // otherwise a single chunk is alive at once
+    for cz in cz0..=cz1 {
+        #[cfg(feature = "parallel")]
+        let pregenerated = if crate::threads::rayon_enabled() {
+            use rayon::prelude::*;
+            let row: Vec<_> = (cx0..=cx1).into_par_iter().map(|cx| generate(cx, cz)).collect();
+            Some(row)
+        } else {
+            None
+        };
+        #[cfg(not(feature = "parallel"))]
+        let pregenerated: Option<Vec<ChunkData>> = None;
+
+        let row: Box<dyn Iterator<Item = ChunkData>> = match pregenerated {
+            Some(chunks) => Box::new(chunks.into_iter()),
+            None => Box::new((cx0..=cx1).map(|cx| generate(cx, cz))),
+        };
+
+        for (cx, chunk) in (cx0..=cx1).zip(row) {
+            let (base_x, base_z) = (cx * edge, cz * edge);
+
+            let wx_start = x0.max(base_x);
+            let wx_end = x1.min(base_x + edge + 1);
+            let wz_start = z0.max(base_z);
+            let wz_end = z1.min(base_z + edge + 1);
+            let run = (wx_end - wx_start) as usize;
+
+            for wz in wz_start..wz_end {
+                let src = (wz - base_z) as usize * size + (wx_start - base_x) as usize;
+                let dst = (wz - z0) as usize * width + (wx_start - x0) as usize;
+                heightmap[dst..dst + run].copy_from_slice(&chunk.heightmap()[src..src + run]);
+                moisture_map[dst..dst + run].copy_from_slice(&chunk.moisture_map()[src..src + run]);
+                biome_map[dst..dst + run].copy_from_slice(&chunk.biome_map()[src..src + run]);
+                material_map[dst..dst + run].copy_from_slice(&chunk.material_map()[src..src + run]);
+            }
+        }
+    }
+
+    let mut min_h = f32::MAX;
+    let mut max_h = f32::MIN;
+    for &v in &heightmap {
+        if v < min_h { min_h = v; }
+        if v > max_h { max_h = v; }
+    }
+
+    Ok(RegionData {
+        heightmap,
+        moisture_map,
+        biome_map,
+        material_map,
+        origin_x: x0 as i32,
+        origin_z: z0 as i32,
+        width: width as u32,
+        height: height as u32,
+        min_height: min_h,
+        max_height: max_h,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::generate_chunk;
+
+    fn small_config() -> TerrainConfig {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        config
+    }
+
+    #[test]
+    fn region_matches_single_chunk() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, 2, -1).unwrap();
+        let region = generate_region(&config, 32, -16, 49, 1).unwrap();
+        assert_eq!((region.width, region.height), (17, 17));
+        assert_eq!(region.heightmap(), chunk.heightmap());
+        assert_eq!(region.biome_map(), chunk.biome_map());
+    }
+
+    #[test]
+    fn region_spans_chunk_seams() {
+        let config = small_config();
+        let region = generate_region(&config, -20, -5, 40, 30).unwrap();
+        let width = region.width as usize;
+        let edge: i32 = 16;
+        let mut chunks = std::collections::HashMap::new();
+
+        for wz in -5i32..30 {
+            for wx in -20i32..40 {
+                let (cx, cz) = (wx.div_euclid(edge), wz.div_euclid(edge));
+                let chunk = chunks
+                    .entry((cx, cz))
+                    .or_insert_with(|| generate_chunk(&config, cx, cz).unwrap());
+                let local = ((wz - cz * edge) * 17 + (wx - cx * edge)) as usize;
+                let idx = (wz + 5) as usize * width + (wx + 20) as usize;
+                assert_eq!(region.heightmap()[idx], chunk.heightmap()[local]);
+                assert_eq!(region.moisture_map()[idx], chunk.moisture_map()[local]);
+            }
+        }
+    }
+
+    #[test]
+    fn region_rejects_empty_and_oversized() {
+        let config = small_config();
+        assert!(matches!(
+            generate_region(&config, 5, 0, 5, 10),
+            Err(RegionError::Empty { .. })
+        ));
+        assert!(matches!(
+            generate_region(&config, 0, 0, 100_000, 100_000),
+            Err(RegionError::TooLarge { .. })
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+//! Point queries at arbitrary (fractional) world positions.
+//!
+//! Without cached chunks the noise pipeline is evaluated directly, so the
+//! result is the un-eroded terrain. When the chunk under a point is
+//! available its layers are interpolated instead, which includes erosion.
+//! Heights are in world units (`height * height_multiplier`).
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::biome::{classify_biome, Biome, MoistureGenerator};
+use crate::chunk::{build_chunk, continent_field, height_sampler, run_stages, NoiseSampler};
+use crate::noise::continent::ContinentField;
+use crate::pipeline::{Stage, StageHashes};
+use crate::config::ConfigError;
+use crate::{ChunkData, TerrainConfig};
+
+/// Chunks already generated for the config being sampled.
+pub trait ChunkSource {
+    fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkData>;
+}
+
+/// No cached chunks: every query evaluates the noise.
+impl ChunkSource for () {
+    fn chunk(&self, _chunk_x: i32, _chunk_z: i32) -> Option<&ChunkData> {
+        None
+    }
+}
+
+impl ChunkSource for ChunkData {
+    fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkData> {
+        (self.chunk_x == chunk_x && self.chunk_z == chunk_z).then_some(self)
+    }
+}
+
+impl ChunkSource for [ChunkData] {
+    fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkData> {
+        self.iter().find(|c| c.chunk_x == chunk_x && c.chunk_z == chunk_z)
+    }
+}
+
+impl ChunkSource for HashMap<(i32, i32), ChunkData> {
+    fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkData> {
+        self.get(&(chunk_x, chunk_z))
+    }
+}
+
+/// A world position resolved to a chunk cell and the fraction across it.
+struct CellPos {
+    chunk_x: i32,
+    chunk_z: i32,
+    x: usize,
+    z: usize,
+    fx: f64,
+    fz: f64,
+}
+
+/// Noise samplers built once and reused across queries. From JS, keep one
+/// per config instead of calling the `sample_*` functions, which rebuild it
+/// every time.
+#[wasm_bindgen]
+pub struct TerrainSampler {
+    height: Box<dyn NoiseSampler>,
+    moisture: Option<MoistureGenerator>,
+    continent: ContinentField,
+    pub(crate) edge: f64,
+    pub(crate) height_multiplier: f64,
+    sea_level: f32,
+}
+
+#[wasm_bindgen]
+impl TerrainSampler {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &TerrainConfig) -> Result<TerrainSampler, ConfigError> {
+        config.validate()?;
+        Ok(Self {
+            height: height_sampler(config),
+            continent: continent_field(config),
+            moisture: config
+                .moisture_enabled
+                .then(|| MoistureGenerator::new(config.seed, config.scale)),
+            edge: (config.chunk_size - 1) as f64,
+            height_multiplier: config.height_multiplier,
+            sea_level: config.sea_level as f32,
+        })
+    }
+
+    /// Un-eroded ground height in world units.
+    pub fn sample_height(&self, world_x: f64, world_z: f64) -> f64 {
+        self.height(&(), world_x, world_z)
+    }
+
+    pub fn sample_continentalness(&self, world_x: f64, world_z: f64) -> f64 {
+        self.continent.continentalness(world_x, world_z)
+    }
+
+    pub fn sample_biome(&self, world_x: f64, world_z: f64) -> Biome {
+        self.classify(&(), world_x, world_z)
+    }
+
+    /// Unit surface normal as `[x, y, z]`, y up.
+    pub fn sample_normal(&self, world_x: f64, world_z: f64) -> Vec<f32> {
+        self.normal(&(), world_x, world_z).iter().map(|&v| v as f32).collect()
+    }
+}
+
+impl TerrainSampler {
+
+    /// Generate a chunk reusing these samplers. `config` must be the one
+    /// this sampler was built from.
+    pub fn generate_chunk(&self, config: &TerrainConfig, chunk_x: i32, chunk_z: i32) -> ChunkData {
+        build_chunk(config, self.height.as_ref(), self.moisture.as_ref(), chunk_x, chunk_z)
+    }
+
+    /// Rerun the stages of `chunk` that `config` made stale; see
+    /// `pipeline`. Returns the stages that ran.
+    pub fn update_chunk(&self, config: &TerrainConfig, chunk: &mut ChunkData) -> Vec<Stage> {
+        let stages = chunk.stages.stale(&StageHashes::new(config));
+        if !stages.is_empty() {
+            run_stages(chunk, config, self.height.as_ref(), self.moisture.as_ref(), &stages);
+        }
+        stages
+    }
+
+    /// The cached chunk at these coords, if it has this config's layout.
+    pub(crate) fn cached<'a>(
+        &self,
+        chunks: &'a (impl ChunkSource + ?Sized),
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> Option<&'a ChunkData> {
+        let size = self.edge as u32 + 1;
+        chunks
+            .chunk(chunk_x, chunk_z)
+            .filter(|c| c.width == size && c.height == size)
+    }
+
+    fn locate(&self, world_x: f64, world_z: f64) -> CellPos {
+        let chunk_x = (world_x / self.edge).floor();
+        let chunk_z = (world_z / self.edge).floor();
+        let local_x = world_x - chunk_x * self.edge;
+        let local_z = world_z - chunk_z * self.edge;
+        // Keep the +1 neighbour inside the chunk, which stores edge + 1 samples
+        let max_cell = self.edge - 1.0;
+        let x = local_x.floor().clamp(0.0, max_cell);
+        let z = local_z.floor().clamp(0.0, max_cell);
+        CellPos {
+            chunk_x: chunk_x as i32,
+            chunk_z: chunk_z as i32,
+            x: x as usize,
+            z: z as usize,
+            fx: (local_x - x).clamp(0.0, 1.0),
+            fz: (local_z - z).clamp(0.0, 1.0),
+        }
+    }
+
+    /// The four samples around a cell: (x0z0, x1z0, x0z1, x1z1).
+    fn corners(layer: &[f32], width: usize, pos: &CellPos) -> [f64; 4] {
+        let i = pos.z * width + pos.x;
+        [layer[i], layer[i + 1], layer[i + width], layer[i + width + 1]].map(f64::from)
+    }
+
+    fn bilinear(c: [f64; 4], fx: f64, fz: f64) -> f64 {
+        let top = c[0] + (c[1] - c[0]) * fx;
+        let bottom = c[2] + (c[3] - c[2]) * fx;
+        top + (bottom - top) * fz
+    }
+
+    /// Normalized (0..1) height.
+    fn raw_height(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> f64 {
+        self.raw_height_or(chunks, world_x, world_z, |x, z| self.height.sample(x, z))
+    }
+
+    /// `raw_height`, with `noise` giving the height outside cached chunks.
+    fn raw_height_or(
+        &self,
+        chunks: &(impl ChunkSource + ?Sized),
+        world_x: f64,
+        world_z: f64,
+        noise: impl Fn(f64, f64) -> f64,
+    ) -> f64 {
+        let pos = self.locate(world_x, world_z);
+        match self.cached(chunks, pos.chunk_x, pos.chunk_z) {
+            Some(chunk) => {
+                let c = Self::corners(&chunk.heightmap, chunk.width as usize, &pos);
+                Self::bilinear(c, pos.fx, pos.fz)
+            }
+            // Round like the stored heightmap so both paths agree on the grid
+            None => noise(world_x, world_z) as f32 as f64,
+        }
+    }
+
+    fn moisture(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> f32 {
+        let Some(moisture) = &self.moisture else {
+            return 0.5;
+        };
+        let pos = self.locate(world_x, world_z);
+        match self.cached(chunks, pos.chunk_x, pos.chunk_z) {
+            Some(chunk) => {
+                let c = Self::corners(&chunk.moisture_map, chunk.width as usize, &pos);
+                Self::bilinear(c, pos.fx, pos.fz) as f32
+            }
+            None => moisture.sample(world_x, world_z),
+        }
+    }
+
+    /// Ground height in world units.
+    pub fn height(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> f64 {
+        self.raw_height(chunks, world_x, world_z) * self.height_multiplier
+    }
+
+    /// `height` for many points in the world box `x0..=x1` x `z0..=z1`;
+    /// craters and stamps reaching the box are looked up once.
+    pub(crate) fn height_in<'a, C: ChunkSource + ?Sized>(
+        &'a self,
+        chunks: &'a C,
+        x0: f64,
+        z0: f64,
+        x1: f64,
+        z1: f64,
+    ) -> impl Fn(f64, f64) -> f64 + 'a {
+        let noise = self.height.sampler_in(x0, z0, x1, z1);
+        move |x, z| self.raw_height_or(chunks, x, z, &noise) * self.height_multiplier
+    }
+
+    /// Biome under the point; cached chunks report their stored biome for
+    /// the nearest cell, or `None` if that id names no biome.
+    pub fn biome(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> Option<Biome> {
+        let pos = self.locate(world_x, world_z);
+        if let Some(chunk) = self.cached(chunks, pos.chunk_x, pos.chunk_z) {
+            let x = pos.x + (pos.fx >= 0.5) as usize;
+            let z = pos.z + (pos.fz >= 0.5) as usize;
+            return Biome::try_from(chunk.biome_map[z * chunk.width as usize + x]).ok();
+        }
+        Some(self.classify(chunks, world_x, world_z))
+    }
+
+    /// Biome classified from the height and moisture under the point.
+    fn classify(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> Biome {
+        let height = self.raw_height(chunks, world_x, world_z) as f32;
+        classify_biome(height, self.moisture(chunks, world_x, world_z), self.sea_level)
+    }
+
+    /// Unit surface normal `[x, y, z]` in world units (y up).
+    pub fn normal(&self, chunks: &(impl ChunkSource + ?Sized), world_x: f64, world_z: f64) -> [f64; 3] {
+        let pos = self.locate(world_x, world_z);
+        let (dx, dz) = match self.cached(chunks, pos.chunk_x, pos.chunk_z) {
+            Some(chunk) => {
+                // Analytic gradient of the bilinear patch
+                let c = Self::corners(&chunk.heightmap, chunk.width as usize, &pos);
+                let dx = (c[1] - c[0]) * (1.0 - pos.fz) + (c[3] - c[2]) * pos.fz;
+                let dz = (c[2] - c[0]) * (1.0 - pos.fx) + (c[3] - c[1]) * pos.fx;
+                (dx, dz)
+            }
+            None => {
+                // Central differences, one cell apart
+                let h = |x, z| self.height.sample(x, z);
+                let dx = h(world_x + 0.5, world_z) - h(world_x - 0.5, world_z);
+                let dz = h(world_x, world_z + 0.5) - h(world_x, world_z - 0.5);
+                (dx, dz)
+            }
+        };
+        let (nx, nz) = (-dx * self.height_multiplier, -dz * self.height_multiplier);
+        let len = (nx * nx + 1.0 + nz * nz).sqrt();
+        [nx / len, 1.0 / len, nz / len]
+    }
+}
+
+// One-shot queries: each builds the samplers again, so prefer a
+// `TerrainSampler` for more than a handful of points.
+
+/// Un-eroded ground height in world units at a fractional world position.
+#[wasm_bindgen]
+pub fn sample_height(config: &TerrainConfig, world_x: f64, world_z: f64) -> Result<f64, ConfigError> {
+    Ok(TerrainSampler::new(config)?.sample_height(world_x, world_z))
+}
+
+/// Continentalness in `0..=1` (land above `continent_threshold`); 0.5
+/// everywhere with continents off.
+#[wasm_bindgen]
+pub fn sample_continentalness(config: &TerrainConfig, world_x: f64, world_z: f64) -> Result<f64, ConfigError> {
+    config.validate()?;
+    Ok(continent_field(config).continentalness(world_x, world_z))
+}
+
+#[wasm_bindgen]
+pub fn sample_biome(config: &TerrainConfig, world_x: f64, world_z: f64) -> Result<Biome, ConfigError> {
+    Ok(TerrainSampler::new(config)?.sample_biome(world_x, world_z))
+}
+
+/// Unit surface normal as `[x, y, z]`, y up.
+#[wasm_bindgen]
+pub fn sample_normal(config: &TerrainConfig, world_x: f64, world_z: f64) -> Result<Vec<f32>, ConfigError> {
+    Ok(TerrainSampler::new(config)?.sample_normal(world_x, world_z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_chunk;
+
+    fn small_config() -> TerrainConfig {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        config
+    }
+
+    #[test]
+    fn uncached_matches_generated_grid() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, -1, 2).unwrap();
+        let sampler = TerrainSampler::new(&config).unwrap();
+        for z in 0..17 {
+            for x in 0..17 {
+                let (wx, wz) = ((-16 + x) as f64, (32 + z) as f64);
+                let idx = (z * 17 + x) as usize;
+                let expected = chunk.heightmap[idx] as f64 * config.height_multiplier;
+                assert_eq!(sampler.height(&(), wx, wz), expected);
+                assert_eq!(sampler.biome(&(), wx, wz).map(|b| b as u8), Some(chunk.biome_map[idx]));
+            }
+        }
+    }
+
+    #[test]
+    fn cached_chunks_include_erosion() {
+        let mut config = small_config();
+        config.erosion_enabled = true;
+        config.erosion_iterations = 2000;
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let sampler = TerrainSampler::new(&config).unwrap();
+
+        let eroded = sampler.height(&chunk, 5.0, 7.0);
+        assert_eq!(eroded, chunk.heightmap[7 * 17 + 5] as f64 * config.height_multiplier);
+
+        // Mid-cell values interpolate the four neighbours
+        let mid = sampler.height(&chunk, 5.5, 7.5) / config.height_multiplier;
+        let around = [(5, 7), (6, 7), (5, 8), (6, 8)].map(|(x, z)| chunk.heightmap[z * 17 + x] as f64);
+        let mean = around.iter().sum::<f64>() / 4.0;
+        assert!((mid - mean).abs() < 1e-6);
+
+        // Outside the cached chunk, fall back to the noise
+        assert_eq!(sampler.height(&chunk, 20.0, 3.0), sampler.height(&(), 20.0, 3.0));
+    }
+
+    #[test]
+    fn corrupt_cached_biomes_are_not_reported() {
+        let config = small_config();
+        let mut chunk = generate_chunk(&config, 0, 0).unwrap();
+        let sampler = TerrainSampler::new(&config).unwrap();
+        chunk.biome_map[3 * 17 + 2] = 200;
+        assert_eq!(sampler.biome(&chunk, 2.0, 3.0), None);
+        assert!(sampler.biome(&chunk, 9.0, 9.0).is_some());
+        assert_eq!(sampler.sample_biome(2.0, 3.0), classify_biome(
+            (sampler.sample_height(2.0, 3.0) / config.height_multiplier) as f32,
+            MoistureGenerator::new(config.seed, config.scale).sample(2.0, 3.0),
+            config.sea_level as f32,
+        ));
+    }
+
+    #[test]
+    fn normals_are_unit_and_point_up() {
+        let config = small_config();
+        let chunk = generate_chunk(&config, 0, 0).unwrap();
+        let sampler = TerrainSampler::new(&config).unwrap();
+        for i in 0..40 {
+            let (x, z) = (i as f64 * 0.37, 15.9 - i as f64 * 0.29);
+            for n in [sampler.normal(&(), x, z), sampler.normal(&chunk, x, z)] {
+                let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                assert!((len - 1.0).abs() < 1e-9);
+                assert!(n[1] > 0.0);
+            }
+        }
+    }
+}
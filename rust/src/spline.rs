@@ -0,0 +1,260 @@
+//! Height remapping with nested cubic splines.
+//!
+//! A spline maps one input (the noise height, continentalness or the
+//! erosion field) to a height through cubic Hermite segments. A point's
+//! value can itself be a spline over another input, so e.g. the height
+//! curve can flatten into plains where erosion is high and sharpen into
+//! cliffs inland.
+
+use noise::{NoiseFn, Simplex};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::hash::Fnv64;
+
+/// Deepest allowed nesting of splines inside spline points.
+pub const MAX_SPLINE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplineInput {
+    /// Noise height after continent shaping, `0..=1`.
+    Height,
+    /// Continent field, `0..=1`; 0.5 with continents off.
+    Continentalness,
+    /// Low-frequency erosion field, `0..=1`: high values flatten terrain.
+    Erosion,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SplineValue {
+    Constant(f64),
+    Spline(Box<HeightSpline>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplinePoint {
+    pub location: f64,
+    pub value: SplineValue,
+    /// Slope at this point; also the slope past the end points.
+    #[serde(default)]
+    pub derivative: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeightSpline {
+    pub input: SplineInput,
+    /// Ordered by strictly increasing `location`.
+    pub points: Vec<SplinePoint>,
+}
+
+/// Field values at one position.
+#[derive(Debug, Clone, Copy)]
+pub struct SplineInputs {
+    pub height: f64,
+    pub continentalness: f64,
+    pub erosion: f64,
+}
+
+impl SplineValue {
+    fn eval(&self, inputs: &SplineInputs) -> f64 {
+        match self {
+            SplineValue::Constant(v) => *v,
+            SplineValue::Spline(spline) => spline.eval(inputs),
+        }
+    }
+}
+
+impl HeightSpline {
+    /// Evaluate at `inputs`, extending linearly past the end points.
+    pub fn eval(&self, inputs: &SplineInputs) -> f64 {
+        let x = match self.input {
+            SplineInput::Height => inputs.height,
+            SplineInput::Continentalness => inputs.continentalness,
+            SplineInput::Erosion => inputs.erosion,
+        };
+        let points = &self.points;
+        let next = points.partition_point(|p| p.location <= x);
+        if next == 0 || next == points.len() {
+            let end = &points[next.saturating_sub(1)];
+            return end.value.eval(inputs) + end.derivative * (x - end.location);
+        }
+
+        let (p0, p1) = (&points[next - 1], &points[next]);
+        let width = p1.location - p0.location;
+        let t = (x - p0.location) / width;
+        let (t2, t3) = (t * t, t * t * t);
+        let (v0, v1) = (p0.value.eval(inputs), p1.value.eval(inputs));
+        (2.0 * t3 - 3.0 * t2 + 1.0) * v0
+            + (t3 - 2.0 * t2 + t) * width * p0.derivative
+            + (-2.0 * t3 + 3.0 * t2) * v1
+            + (t3 - t2) * width * p1.derivative
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.validate_at(1)
+    }
+
+    fn validate_at(&self, depth: usize) -> Result<(), ConfigError> {
+        if depth > MAX_SPLINE_DEPTH {
+            return Err(ConfigError::HeightSpline("nesting is too deep"));
+        }
+        if self.points.is_empty() {
+            return Err(ConfigError::HeightSpline("has a spline with no points"));
+        }
+        for (i, point) in self.points.iter().enumerate() {
+            if !(point.location.is_finite() && point.derivative.is_finite()) {
+                return Err(ConfigError::HeightSpline("locations and derivatives must be finite"));
+            }
+            if i > 0 && point.location <= self.points[i - 1].location {
+                return Err(ConfigError::HeightSpline("locations must be strictly increasing"));
+            }
+            match &point.value {
+                SplineValue::Constant(v) if !v.is_finite() => {
+                    return Err(ConfigError::HeightSpline("values must be finite"));
+                }
+                SplineValue::Constant(_) => {}
+                SplineValue::Spline(spline) => spline.validate_at(depth + 1)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn hash_into(&self, h: &mut Fnv64) {
+        h.write(&[self.input as u8]);
+        h.write_u32(self.points.len() as u32);
+        for point in &self.points {
+            h.write_f64(point.location);
+            h.write_f64(point.derivative);
+            match &point.value {
+                SplineValue::Constant(v) => {
+                    h.write(&[0]);
+                    h.write_f64(*v);
+                }
+                SplineValue::Spline(spline) => {
+                    h.write(&[1]);
+                    spline.hash_into(h);
+                }
+            }
+        }
+    }
+}
+
+/// The spline's `erosion` input: low-frequency fBm in `0..=1`. Unrelated to
+/// hydraulic erosion, which runs later on the remapped heights.
+pub struct ErosionField {
+    noise: Simplex,
+    frequency: f64,
+}
+
+impl ErosionField {
+    /// `frequency` is in cycles per world unit.
+    pub fn new(seed: u64, frequency: f64) -> Self {
+        let s = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            noise: Simplex::new(s.wrapping_add(6000)),
+            frequency,
+        }
+    }
+
+    pub fn sample(&self, world_x: f64, world_z: f64) -> f64 {
+        let (x, z) = (world_x * self.frequency, world_z * self.frequency);
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut value = 0.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..3 {
+            value += self.noise.get([x * frequency, z * frequency]) * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        ((value / max_amplitude + 1.0) * 0.5).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(location: f64, value: f64, derivative: f64) -> SplinePoint {
+        SplinePoint {
+            location,
+            value: SplineValue::Constant(value),
+            derivative,
+        }
+    }
+
+    fn inputs(height: f64, erosion: f64) -> SplineInputs {
+        SplineInputs {
+            height,
+            continentalness: 0.5,
+            erosion,
+        }
+    }
+
+    #[test]
+    fn hermite_segments_hit_points_and_extend_linearly() {
+        let spline = HeightSpline {
+            input: SplineInput::Height,
+            points: vec![point(0.2, 0.1, 0.5), point(0.5, 0.4, 0.0), point(0.8, 0.9, 1.0)],
+        };
+        assert_eq!(spline.eval(&inputs(0.2, 0.0)), 0.1);
+        assert_eq!(spline.eval(&inputs(0.5, 0.0)), 0.4);
+        assert_eq!(spline.eval(&inputs(0.8, 0.0)), 0.9);
+        assert!((spline.eval(&inputs(0.0, 0.0)) - 0.0).abs() < 1e-12);
+        assert!((spline.eval(&inputs(1.0, 0.0)) - 1.1).abs() < 1e-12);
+        // Zero slope at 0.5: flat nearby
+        let near = spline.eval(&inputs(0.51, 0.0));
+        assert!((near - 0.4).abs() < 5e-3);
+    }
+
+    #[test]
+    fn nested_splines_blend_by_their_input() {
+        let plains = HeightSpline {
+            input: SplineInput::Height,
+            points: vec![point(0.0, 0.3, 0.2), point(1.0, 0.5, 0.2)],
+        };
+        let spline = HeightSpline {
+            input: SplineInput::Erosion,
+            points: vec![
+                point(0.0, 0.9, 0.0),
+                SplinePoint {
+                    location: 1.0,
+                    value: SplineValue::Spline(Box::new(plains)),
+                    derivative: 0.0,
+                },
+            ],
+        };
+        assert_eq!(spline.eval(&inputs(0.7, 0.0)), 0.9);
+        assert!((spline.eval(&inputs(0.5, 1.0)) - 0.4).abs() < 1e-12);
+        let mid = spline.eval(&inputs(0.5, 0.5));
+        assert!((mid - 0.65).abs() < 1e-12);
+    }
+
+    #[test]
+    fn validation_and_json() {
+        let json = r#"{"input": "continentalness", "points": [
+            {"location": 0.0, "value": 0.1},
+            {"location": 0.6, "value": {"input": "height", "points": [{"location": 0.5, "value": 0.5, "derivative": 1.0}]}}
+        ]}"#;
+        let spline: HeightSpline = serde_json::from_str(json).unwrap();
+        assert_eq!(spline.validate(), Ok(()));
+        assert_eq!(spline.points[0].derivative, 0.0);
+
+        let mut unordered = spline.clone();
+        unordered.points.swap(0, 1);
+        assert_eq!(
+            unordered.validate(),
+            Err(ConfigError::HeightSpline("locations must be strictly increasing"))
+        );
+        let empty = HeightSpline {
+            input: SplineInput::Height,
+            points: vec![],
+        };
+        assert!(empty.validate().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Terraced heights and stratified rock.
+//!
+//! Terracing quantises heights into flat treads joined by risers. A
+//! low-frequency noise nudges the tread heights so steps don't follow exact
+//! contour lines. Strata stack rock layers by altitude; each layer's
+//! hardness slows hydraulic erosion, so hard caprock survives as mesa tops
+//! while the softer bands beneath wear back into slopes.
+
+use noise::{NoiseFn, Simplex};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::hash::Fnv64;
+
+pub const MAX_TERRACE_STEPS: u32 = 64;
+pub const MAX_STRATA: usize = 64;
+/// Thinnest allowed layer, in normalized height.
+pub const MIN_LAYER_THICKNESS: f64 = 1e-3;
+/// Cycles per world unit of the tread jitter noise.
+const JITTER_FREQUENCY: f64 = 0.004;
+
+/// Height terracing: `steps` treads over `0..=1`.
+pub struct Terrace {
+    steps: f64,
+    smoothness: f64,
+    jitter: f64,
+    noise: Simplex,
+}
+
+impl Terrace {
+    /// `smoothness` is the share of each step taken by its riser (0 gives
+    /// vertical cliffs); `jitter` moves treads up or down by up to half that
+    /// share of a step.
+    pub fn new(seed: u64, steps: u32, smoothness: f64, jitter: f64) -> Self {
+        let s = (seed & 0xFFFFFFFF) as u32;
+        Self {
+            steps: steps as f64,
+            smoothness,
+            jitter,
+            noise: Simplex::new(s.wrapping_add(7000)),
+        }
+    }
+
+    pub fn apply(&self, world_x: f64, world_z: f64, height: f64) -> f64 {
+        let offset = if self.jitter > 0.0 {
+            let n = self.noise.get([world_x * JITTER_FREQUENCY, world_z * JITTER_FREQUENCY]);
+            n * self.jitter * 0.5
+        } else {
+            0.0
+        };
+        let t = height * self.steps + offset;
+        let step = t.floor();
+        let f = t - step;
+        // Flat tread, then the riser over the last `smoothness` of the step
+        let rise = if self.smoothness > 0.0 {
+            let r = ((f - 1.0 + self.smoothness) / self.smoothness).clamp(0.0, 1.0);
+            r * r * (3.0 - 2.0 * r)
+        } else {
+            0.0
+        };
+        ((step + rise - offset) / self.steps).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RockLayer {
+    /// Rock type id written to the chunk's material map.
+    pub rock: u8,
+    /// In normalized height.
+    pub thickness: f64,
+    /// 0 erodes at the configured rate, 1 not at all.
+    #[serde(default)]
+    pub hardness: f64,
+}
+
+/// Rock layers stacked bottom to top from height 0, repeating upwards if
+/// they end below 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Strata {
+    pub layers: Vec<RockLayer>,
+}
+
+impl Strata {
+    /// The layer exposed at a normalized height.
+    pub fn layer_at(&self, height: f64) -> &RockLayer {
+        let period: f64 = self.layers.iter().map(|l| l.thickness).sum();
+        let mut h = height.max(0.0) % period;
+        for layer in &self.layers {
+            if h < layer.thickness {
+                return layer;
+            }
+            h -= layer.thickness;
+        }
+        // Rounding at the top of the period
+        &self.layers[self.layers.len() - 1]
+    }
+
+    pub fn rock_at(&self, height: f64) -> u8 {
+        self.layer_at(height).rock
+    }
+
+    pub fn hardness_at(&self, height: f64) -> f64 {
+        self.layer_at(height).hardness
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.layers.is_empty() || self.layers.len() > MAX_STRATA {
+            return Err(ConfigError::Strata("must have 1..=64 layers"));
+        }
+        for layer in &self.layers {
+            if !(layer.thickness.is_finite() && layer.thickness >= MIN_LAYER_THICKNESS) {
+                return Err(ConfigError::Strata("thickness must be at least 0.001"));
+            }
+            if !(0.0..=1.0).contains(&layer.hardness) {
+                return Err(ConfigError::Strata("hardness must be in 0..=1"));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn hash_into(&self, h: &mut Fnv64) {
+        h.write_u32(self.layers.len() as u32);
+        for layer in &self.layers {
+            h.write(&[layer.rock]);
+            h.write_f64(layer.thickness);
+            h.write_f64(layer.hardness);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terraces_flatten_into_steps() {
+        let cliffs = Terrace::new(1, 4, 0.0, 0.0);
+        assert_eq!(cliffs.apply(0.0, 0.0, 0.1), 0.0);
+        assert_eq!(cliffs.apply(0.0, 0.0, 0.3), 0.25);
+        assert_eq!(cliffs.apply(0.0, 0.0, 0.49), 0.25);
+        assert_eq!(cliffs.apply(0.0, 0.0, 1.0), 1.0);
+
+        // Risers are smooth and never reverse
+        let smooth = Terrace::new(1, 5, 0.4, 0.0);
+        let mut prev = 0.0;
+        for i in 0..=1000 {
+            let h = smooth.apply(0.0, 0.0, i as f64 / 1000.0);
+            assert!(h >= prev && h - prev < 0.01, "{} after {}", h, prev);
+            prev = h;
+        }
+    }
+
+    #[test]
+    fn jitter_moves_treads_between_places() {
+        let terrace = Terrace::new(9, 6, 0.1, 0.8);
+        let treads: Vec<f64> = (0..20).map(|i| terrace.apply(i as f64 * 97.0, 0.0, 0.45)).collect();
+        assert!(treads.iter().any(|&t| t != treads[0]));
+        // At most one step below, plus half the jitter
+        assert!(treads.iter().all(|&t| (t - 0.45).abs() <= (1.0 + 0.8 / 2.0) / 6.0));
+
+        // Without risers every tread sits within half the jitter of a step
+        let sharp = Terrace::new(9, 6, 0.0, 0.8);
+        for i in 0..200 {
+            let level = sharp.apply(i as f64 * 97.0, i as f64 * 31.0, 0.45) * 6.0;
+            assert!((level - level.round()).abs() <= 0.4 + 1e-9, "tread at {level}");
+        }
+    }
+
+    #[test]
+    fn layers_repeat_and_validate() {
+        let strata: Strata = serde_json::from_str(
+            r#"{"layers": [
+                {"rock": 1, "thickness": 0.1, "hardness": 0.2},
+                {"rock": 2, "thickness": 0.05, "hardness": 0.9}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(strata.validate(), Ok(()));
+        assert_eq!(strata.rock_at(0.0), 1);
+        assert_eq!(strata.rock_at(0.12), 2);
+        assert_eq!(strata.hardness_at(0.12), 0.9);
+        // Period 0.15, so 0.32 is 0.02 into the third repeat
+        assert_eq!(strata.rock_at(0.32), 1);
+
+        let thin = Strata {
+            layers: vec![RockLayer { rock: 0, thickness: 0.0, hardness: 0.0 }],
+        };
+        assert!(thin.validate().is_err());
+        assert!(Strata { layers: vec![] }.validate().is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Thread pool plumbing for the `parallel` and `wasm-threads` features.
+//!
+//! Native builds use rayon's default global pool. A threaded WASM build
+//! (atomics + shared memory, see `build.sh --threads`) has no OS threads, so
+//! `init_thread_pool` starts Web Workers that instantiate this module on the
+//! same shared memory and hands each one a rayon `ThreadBuilder`. Until that
+//! pool's workers are all running every parallel path falls back to running
+//! serially.
+//!
+//! Hydraulic erosion runs droplets in row bands that can't interact, every
+//! other band at once, so one large run uses the whole pool and gives the
+//! same heightmap as running without it.
+
+#[cfg(all(feature = "wasm-threads", target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "wasm-threads")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(all(feature = "wasm-threads", target_arch = "wasm32"))]
+static POOL_READY: AtomicBool = AtomicBool::new(false);
+
+/// Whether parallel paths may use the rayon global pool.
+pub(crate) fn rayon_enabled() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        #[cfg(feature = "wasm-threads")]
+        return POOL_READY.load(Ordering::Acquire);
+        #[cfg(not(feature = "wasm-threads"))]
+        return false;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    true
+}
+
+#[cfg(feature = "wasm-threads")]
+#[wasm_bindgen(module = "/js/poolWorker.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = startPoolWorker)]
+    fn start_pool_worker(module: JsValue, memory: JsValue, builder: usize);
+
+    #[wasm_bindgen(js_name = poolWorkersReady)]
+    fn pool_workers_ready() -> js_sys::Promise;
+}
+
+/// Build the global rayon pool from `num_threads` Web Workers.
+///
+/// Resolves once every worker is running; only then do parallel paths start
+/// using the pool, so generating before the promise settles is safe but
+/// serial. Call it from a worker, not the page's main thread: rayon blocks
+/// callers with `Atomics.wait`, which the main thread forbids. Requires a
+/// cross-origin isolated page.
+#[cfg(feature = "wasm-threads")]
+#[wasm_bindgen]
+pub fn init_thread_pool(num_threads: usize) -> Result<js_sys::Promise, JsError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads.max(1))
+        .spawn_handler(|thread| {
+            let builder = Box::into_raw(Box::new(thread)) as usize;
+            start_pool_worker(wasm_bindgen::module(), wasm_bindgen::memory(), builder);
+            Ok(())
+        })
+        .build_global()
+        .map_err(|err| JsError::new(&err.to_string()))?;
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let ready = Closure::once(|_: JsValue| POOL_READY.store(true, Ordering::Release));
+        let promise = pool_workers_ready().then(&ready);
+        ready.forget();
+        Ok(promise)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    Ok(pool_workers_ready())
+}
+
+/// Entry point of a pool worker; runs rayon's worker loop and never returns
+/// while the pool is alive.
+#[cfg(feature = "wasm-threads")]
+#[wasm_bindgen]
+pub fn run_pool_worker(builder: usize) {
+    // SAFETY: `builder` is the pointer produced by `Box::into_raw` in
+    // `init_thread_pool`, and each pointer is sent to exactly one worker
+    let thread = unsafe { Box::from_raw(builder as *mut rayon::ThreadBuilder) };
+    thread.run();
+}
@@ -0,0 +1,396 @@
+//! A long-lived generator: one validated config, samplers built once, and
+//! an LRU cache of generated chunks bounded by a memory budget.
+//!
+//! Config changes keep cached chunks; each is brought up to date by
+//! rerunning only its stale stages (see `pipeline`) when next loaded.
+//! Until then queries treat it as uncached.
+
+use std::collections::{BTreeMap, HashMap};
+
+use wasm_bindgen::prelude::*;
+
+use crate::biome::Biome;
+use crate::config::ConfigError;
+use crate::pipeline::StageHashes;
+use crate::raycast::{raycast, RayHit};
+use crate::sample::{ChunkSource, TerrainSampler};
+use crate::{ChunkData, TerrainConfig};
+
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+struct CacheEntry {
+    chunk: ChunkData,
+    last_used: u64,
+    bytes: usize,
+}
+
+/// Chunks keyed by coords, evicted least recently used first.
+struct ChunkCache {
+    entries: HashMap<(i32, i32), CacheEntry>,
+    // last_used tick -> key, oldest first
+    order: BTreeMap<u64, (i32, i32)>,
+    tick: u64,
+    bytes: usize,
+    budget: usize,
+}
+
+impl ChunkCache {
+    fn new(budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            budget,
+        }
+    }
+
+    /// Mark a chunk as used. Returns whether it was cached.
+    fn touch(&mut self, key: (i32, i32)) -> bool {
+        self.tick += 1;
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return false;
+        };
+        self.order.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.order.insert(self.tick, key);
+        true
+    }
+
+    fn insert(&mut self, key: (i32, i32), chunk: ChunkData) {
+        self.remove(key);
+        self.tick += 1;
+        let bytes = chunk.byte_size();
+        self.bytes += bytes;
+        self.order.insert(self.tick, key);
+        self.entries.insert(
+            key,
+            CacheEntry {
+                chunk,
+                last_used: self.tick,
+                bytes,
+            },
+        );
+        self.evict();
+    }
+
+    fn remove(&mut self, key: (i32, i32)) -> bool {
+        match self.entries.remove(&key) {
+            Some(entry) => {
+                self.order.remove(&entry.last_used);
+                self.bytes -= entry.bytes;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Recount the bytes of chunks that grew or shrank in place, such as
+    /// by building their raycast quadtree, then evict to the budget.
+    fn recount(&mut self) {
+        let mut bytes = 0;
+        for entry in self.entries.values_mut() {
+            entry.bytes = entry.chunk.byte_size();
+            bytes += entry.bytes;
+        }
+        self.bytes = bytes;
+        self.evict();
+    }
+
+    /// Drop old chunks until under budget, always keeping the newest one.
+    fn evict(&mut self) {
+        while self.bytes > self.budget && self.entries.len() > 1 {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.bytes;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+}
+
+#[wasm_bindgen]
+pub struct World {
+    config: TerrainConfig,
+    config_hash: u64,
+    stages: StageHashes,
+    sampler: TerrainSampler,
+    cache: ChunkCache,
+}
+
+#[wasm_bindgen]
+impl World {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &TerrainConfig) -> Result<World, ConfigError> {
+        Ok(Self {
+            sampler: TerrainSampler::new(config)?,
+            config: config.clone(),
+            config_hash: config.config_hash(),
+            stages: StageHashes::new(config),
+            cache: ChunkCache::new(DEFAULT_MEMORY_BUDGET),
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn config(&self) -> TerrainConfig {
+        self.config.clone()
+    }
+
+    /// Replace the config. Returns true if it changed; an invalid config
+    /// leaves the world untouched. Cached chunks are kept for incremental
+    /// updates unless the chunk size changed.
+    pub fn set_config(&mut self, config: &TerrainConfig) -> Result<bool, ConfigError> {
+        let hash = config.config_hash();
+        // height_multiplier isn't hashed but scales sampled heights
+        if hash == self.config_hash && config.height_multiplier == self.config.height_multiplier {
+            return Ok(false);
+        }
+        self.sampler = TerrainSampler::new(config)?;
+        if config.chunk_size != self.config.chunk_size {
+            self.cache.clear();
+        }
+        self.config = config.clone();
+        self.config_hash = hash;
+        self.stages = StageHashes::new(config);
+        Ok(true)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn memory_budget(&self) -> usize {
+        self.cache.budget
+    }
+
+    /// Cap on cached chunk bytes, raycast quadtrees included; shrinking it
+    /// evicts immediately.
+    #[wasm_bindgen(setter)]
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.cache.budget = bytes;
+        self.cache.evict();
+    }
+
+    /// Bytes held by cached chunk layers and their raycast quadtrees.
+    #[wasm_bindgen(getter)]
+    pub fn memory_usage(&self) -> usize {
+        self.cache.bytes
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cached_chunks(&self) -> usize {
+        self.cache.entries.len()
+    }
+
+    pub fn is_cached(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.cache.entries.contains_key(&(chunk_x, chunk_z))
+    }
+
+    /// A copy of the chunk, generating and caching it if needed.
+    pub fn get_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> ChunkData {
+        self.load_chunk(chunk_x, chunk_z).clone()
+    }
+
+    /// Drop one chunk from the cache. Returns whether it was cached.
+    pub fn invalidate_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.cache.remove((chunk_x, chunk_z))
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Ground height in world units, from cached chunks where available.
+    pub fn sample_height(&self, world_x: f64, world_z: f64) -> f64 {
+        self.sampler.height(self, world_x, world_z)
+    }
+
+    /// `undefined` if a cached chunk stores an unknown biome id there.
+    pub fn sample_biome(&self, world_x: f64, world_z: f64) -> Option<Biome> {
+        self.sampler.biome(self, world_x, world_z)
+    }
+
+    /// Unit surface normal as `[x, y, z]`, y up.
+    pub fn sample_normal(&self, world_x: f64, world_z: f64) -> Vec<f32> {
+        let n = self.sampler.normal(self, world_x, world_z);
+        n.iter().map(|&v| v as f32).collect()
+    }
+
+    /// Raycast against the cached chunks where available. Quadtrees built
+    /// for cached chunks count towards the memory budget, so this may evict.
+    #[allow(clippy::too_many_arguments)]
+    pub fn raycast(
+        &mut self,
+        origin_x: f64,
+        origin_y: f64,
+        origin_z: f64,
+        dir_x: f64,
+        dir_y: f64,
+        dir_z: f64,
+        max_distance: f64,
+    ) -> Option<RayHit> {
+        let hit = raycast(
+            &self.sampler,
+            &*self,
+            [origin_x, origin_y, origin_z],
+            [dir_x, dir_y, dir_z],
+            max_distance,
+        );
+        self.cache.recount();
+        hit
+    }
+}
+
+impl World {
+    /// The chunk at these coords, generating and caching it if needed.
+    pub fn load_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> &ChunkData {
+        let key = (chunk_x, chunk_z);
+        if self.cache.touch(key) {
+            let entry = self.cache.entries.get_mut(&key).expect("touched entry is cached");
+            if !self.sampler.update_chunk(&self.config, &mut entry.chunk).is_empty() {
+                // Stages may drop the quadtree or resize layers
+                self.cache.recount();
+            }
+        } else {
+            let chunk = self.sampler.generate_chunk(&self.config, chunk_x, chunk_z);
+            self.cache.insert(key, chunk);
+        }
+        &self.cache.entries[&key].chunk
+    }
+
+    pub fn sampler(&self) -> &TerrainSampler {
+        &self.sampler
+    }
+}
+
+/// Up-to-date cached chunks only; lookups don't count as uses for eviction.
+impl ChunkSource for World {
+    fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkData> {
+        self.cache
+            .entries
+            .get(&(chunk_x, chunk_z))
+            .map(|e| &e.chunk)
+            .filter(|c| c.stages == self.stages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_chunk;
+
+    fn small_config() -> TerrainConfig {
+        let mut config = TerrainConfig::new(42);
+        config.chunk_size = 17;
+        config
+    }
+
+    #[test]
+    fn cached_chunks_match_generate_chunk() {
+        let config = small_config();
+        let mut world = World::new(&config).unwrap();
+        let expected = generate_chunk(&config, 3, -2).unwrap();
+        assert_eq!(world.load_chunk(3, -2).heightmap, expected.heightmap);
+        assert_eq!(world.load_chunk(3, -2).biome_map, expected.biome_map);
+        assert_eq!(world.cached_chunks(), 1);
+        assert_eq!(world.memory_usage(), expected.byte_size());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut world = World::new(&small_config()).unwrap();
+        let per_chunk = world.load_chunk(0, 0).byte_size();
+        world.set_memory_budget(per_chunk * 3);
+
+        world.load_chunk(1, 0);
+        world.load_chunk(2, 0);
+        world.load_chunk(0, 0); // (1, 0) is now the oldest
+        world.load_chunk(3, 0);
+
+        assert_eq!(world.cached_chunks(), 3);
+        assert!(!world.is_cached(1, 0));
+        assert!(world.is_cached(0, 0) && world.is_cached(2, 0) && world.is_cached(3, 0));
+        assert!(world.memory_usage() <= world.memory_budget());
+
+        world.set_memory_budget(0);
+        assert_eq!(world.cached_chunks(), 1);
+        assert!(world.is_cached(3, 0));
+    }
+
+    #[test]
+    fn raycast_quadtrees_count_against_budget() {
+        let config = small_config();
+        let mut world = World::new(&config).unwrap();
+        let layers = world.load_chunk(0, 0).byte_size();
+        world.set_memory_budget(layers * 4);
+        for x in 1..4 {
+            world.load_chunk(x, 0);
+        }
+        assert_eq!(world.cached_chunks(), 4);
+        assert_eq!(world.memory_usage(), layers * 4);
+
+        // Level with the ceiling, the ray crosses all four chunks and builds
+        // each one's quadtree, roughly doubling its size
+        world.raycast(0.5, config.height_multiplier, 8.0, 1.0, 0.0, 0.0, 63.0);
+        assert!(world.memory_usage() <= world.memory_budget());
+        assert!(world.cached_chunks() < 4);
+        let counted: usize = world.cache.entries.values().map(|e| e.chunk.byte_size()).sum();
+        assert_eq!(world.memory_usage(), counted);
+        assert!(counted > world.cached_chunks() * layers, "quadtrees must be counted");
+    }
+
+    #[test]
+    fn config_changes_invalidate() {
+        let mut config = small_config();
+        let mut world = World::new(&config).unwrap();
+        world.load_chunk(0, 0);
+        world.load_chunk(1, 0);
+
+        assert!(!world.set_config(&config).unwrap());
+        assert_eq!(world.cached_chunks(), 2);
+
+        assert!(world.invalidate_chunk(1, 0));
+        assert!(!world.invalidate_chunk(1, 0));
+
+        config.sea_level = 0.5;
+        assert!(world.set_config(&config).unwrap());
+        assert_eq!(world.cached_chunks(), 1);
+        assert_eq!(world.load_chunk(0, 0).biome_map, generate_chunk(&config, 0, 0).unwrap().biome_map);
+
+        // A viewer-only setting keeps the hash and every cached chunk
+        let hash = config.config_hash();
+        config.height_multiplier = 120.0;
+        assert_eq!(config.config_hash(), hash);
+        assert!(world.set_config(&config).unwrap());
+        assert_eq!(world.cached_chunks(), 1);
+        let stored = world.load_chunk(0, 0).heightmap[4 * 17 + 3] as f64;
+        assert_eq!(world.sample_height(3.0, 4.0), stored * 120.0);
+
+        config.chunk_size = 33;
+        assert!(world.set_config(&config).unwrap());
+        assert_eq!(world.cached_chunks(), 0);
+        assert_eq!(world.memory_usage(), 0);
+
+        config.octaves = 0;
+        assert_eq!(world.set_config(&config), Err(ConfigError::Octaves(0)));
+        assert_eq!(world.config().sea_level, 0.5);
+    }
+
+    #[test]
+    fn queries_use_cached_erosion() {
+        let mut config = small_config();
+        config.erosion_enabled = true;
+        config.erosion_iterations = 2000;
+        let mut world = World::new(&config).unwrap();
+
+        let uncached = world.sample_height(5.0, 5.0);
+        let stored = world.load_chunk(0, 0).heightmap[5 * 17 + 5] as f64 * config.height_multiplier;
+        assert_eq!(world.sample_height(5.0, 5.0), stored);
+        assert_ne!(uncached, stored);
+        assert!(world.raycast(5.0, 200.0, 5.0, 0.0, -1.0, 0.0, 500.0).is_some());
+    }
+}
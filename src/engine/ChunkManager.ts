@@ -1,20 +1,38 @@
 import * as THREE from 'three';
 import type { Chunk, ChunkData, WorldConfig } from '../types/terrain';
-import { createChunkLOD, disposeChunkLOD } from './TerrainChunk';
+import { createChunkLOD, disposeChunkLOD, getChunkLODLevel, setChunkLODLevel } from './TerrainChunk';
 import {
   ChunkWorkerBridge,
+  isChunkConfigError,
   isChunkRequestCancelledError,
 } from '../generation/ChunkWorkerBridge';
+import { createChunkPlanner, type WasmChunkPlanner } from '../generation/WasmBridge';
+
+/** First retry delay after a chunk fails to generate; doubles per failure. */
+const RETRY_BASE_MS = 1000;
+const RETRY_MAX_MS = 30000;
+
+interface ChunkFailure {
+  cx: number;
+  cz: number;
+  attempts: number;
+  /** When to hand the chunk back to the planner; Infinity while waiting on a config change or a retry. */
+  retryAt: number;
+}
 
 export class ChunkManager {
   private chunks = new Map<string, Chunk>();
-  private inFlight = new Set<string>();
+  /** In-flight requests and the LOD each should show once it arrives. */
+  private inFlight = new Map<string, number>();
+  /** Chunks whose last request failed; the planner still counts them as loaded. */
+  private failures = new Map<string, ChunkFailure>();
   private scene: THREE.Scene;
   private config: WorldConfig;
   private maxChunksPerFrame = 2;
-  private cacheMaxSize = 500;
   private generationId = 0;
   private workerBridge: ChunkWorkerBridge;
+  private planner: WasmChunkPlanner;
+  private cameraDirection = new THREE.Vector3();
   chunkCount = 0;
   onChunkLoaded: ((cx: number, cz: number, biomeData: Uint8Array, size: number) => void) | null = null;
   onChunkUnloaded: ((cx: number, cz: number) => void) | null = null;
@@ -23,96 +41,165 @@ export class ChunkManager {
     this.scene = scene;
     this.config = config;
     this.workerBridge = new ChunkWorkerBridge();
+    this.planner = this.createPlanner();
+  }
+
+  private createPlanner(): WasmChunkPlanner {
+    const planner = createChunkPlanner(this.config.chunkSize, this.config.viewDistance);
+    planner.max_loads = this.maxChunksPerFrame;
+    return planner;
   }
 
   updateConfig(config: WorldConfig): void {
+    // Anything the generator reads; chunk size is handled separately
     const needsRegenerate =
       config.seed !== this.config.seed ||
       config.scale !== this.config.scale ||
       config.octaves !== this.config.octaves ||
       config.persistence !== this.config.persistence ||
       config.lacunarity !== this.config.lacunarity ||
-      config.heightMultiplier !== this.config.heightMultiplier ||
       config.seaLevel !== this.config.seaLevel ||
       config.noiseType !== this.config.noiseType ||
       config.warpStrength !== this.config.warpStrength ||
+      config.warpBase !== this.config.warpBase ||
+      config.warpDepth !== this.config.warpDepth ||
+      config.warpOctaves !== this.config.warpOctaves ||
+      config.warpFrequency !== this.config.warpFrequency ||
+      config.warpLevelGain !== this.config.warpLevelGain ||
+      config.continentMode !== this.config.continentMode ||
+      config.continentFrequency !== this.config.continentFrequency ||
+      config.continentThreshold !== this.config.continentThreshold ||
+      config.continentStrength !== this.config.continentStrength ||
+      JSON.stringify(config.heightSpline) !== JSON.stringify(this.config.heightSpline) ||
+      config.splineErosionFrequency !== this.config.splineErosionFrequency ||
+      config.terraceSteps !== this.config.terraceSteps ||
+      config.terraceSmoothness !== this.config.terraceSmoothness ||
+      config.terraceJitter !== this.config.terraceJitter ||
+      JSON.stringify(config.strata) !== JSON.stringify(this.config.strata) ||
+      config.craterDensity !== this.config.craterDensity ||
+      config.craterMinRadius !== this.config.craterMinRadius ||
+      config.craterMaxRadius !== this.config.craterMaxRadius ||
+      config.craterSizeExponent !== this.config.craterSizeExponent ||
+      config.craterDepth !== this.config.craterDepth ||
+      config.craterPeak !== this.config.craterPeak ||
+      JSON.stringify(config.stamps) !== JSON.stringify(this.config.stamps) ||
+      config.cellularReturn !== this.config.cellularReturn ||
+      config.cellularDistance !== this.config.cellularDistance ||
+      config.cellularJitter !== this.config.cellularJitter ||
+      config.multifractalOffset !== this.config.multifractalOffset ||
+      config.multifractalGain !== this.config.multifractalGain ||
+      config.ridgedOffset !== this.config.ridgedOffset ||
+      config.ridgedGain !== this.config.ridgedGain ||
+      config.ridgedExponent !== this.config.ridgedExponent ||
+      config.ridgedWeightFrequency !== this.config.ridgedWeightFrequency ||
       config.erosionEnabled !== this.config.erosionEnabled ||
       config.erosionIterations !== this.config.erosionIterations ||
+      config.hardnessNoise !== this.config.hardnessNoise ||
+      config.hardnessFrequency !== this.config.hardnessFrequency ||
       config.moistureEnabled !== this.config.moistureEnabled;
+    const chunkSizeChanged = config.chunkSize !== this.config.chunkSize;
+    const heightMultiplierChanged = config.heightMultiplier !== this.config.heightMultiplier;
 
     this.config = config;
-
-    if (needsRegenerate) {
-      const previousGenerationId = this.generationId;
-      this.generationId++;
-      this.workerBridge.cancelGenerationRequests(previousGenerationId);
+    this.planner.view_distance = config.viewDistance;
+    // The new config may be the fix for whatever made them fail
+    this.retryFailures();
+
+    if (chunkSizeChanged) {
+      // Cached layers don't fit the new grid
+      this.planner.free();
+      this.planner = this.createPlanner();
+      this.cancelRequests();
       this.clearAll();
+    } else if (needsRegenerate) {
+      this.refreshChunks();
+    } else if (heightMultiplierChanged) {
+      // Only the meshes scale with it
+      for (const chunk of [...this.chunks.values()]) {
+        this.replaceChunk(chunk, chunk.data, chunk.config);
+      }
     }
   }
 
-  update(cameraPosition: THREE.Vector3): void {
-    const edge = this.config.chunkSize - 1;
-    const camChunkX = Math.floor(cameraPosition.x / edge);
-    const camChunkZ = Math.floor(cameraPosition.z / edge);
-    const vd = this.config.viewDistance;
-
-    // Determine which chunks should be loaded
-    const toGenerate: Array<{ x: number; z: number; distance: number }> = [];
-
-    for (let dz = -vd; dz <= vd; dz++) {
-      for (let dx = -vd; dx <= vd; dx++) {
-        const cx = camChunkX + dx;
-        const cz = camChunkZ + dz;
-        const key = `${cx},${cz}`;
-        if (!this.chunks.has(key)) {
-          if (!this.inFlight.has(key)) {
-            const dist = dx * dx + dz * dz;
-            toGenerate.push({ x: cx, z: cz, distance: dist });
-          }
-        } else {
-          const chunk = this.chunks.get(key)!;
-          chunk.lastUsed = performance.now();
-        }
-      }
+  private cancelRequests(): void {
+    const previousGenerationId = this.generationId;
+    this.generationId++;
+    this.workerBridge.cancelGenerationRequests(previousGenerationId);
+  }
+
+  /**
+   * Sends every loaded chunk back through the worker with the new config so
+   * only the stages it changed rerun; chunks keep showing until theirs lands.
+   */
+  private refreshChunks(): void {
+    this.cancelRequests();
+
+    // Requested with the old config; let the planner hand them out again
+    for (const key of this.inFlight.keys()) {
+      const [cx, cz] = key.split(',').map(Number);
+      this.planner.forget(cx!, cz!);
     }
+    this.inFlight.clear();
 
-    // Remove chunks beyond view distance (with hysteresis)
-    const unloadDistance = vd + 2;
-    const toRemove: string[] = [];
-    for (const [key, chunk] of this.chunks) {
-      const dx = chunk.x - camChunkX;
-      const dz = chunk.z - camChunkZ;
-      if (Math.abs(dx) > unloadDistance || Math.abs(dz) > unloadDistance) {
-        toRemove.push(key);
-      }
+    for (const chunk of this.chunks.values()) {
+      this.requestUpdate(chunk);
     }
-    for (const key of toRemove) {
-      this.removeChunk(key);
+  }
+
+  /** Loads, drops and re-LODs chunks as the Rust planner decides for this camera. */
+  update(camera: THREE.Camera): void {
+    const now = performance.now();
+    for (const failure of this.failures.values()) {
+      if (failure.retryAt <= now) {
+        failure.retryAt = Infinity;
+        this.planner.forget(failure.cx, failure.cz);
+      }
     }
 
-    // Sort by distance (closest first) and generate
-    toGenerate.sort((a, b) => a.distance - b.distance);
+    camera.getWorldDirection(this.cameraDirection);
+    const plan = this.planner.plan(
+      camera.position.x,
+      camera.position.z,
+      this.cameraDirection.x,
+      this.cameraDirection.z,
+    );
 
-    let generated = 0;
-    for (const item of toGenerate) {
-      if (generated >= this.maxChunksPerFrame) break;
+    try {
+      const unload = plan.unload_list();
+      for (let i = 0; i < unload.length; i += 2) {
+        const key = `${unload[i]},${unload[i + 1]}`;
+        // A dropped in-flight request is discarded when it arrives
+        this.inFlight.delete(key);
+        this.failures.delete(key);
+        this.removeChunk(key);
+      }
 
-      const key = `${item.x},${item.z}`;
-      if (this.chunks.has(key) || this.inFlight.has(key)) continue;
+      const relod = plan.relod_list();
+      for (let i = 0; i < relod.length; i += 3) {
+        const key = `${relod[i]},${relod[i + 1]}`;
+        const level = relod[i + 2]!;
+        const chunk = this.chunks.get(key);
+        if (chunk) {
+          setChunkLODLevel(chunk.lod, level);
+          chunk.lastUsed = performance.now();
+        } else if (this.inFlight.has(key)) {
+          this.inFlight.set(key, level);
+        }
+      }
 
-      this.requestChunk(item.x, item.z);
-      generated++;
+      // Already in planner priority order, nearest and facing first
+      const load = plan.load_list();
+      for (let i = 0; i < load.length; i += 3) {
+        this.requestChunk(load[i]!, load[i + 1]!, load[i + 2]!);
+      }
+    } finally {
+      plan.free();
     }
 
     this.chunkCount = this.chunks.size;
-
-    // Enforce cache limit
-    if (this.chunks.size > this.cacheMaxSize) {
-      this.evictOldest(this.chunks.size - this.cacheMaxSize);
-    }
   }
 
-  private addChunk(cx: number, cz: number, chunkData: ChunkData): void {
+  private addChunk(cx: number, cz: number, chunkData: ChunkData, level: number, config: WorldConfig): void {
     const lod = createChunkLOD(
       chunkData,
       cx,
@@ -121,6 +208,7 @@ export class ChunkManager {
       this.config.seaLevel,
       this.config.wireframe,
     );
+    setChunkLODLevel(lod, level);
 
     this.scene.add(lod);
 
@@ -130,8 +218,8 @@ export class ChunkManager {
       z: cz,
       key,
       lod,
-      heightData: chunkData.heightmap,
-      biomeData: chunkData.biomeMap,
+      data: chunkData,
+      config,
       lastUsed: performance.now(),
     };
 
@@ -139,9 +227,9 @@ export class ChunkManager {
     this.onChunkLoaded?.(cx, cz, chunkData.biomeMap, chunkData.width);
   }
 
-  private requestChunk(cx: number, cz: number): void {
+  private requestChunk(cx: number, cz: number, level: number): void {
     const key = `${cx},${cz}`;
-    this.inFlight.add(key);
+    this.inFlight.set(key, level);
 
     const requestedGenerationId = this.generationId;
     const configSnapshot = { ...this.config };
@@ -149,24 +237,63 @@ export class ChunkManager {
     this.workerBridge
       .requestChunk(configSnapshot, cx, cz, requestedGenerationId)
       .then((chunkData) => {
-        this.inFlight.delete(key);
-
         if (requestedGenerationId !== this.generationId) return;
+        // Unloaded by the planner while it was generating
+        const level = this.inFlight.get(key);
+        if (level === undefined) return;
+        this.inFlight.delete(key);
+        this.failures.delete(key);
         if (this.chunks.has(key)) return;
 
-        this.addChunk(cx, cz, chunkData);
+        this.addChunk(cx, cz, chunkData, level, configSnapshot);
       })
       .catch((error) => {
-        this.inFlight.delete(key);
-
         if (isChunkRequestCancelledError(error)) {
           return;
         }
-
+        if (requestedGenerationId !== this.generationId || !this.inFlight.delete(key)) return;
+
+        // The planner keeps it as loaded until the retry is due; a rejected
+        // config fails every time, so that waits for the next config change
+        const attempts = (this.failures.get(key)?.attempts ?? 0) + 1;
+        const retryAt = isChunkConfigError(error)
+          ? Infinity
+          : performance.now() + Math.min(RETRY_BASE_MS * 2 ** (attempts - 1), RETRY_MAX_MS);
+        this.failures.set(key, { cx, cz, attempts, retryAt });
         console.error(`Failed to generate chunk ${key}:`, error);
       });
   }
 
+  private requestUpdate(chunk: Chunk): void {
+    const requestedGenerationId = this.generationId;
+    const configSnapshot = { ...this.config };
+
+    this.workerBridge
+      .requestChunkUpdate(chunk.config, configSnapshot, chunk.x, chunk.z, chunk.data, requestedGenerationId)
+      .then((chunkData) => {
+        if (requestedGenerationId !== this.generationId) return;
+        // Unloaded by the planner while it was updating
+        if (this.chunks.get(chunk.key) !== chunk) return;
+
+        this.replaceChunk(chunk, chunkData, configSnapshot);
+      })
+      .catch((error) => {
+        if (isChunkRequestCancelledError(error)) {
+          return;
+        }
+        // Keeps its old layers; the next config change tries again
+        console.error(`Failed to update chunk ${chunk.key}:`, error);
+      });
+  }
+
+  /** Swaps a loaded chunk's meshes for ones built from `chunkData`, keeping its LOD. */
+  private replaceChunk(chunk: Chunk, chunkData: ChunkData, config: WorldConfig): void {
+    const level = getChunkLODLevel(chunk.lod);
+    this.scene.remove(chunk.lod);
+    disposeChunkLOD(chunk.lod);
+    this.addChunk(chunk.x, chunk.z, chunkData, level, config);
+  }
+
   private removeChunk(key: string): void {
     const chunk = this.chunks.get(key);
     if (!chunk) return;
@@ -177,13 +304,12 @@ export class ChunkManager {
     this.onChunkUnloaded?.(chunk.x, chunk.z);
   }
 
-  private evictOldest(count: number): void {
-    const sorted = [...this.chunks.entries()]
-      .sort(([, a], [, b]) => a.lastUsed - b.lastUsed);
-
-    for (let i = 0; i < count && i < sorted.length; i++) {
-      this.removeChunk(sorted[i]![0]);
+  /** Hands every failed chunk back to the planner now, e.g. after the config changed. */
+  private retryFailures(): void {
+    for (const failure of this.failures.values()) {
+      this.planner.forget(failure.cx, failure.cz);
     }
+    this.failures.clear();
   }
 
   clearAll(): void {
@@ -191,6 +317,8 @@ export class ChunkManager {
       this.removeChunk(key);
     }
     this.inFlight.clear();
+    this.failures.clear();
+    this.planner.reset();
   }
 
   getHeightAt(worldX: number, worldZ: number): number | null {
@@ -204,7 +332,7 @@ export class ChunkManager {
     const localX = Math.floor(worldX - cx * edge);
     const localZ = Math.floor(worldZ - cz * edge);
     const idx = localZ * this.config.chunkSize + localX;
-    const height = chunk.heightData[idx];
+    const height = chunk.data.heightmap[idx];
     if (height === undefined) return null;
 
     return height * this.config.heightMultiplier;
@@ -212,6 +340,7 @@ export class ChunkManager {
 
   dispose(): void {
     this.clearAll();
+    this.planner.free();
     this.workerBridge.dispose();
   }
 }
@@ -124,6 +124,19 @@ export function createChunkLOD(
   return lod;
 }
 
+/** Show only `level`; the LOD stops choosing levels by camera distance. */
+export function setChunkLODLevel(lod: THREE.LOD, level: number): void {
+  lod.autoUpdate = false;
+  lod.levels.forEach((entry, i) => {
+    entry.object.visible = i === Math.min(level, lod.levels.length - 1);
+  });
+}
+
+/** Index of the visible LOD level, as last set by `setChunkLODLevel`. */
+export function getChunkLODLevel(lod: THREE.LOD): number {
+  return Math.max(0, lod.levels.findIndex((entry) => entry.object.visible));
+}
+
 export function disposeChunkLOD(lod: THREE.LOD): void {
   for (const level of lod.levels) {
     const mesh = level.object as THREE.Mesh;
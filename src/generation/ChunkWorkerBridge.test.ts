@@ -1,5 +1,6 @@
 import { describe, expect, it } from 'vitest';
 import {
+  ChunkConfigError,
   ChunkRequestCancelledError,
   ChunkWorkerBridge,
 } from './ChunkWorkerBridge';
@@ -73,6 +74,7 @@ describe('ChunkWorkerBridge', () => {
         heightmap: new Float32Array([0, 0, 0, 0]),
         moistureMap: new Float32Array([0, 0, 0, 0]),
         biomeMap: new Uint8Array([0, 0, 0, 0]),
+        materialMap: new Uint8Array([0, 0, 0, 0]),
       },
     });
 
@@ -82,6 +84,49 @@ describe('ChunkWorkerBridge', () => {
     bridge.dispose();
   });
 
+  it('sends cached layers and both configs for an update', async () => {
+    const mockWorker = new MockWorker();
+    const bridge = new ChunkWorkerBridge(() => mockWorker as unknown as Worker);
+    const data = {
+      width: 2,
+      height: 2,
+      minHeight: 0,
+      maxHeight: 1,
+      heightmap: new Float32Array([0, 1, 0, 1]),
+      moistureMap: new Float32Array([0, 0, 0, 0]),
+      biomeMap: new Uint8Array([1, 2, 1, 2]),
+      materialMap: new Uint8Array([0, 0, 0, 0]),
+    };
+    const next = { ...config, seaLevel: config.seaLevel + 0.1 };
+
+    const promise = bridge.requestChunkUpdate(config, next, 3, -1, data, 2);
+    const request = mockWorker.postedMessages[0] as {
+      type: string;
+      requestId: number;
+      previousConfig: WorldConfig;
+      config: WorldConfig;
+      layers: { heightmap: Float32Array };
+    };
+    expect(request.type).toBe('update');
+    expect(request.previousConfig).toBe(config);
+    expect(request.config).toBe(next);
+    expect(request.layers.heightmap).toBe(data.heightmap);
+
+    mockWorker.emitMessage({
+      type: 'chunk',
+      requestId: request.requestId,
+      generationId: 2,
+      chunkX: 3,
+      chunkZ: -1,
+      data: { ...data, biomeMap: new Uint8Array([3, 3, 3, 3]) },
+    });
+
+    const result = await promise;
+    expect(result.biomeMap[0]).toBe(3);
+
+    bridge.dispose();
+  });
+
   it('rejects pending request when generation is cancelled', async () => {
     const mockWorker = new MockWorker();
     const bridge = new ChunkWorkerBridge(() => mockWorker as unknown as Worker);
@@ -94,6 +139,35 @@ describe('ChunkWorkerBridge', () => {
     bridge.dispose();
   });
 
+  it('rejects with ChunkConfigError when the worker rejects the config', async () => {
+    const mockWorker = new MockWorker();
+    const bridge = new ChunkWorkerBridge(() => mockWorker as unknown as Worker);
+
+    const bad = bridge.requestChunk(config, 0, 0, 1);
+    const failed = bridge.requestChunk(config, 1, 0, 1);
+    const [badRequest, failedRequest] = mockWorker.postedMessages as { requestId: number }[];
+
+    mockWorker.emitMessage({
+      type: 'error',
+      requestId: badRequest!.requestId,
+      generationId: 1,
+      reason: 'config',
+      message: 'octaves must be 1..=16',
+    });
+    mockWorker.emitMessage({
+      type: 'error',
+      requestId: failedRequest!.requestId,
+      generationId: 1,
+      reason: 'generation',
+      message: 'out of memory',
+    });
+
+    await expect(bad).rejects.toBeInstanceOf(ChunkConfigError);
+    await expect(failed).rejects.not.toBeInstanceOf(ChunkConfigError);
+
+    bridge.dispose();
+  });
+
   it('rejects all pending requests when worker errors', async () => {
     const mockWorker = new MockWorker();
     const bridge = new ChunkWorkerBridge(() => mockWorker as unknown as Worker);
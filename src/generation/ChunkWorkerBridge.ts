@@ -9,6 +9,17 @@ interface ChunkRequestMessage {
   config: WorldConfig;
 }
 
+interface ChunkUpdateMessage {
+  type: 'update';
+  requestId: number;
+  generationId: number;
+  chunkX: number;
+  chunkZ: number;
+  previousConfig: WorldConfig;
+  config: WorldConfig;
+  layers: Pick<ChunkData, 'heightmap' | 'moistureMap' | 'biomeMap' | 'materialMap'>;
+}
+
 interface ChunkResponseMessage {
   type: 'chunk';
   requestId: number;
@@ -22,6 +33,7 @@ interface ChunkErrorMessage {
   type: 'error';
   requestId: number;
   generationId: number;
+  reason: 'config' | 'generation';
   message: string;
 }
 
@@ -48,6 +60,18 @@ export function isChunkRequestCancelledError(error: unknown): error is ChunkRequ
   return error instanceof ChunkRequestCancelledError;
 }
 
+/** The worker rejected the config itself; the same request fails until the config changes. */
+export class ChunkConfigError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = 'ChunkConfigError';
+  }
+}
+
+export function isChunkConfigError(error: unknown): error is ChunkConfigError {
+  return error instanceof ChunkConfigError;
+}
+
 export class ChunkWorkerBridge {
   private worker: Worker;
   private requestId = 0;
@@ -61,6 +85,46 @@ export class ChunkWorkerBridge {
   }
 
   requestChunk(config: WorldConfig, chunkX: number, chunkZ: number, generationId: number): Promise<ChunkData> {
+    return this.send(generationId, (requestId) => ({
+      type: 'generate',
+      requestId,
+      generationId,
+      chunkX,
+      chunkZ,
+      config,
+    }));
+  }
+
+  /**
+   * Brings `data`, generated with `previousConfig`, up to `config` in the worker;
+   * only the stages whose inputs changed are rerun. The layers are copied, so
+   * `data` stays usable until the update arrives.
+   */
+  requestChunkUpdate(
+    previousConfig: WorldConfig,
+    config: WorldConfig,
+    chunkX: number,
+    chunkZ: number,
+    data: ChunkData,
+    generationId: number,
+  ): Promise<ChunkData> {
+    const { heightmap, moistureMap, biomeMap, materialMap } = data;
+    return this.send(generationId, (requestId) => ({
+      type: 'update',
+      requestId,
+      generationId,
+      chunkX,
+      chunkZ,
+      previousConfig,
+      config,
+      layers: { heightmap, moistureMap, biomeMap, materialMap },
+    }));
+  }
+
+  private send(
+    generationId: number,
+    build: (requestId: number) => ChunkRequestMessage | ChunkUpdateMessage,
+  ): Promise<ChunkData> {
     return new Promise<ChunkData>((resolve, reject) => {
       const requestId = ++this.requestId;
       this.pending.set(requestId, {
@@ -69,16 +133,7 @@ export class ChunkWorkerBridge {
         reject,
       });
 
-      const message: ChunkRequestMessage = {
-        type: 'generate',
-        requestId,
-        generationId,
-        chunkX,
-        chunkZ,
-        config,
-      };
-
-      this.worker.postMessage(message);
+      this.worker.postMessage(build(requestId));
     });
   }
 
@@ -108,7 +163,9 @@ export class ChunkWorkerBridge {
     this.pending.delete(message.requestId);
 
     if (message.type === 'error') {
-      pending.reject(new Error(message.message));
+      pending.reject(
+        message.reason === 'config' ? new ChunkConfigError(message.message) : new Error(message.message),
+      );
       // Show user-facing error toast
       import('../utils/toast').then(({ showToast }) => {
         showToast(`Terrain generation failed: ${message.message}`, 5000);
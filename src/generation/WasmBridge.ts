@@ -3,9 +3,55 @@ import type { ChunkData, WorldConfig } from '../types/terrain';
 // The WASM module types (matches wasm-bindgen output)
 interface WasmModule {
   default: (input?: RequestInfo | URL) => Promise<void>;
-  TerrainConfig: new (seed: bigint) => WasmTerrainConfig;
+  TerrainConfig: {
+    new (seed: bigint): WasmTerrainConfig;
+    from_json: (json: string) => WasmTerrainConfig;
+  };
   generate_chunk: (config: WasmTerrainConfig, chunkX: number, chunkZ: number) => WasmChunkData;
-  erode_heightmap: (heightmap: Float32Array, width: number, height: number, config: WasmTerrainConfig) => void;
+  generate_cell_mask: (config: WasmTerrainConfig, chunkX: number, chunkZ: number) => Uint32Array;
+  chunk_hardness_map: (config: WasmTerrainConfig, chunk: WasmChunkData) => Float32Array;
+  erode_heightmap: (
+    heightmap: Float32Array,
+    width: number,
+    height: number,
+    originX: number,
+    originZ: number,
+    config: WasmTerrainConfig,
+  ) => void;
+  ErosionJob: new (
+    heightmap: Float32Array,
+    width: number,
+    height: number,
+    originX: number,
+    originZ: number,
+    config: WasmTerrainConfig,
+  ) => WasmErosionJob;
+  generate_region: (config: WasmTerrainConfig, x0: number, z0: number, x1: number, z1: number) => WasmRegionData;
+  StlOptions: new () => WasmStlOptions;
+  export_stl: (
+    config: WasmTerrainConfig,
+    chunkX0: number,
+    chunkZ0: number,
+    chunkX1: number,
+    chunkZ1: number,
+    options: WasmStlOptions,
+  ) => Uint8Array;
+  sample_height: (config: WasmTerrainConfig, x: number, z: number) => number;
+  sample_continentalness: (config: WasmTerrainConfig, x: number, z: number) => number;
+  sample_biome: (config: WasmTerrainConfig, x: number, z: number) => number;
+  sample_normal: (config: WasmTerrainConfig, x: number, z: number) => Float32Array;
+  TerrainSampler: new (config: WasmTerrainConfig) => WasmTerrainSampler;
+  ChunkPlanner: new (chunkSize: number, viewDistance: number) => WasmChunkPlanner;
+  raycast_terrain: (
+    config: WasmTerrainConfig,
+    originX: number,
+    originY: number,
+    originZ: number,
+    dirX: number,
+    dirY: number,
+    dirZ: number,
+    maxDistance: number,
+  ) => WasmRayHit | undefined;
 }
 
 interface WasmTerrainConfig {
@@ -23,10 +69,46 @@ interface WasmTerrainConfig {
   erosion_deposition: number;
   erosion_erosion_rate: number;
   erosion_evaporation: number;
+  hardness_noise: number;
+  hardness_frequency: number;
   moisture_enabled: boolean;
   erosion_enabled: boolean;
   noise_type: number;
   warp_strength: number;
+  warp_base: number;
+  warp_depth: number;
+  warp_octaves: number;
+  warp_frequency: number;
+  warp_level_gain: number;
+  continent_mode: number;
+  continent_frequency: number;
+  continent_threshold: number;
+  continent_strength: number;
+  spline_erosion_frequency: number;
+  height_spline_json: () => string | undefined;
+  set_height_spline_json: (json?: string) => void;
+  terrace_steps: number;
+  terrace_smoothness: number;
+  terrace_jitter: number;
+  strata_json: () => string | undefined;
+  set_strata_json: (json?: string) => void;
+  crater_density: number;
+  crater_min_radius: number;
+  crater_max_radius: number;
+  crater_size_exponent: number;
+  crater_depth: number;
+  crater_peak: number;
+  stamps_json: () => string | undefined;
+  set_stamps_json: (json?: string) => void;
+  cellular_return: number;
+  cellular_distance: number;
+  cellular_jitter: number;
+  multifractal_offset: number;
+  multifractal_gain: number;
+  ridged_offset: number;
+  ridged_gain: number;
+  ridged_exponent: number;
+  ridged_weight_frequency: number;
   free: () => void;
 }
 
@@ -38,9 +120,83 @@ interface WasmChunkData {
   get_heightmap: () => Float32Array;
   get_moisture_map: () => Float32Array;
   get_biome_map: () => Uint8Array;
+  get_material_map: () => Uint8Array;
+  heightmap_view: () => Float32Array;
+  moisture_map_view: () => Float32Array;
+  biome_map_view: () => Uint8Array;
+  material_map_view: () => Uint8Array;
   free: () => void;
 }
 
+interface WasmRegionData extends WasmChunkData {
+  origin_x: number;
+  origin_z: number;
+}
+
+export interface WasmRayHit {
+  x: number;
+  y: number;
+  z: number;
+  normal_x: number;
+  normal_y: number;
+  normal_z: number;
+  chunk_x: number;
+  chunk_z: number;
+  biome: number | undefined;
+  distance: number;
+  free: () => void;
+}
+
+export interface WasmTerrainSampler {
+  sample_height: (x: number, z: number) => number;
+  sample_continentalness: (x: number, z: number) => number;
+  sample_biome: (x: number, z: number) => number;
+  sample_normal: (x: number, z: number) => Float32Array;
+  free: () => void;
+}
+
+export interface WasmChunkPlan {
+  load_list: () => Int32Array;
+  unload_list: () => Int32Array;
+  relod_list: () => Int32Array;
+  free: () => void;
+}
+
+export interface WasmChunkPlanner {
+  view_distance: number;
+  max_loads: number;
+  readonly loaded_count: number;
+  forget: (chunkX: number, chunkZ: number) => void;
+  reset: () => void;
+  plan: (cameraX: number, cameraZ: number, dirX: number, dirZ: number) => WasmChunkPlan;
+  free: () => void;
+}
+
+interface WasmErosionJob {
+  readonly progress: number;
+  step: (droplets: number) => number;
+  is_finished: () => boolean;
+  cancel: () => void;
+  snapshot: () => Float32Array;
+  into_heightmap: () => Float32Array;
+  free: () => void;
+}
+
+interface WasmStlOptions {
+  base_thickness_mm: number;
+  vertical_exaggeration: number;
+  size_mm: number;
+  binary: boolean;
+  free: () => void;
+}
+
+export interface StlExportOptions {
+  baseThicknessMm: number;
+  verticalExaggeration: number;
+  sizeMm: number;
+  binary: boolean;
+}
+
 let wasmModule: WasmModule | null = null;
 
 export async function initWasm(): Promise<void> {
@@ -67,27 +223,8 @@ function getWasm(): WasmModule {
 
 export function createWasmConfig(config: WorldConfig): WasmTerrainConfig {
   const wasm = getWasm();
-  const wasmConfig = new wasm.TerrainConfig(BigInt(config.seed));
-
-  wasmConfig.chunk_size = config.chunkSize;
-  wasmConfig.scale = config.scale;
-  wasmConfig.octaves = config.octaves;
-  wasmConfig.persistence = config.persistence;
-  wasmConfig.lacunarity = config.lacunarity;
-  wasmConfig.height_multiplier = config.heightMultiplier;
-  wasmConfig.sea_level = config.seaLevel;
-  wasmConfig.erosion_iterations = config.erosionIterations;
-  wasmConfig.erosion_inertia = config.erosionInertia;
-  wasmConfig.erosion_capacity = config.erosionCapacity;
-  wasmConfig.erosion_deposition = config.erosionDeposition;
-  wasmConfig.erosion_erosion_rate = config.erosionErosionRate;
-  wasmConfig.erosion_evaporation = config.erosionEvaporation;
-  wasmConfig.moisture_enabled = config.moistureEnabled;
-  wasmConfig.erosion_enabled = config.erosionEnabled;
-  wasmConfig.noise_type = config.noiseType;
-  wasmConfig.warp_strength = config.warpStrength;
-
-  return wasmConfig;
+  // Same document format as exported settings files; Rust migrates the camelCase keys
+  return wasm.TerrainConfig.from_json(JSON.stringify(config));
 }
 
 export function generateChunk(config: WorldConfig, chunkX: number, chunkZ: number): ChunkData {
@@ -97,10 +234,73 @@ export function generateChunk(config: WorldConfig, chunkX: number, chunkZ: numbe
   try {
     const result = wasm.generate_chunk(wasmConfig, chunkX, chunkZ);
 
+    // Copy out of the zero-copy views before the next WASM call
+    const data: ChunkData = {
+      heightmap: result.heightmap_view().slice(),
+      moistureMap: result.moisture_map_view().slice(),
+      biomeMap: result.biome_map_view().slice(),
+      materialMap: result.material_map_view().slice(),
+      width: result.width,
+      height: result.height,
+      minHeight: result.min_height,
+      maxHeight: result.max_height,
+    };
+
+    result.free();
+    return data;
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/** Cellular-noise cell ids over a chunk's grid, for masking regions. */
+export function generateCellMask(config: WorldConfig, chunkX: number, chunkZ: number): Uint32Array {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return wasm.generate_cell_mask(wasmConfig, chunkX, chunkZ);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/** Erosion hardness per cell of a chunk (0 erodes normally, 1 not at all), for debugging. */
+export function generateHardnessMap(config: WorldConfig, chunkX: number, chunkZ: number): Float32Array {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    const chunk = wasm.generate_chunk(wasmConfig, chunkX, chunkZ);
+    try {
+      return wasm.chunk_hardness_map(wasmConfig, chunk);
+    } finally {
+      chunk.free();
+    }
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/** Generate world cells x0..x1 × z0..z1 as one stitched map (throws if too large). */
+export function generateRegion(
+  config: WorldConfig,
+  x0: number,
+  z0: number,
+  x1: number,
+  z1: number,
+): ChunkData {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    const result = wasm.generate_region(wasmConfig, x0, z0, x1, z1);
+
     const data: ChunkData = {
       heightmap: result.get_heightmap(),
       moistureMap: result.get_moisture_map(),
       biomeMap: result.get_biome_map(),
+      materialMap: result.get_material_map(),
       width: result.width,
       height: result.height,
       minHeight: result.min_height,
@@ -114,17 +314,202 @@ export function generateChunk(config: WorldConfig, chunkX: number, chunkZ: numbe
   }
 }
 
+/**
+ * Erode a chunk's heightmap in place. The chunk coordinates place it in the
+ * world so the hardness field matches erosion during generation.
+ */
 export function erodeHeightmap(
   heightmap: Float32Array,
   width: number,
   height: number,
+  chunkX: number,
+  chunkZ: number,
   config: WorldConfig,
 ): void {
   const wasm = getWasm();
   const wasmConfig = createWasmConfig(config);
+  const edge = config.chunkSize - 1;
+
+  try {
+    wasm.erode_heightmap(heightmap, width, height, chunkX * edge, chunkZ * edge, wasmConfig);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+export interface ErosionRunOptions {
+  /** Droplets per step between progress callbacks */
+  batch?: number;
+  onProgress?: (progress: number, snapshot: () => Float32Array) => void;
+  signal?: AbortSignal;
+}
+
+/**
+ * Erode a chunk's heightmap in batches, yielding to the event loop between
+ * them. Resolves to the eroded heightmap, or null if `signal` aborted the run.
+ */
+export async function runErosionJob(
+  heightmap: Float32Array,
+  width: number,
+  height: number,
+  chunkX: number,
+  chunkZ: number,
+  config: WorldConfig,
+  options: ErosionRunOptions = {},
+): Promise<Float32Array | null> {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+  const edge = config.chunkSize - 1;
+  const job = new wasm.ErosionJob(heightmap, width, height, chunkX * edge, chunkZ * edge, wasmConfig);
+  wasmConfig.free();
+
+  const batch = options.batch ?? 2000;
+  while (!job.is_finished()) {
+    if (options.signal?.aborted) {
+      job.cancel();
+      job.free();
+      return null;
+    }
+    const progress = job.step(batch);
+    options.onProgress?.(progress, () => job.snapshot());
+    await new Promise((resolve) => setTimeout(resolve, 0));
+  }
+  // Consumes the job
+  return job.into_heightmap();
+}
+
+export function exportChunkRangeSTL(
+  config: WorldConfig,
+  chunkX0: number,
+  chunkZ0: number,
+  chunkX1: number,
+  chunkZ1: number,
+  options: StlExportOptions,
+): Uint8Array {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+  const wasmOptions = new wasm.StlOptions();
+
+  try {
+    wasmOptions.base_thickness_mm = options.baseThicknessMm;
+    wasmOptions.vertical_exaggeration = options.verticalExaggeration;
+    wasmOptions.size_mm = options.sizeMm;
+    wasmOptions.binary = options.binary;
+    return wasm.export_stl(wasmConfig, chunkX0, chunkZ0, chunkX1, chunkZ1, wasmOptions);
+  } finally {
+    wasmOptions.free();
+    wasmConfig.free();
+  }
+}
+
+/**
+ * Reusable point sampler for one config; the caller must `free()` it. Use it
+ * for repeated queries: the one-shot `sample*` functions below rebuild the
+ * noise pipeline on every call.
+ */
+export function createTerrainSampler(config: WorldConfig): WasmTerrainSampler {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return new wasm.TerrainSampler(wasmConfig);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/**
+ * Planner deciding which chunks to load, drop and re-LOD around the camera;
+ * the caller must `free()` it. The view distance is clamped in Rust.
+ */
+export function createChunkPlanner(chunkSize: number, viewDistance: number): WasmChunkPlanner {
+  return new (getWasm().ChunkPlanner)(chunkSize, viewDistance);
+}
+
+/** Un-eroded ground height in world units at a fractional world position. */
+export function sampleHeight(config: WorldConfig, x: number, z: number): number {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return wasm.sample_height(wasmConfig, x, z);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/** Continentalness in 0..1; land lies above `continentThreshold`. */
+export function sampleContinentalness(config: WorldConfig, x: number, z: number): number {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return wasm.sample_continentalness(wasmConfig, x, z);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+export function sampleBiome(config: WorldConfig, x: number, z: number): number {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return wasm.sample_biome(wasmConfig, x, z);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+/** Unit surface normal as `[x, y, z]`, y up. */
+export function sampleNormal(config: WorldConfig, x: number, z: number): Float32Array {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
+
+  try {
+    return wasm.sample_normal(wasmConfig, x, z);
+  } finally {
+    wasmConfig.free();
+  }
+}
+
+export interface TerrainHit {
+  position: [number, number, number];
+  normal: [number, number, number];
+  chunkX: number;
+  chunkZ: number;
+  biome: number | null; // null if a cached chunk stores an unknown biome id
+  distance: number;
+}
+
+/**
+ * First terrain hit along a ray, against the un-eroded terrain. `maxDistance`
+ * must be finite and is clamped to 65536 world units.
+ */
+export function raycastTerrain(
+  config: WorldConfig,
+  origin: [number, number, number],
+  direction: [number, number, number],
+  maxDistance: number,
+): TerrainHit | null {
+  const wasm = getWasm();
+  const wasmConfig = createWasmConfig(config);
 
   try {
-    wasm.erode_heightmap(heightmap, width, height, wasmConfig);
+    const hit = wasm.raycast_terrain(wasmConfig, ...origin, ...direction, maxDistance);
+    if (!hit) return null;
+    try {
+      return {
+        position: [hit.x, hit.y, hit.z],
+        normal: [hit.normal_x, hit.normal_y, hit.normal_z],
+        chunkX: hit.chunk_x,
+        chunkZ: hit.chunk_z,
+        biome: hit.biome ?? null,
+        distance: hit.distance,
+      };
+    } finally {
+      hit.free();
+    }
   } finally {
     wasmConfig.free();
   }
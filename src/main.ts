@@ -154,7 +154,7 @@ async function main(): Promise<void> {
   // Animation loop
   sceneManager.onAnimate((delta, elapsed) => {
     flyControls.update(delta);
-    chunkManager.update(sceneManager.camera.position);
+    chunkManager.update(sceneManager.camera);
 
     if (water && config.generateWater) {
       water.update(sceneManager.camera.position, elapsed);
@@ -4,6 +4,8 @@ export interface ChunkData {
   heightmap: Float32Array;
   moistureMap: Float32Array;
   biomeMap: Uint8Array;
+  /** Rock type id per cell; zeros without strata. */
+  materialMap: Uint8Array;
   width: number;
   height: number;
   minHeight: number;
@@ -15,11 +17,50 @@ export interface Chunk {
   z: number;
   key: string;
   lod: THREE.LOD;
-  heightData: Float32Array;
-  biomeData: Uint8Array;
+  data: ChunkData;
+  /** Config the layers were generated with; the baseline for incremental updates. */
+  config: WorldConfig;
   lastUsed: number;
 }
 
+/** Height remap curve; a point's value may itself be a spline over another input. */
+export interface HeightSpline {
+  input: 'height' | 'continentalness' | 'erosion';
+  points: Array<{
+    location: number;
+    value: number | HeightSpline;
+    derivative?: number;
+  }>;
+}
+
+/** Rock layers stacked from height 0 upwards, repeating if they end below 1. */
+export interface Strata {
+  layers: Array<{
+    rock: number;
+    thickness: number;
+    hardness?: number; // 0 erodes normally, 1 not at all
+  }>;
+}
+
+/** Hand-authored heightmaps (brushes) and where to place them. */
+export interface StampLibrary {
+  brushes: Array<{
+    name: string;
+    width: number;
+    height: number;
+    data: number[]; // row-major normalized heights
+  }>;
+  stamps: Array<{
+    brush: string;
+    x: number; // world position of the brush centre
+    z: number;
+    rotation?: number; // degrees; positive turns the brush's x axis towards +z
+    scale: number; // world units per brush texel
+    blend: 'add' | 'max' | 'min' | 'replace';
+    falloff?: number; // 0..1 share of the way in from the edge to fade over
+  }>;
+}
+
 export interface WorldConfig {
   seed: number;
   chunkSize: number;
@@ -30,8 +71,39 @@ export interface WorldConfig {
   heightMultiplier: number;
   seaLevel: number;
   viewDistance: number;
-  noiseType: number; // 0=Simplex, 1=Perlin, 2=Ridged, 3=Warped
+  noiseType: number; // 0=Simplex, 1=Perlin, 2=Ridged, 3=Warped, 4=Cellular, 5=Billow, 6=Hybrid, 7=Hetero
   warpStrength: number;
+  warpBase: number; // noiseType warped by Warped, any but 3
+  warpDepth: number;
+  warpOctaves: number;
+  warpFrequency: number;
+  warpLevelGain: number;
+  continentMode: number; // 0=Off, 1=Noise, 2=Plates
+  continentFrequency: number;
+  continentThreshold: number;
+  continentStrength: number;
+  heightSpline: HeightSpline | null;
+  splineErosionFrequency: number;
+  terraceSteps: number; // 0 = no terracing
+  terraceSmoothness: number;
+  terraceJitter: number;
+  strata: Strata | null;
+  craterDensity: number; // mean craters per 1024x1024 region, 0 = none
+  craterMinRadius: number;
+  craterMaxRadius: number;
+  craterSizeExponent: number;
+  craterDepth: number;
+  craterPeak: number;
+  stamps: StampLibrary | null;
+  cellularReturn: number; // 0=F1, 1=F2, 2=F2-F1, 3=Cell Id
+  cellularDistance: number; // 0=Euclidean, 1=Manhattan, 2=Chebyshev
+  cellularJitter: number;
+  multifractalOffset: number;
+  multifractalGain: number;
+  ridgedOffset: number;
+  ridgedGain: number;
+  ridgedExponent: number;
+  ridgedWeightFrequency: number; // 0 = no mountain-range weight field
   erosionEnabled: boolean;
   erosionIterations: number;
   erosionInertia: number;
@@ -39,6 +111,8 @@ export interface WorldConfig {
   erosionDeposition: number;
   erosionErosionRate: number;
   erosionEvaporation: number;
+  hardnessNoise: number; // 0 = uniform rock (strata hardness still applies)
+  hardnessFrequency: number;
   moistureEnabled: boolean;
   generateWater: boolean;
   generateFog: boolean;
@@ -59,6 +133,37 @@ export function defaultWorldConfig(): WorldConfig {
     viewDistance: 4,
     noiseType: 0,
     warpStrength: 0.5,
+    warpBase: 0,
+    warpDepth: 1,
+    warpOctaves: 1,
+    warpFrequency: 0.003,
+    warpLevelGain: 1.0,
+    continentMode: 0,
+    continentFrequency: 0.0005,
+    continentThreshold: 0.5,
+    continentStrength: 1.0,
+    heightSpline: null,
+    splineErosionFrequency: 0.001,
+    terraceSteps: 0,
+    terraceSmoothness: 0.2,
+    terraceJitter: 0,
+    strata: null,
+    craterDensity: 0,
+    craterMinRadius: 8,
+    craterMaxRadius: 120,
+    craterSizeExponent: 2.0,
+    craterDepth: 0.15,
+    craterPeak: 0.3,
+    stamps: null,
+    cellularReturn: 0,
+    cellularDistance: 0,
+    cellularJitter: 1.0,
+    multifractalOffset: 0.7,
+    multifractalGain: 1.0,
+    ridgedOffset: 1.0,
+    ridgedGain: 2.0,
+    ridgedExponent: 2.0,
+    ridgedWeightFrequency: 0,
     erosionEnabled: false,
     erosionIterations: 50000,
     erosionInertia: 0.05,
@@ -66,6 +171,8 @@ export function defaultWorldConfig(): WorldConfig {
     erosionDeposition: 0.3,
     erosionErosionRate: 0.3,
     erosionEvaporation: 0.01,
+    hardnessNoise: 0,
+    hardnessFrequency: 0.01,
     moistureEnabled: true,
     generateWater: true,
     generateFog: true,
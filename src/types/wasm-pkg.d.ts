@@ -16,28 +16,308 @@ declare module '*terra_wasm.js' {
     erosion_deposition: number;
     erosion_erosion_rate: number;
     erosion_evaporation: number;
+    hardness_noise: number;
+    hardness_frequency: number;
     moisture_enabled: boolean;
     erosion_enabled: boolean;
     noise_type: number;
     warp_strength: number;
+    warp_base: number;
+    warp_depth: number;
+    warp_octaves: number;
+    warp_frequency: number;
+    warp_level_gain: number;
+    continent_mode: number;
+    continent_frequency: number;
+    continent_threshold: number;
+    continent_strength: number;
+    spline_erosion_frequency: number;
+    /** Height spline as JSON, or undefined when unset. */
+    height_spline_json(): string | undefined;
+    /** Throws if the JSON isn't a spline; validation happens in `validate()`. */
+    set_height_spline_json(json?: string): void;
+    terrace_steps: number;
+    terrace_smoothness: number;
+    terrace_jitter: number;
+    /** Strata as JSON, or undefined when unset. */
+    strata_json(): string | undefined;
+    /** Throws if the JSON isn't a strata document; validation happens in `validate()`. */
+    set_strata_json(json?: string): void;
+    crater_density: number;
+    crater_min_radius: number;
+    crater_max_radius: number;
+    crater_size_exponent: number;
+    crater_depth: number;
+    crater_peak: number;
+    /** Stamp library as JSON, or undefined when unset. */
+    stamps_json(): string | undefined;
+    /** Throws if the JSON isn't a stamp library; validation happens in `validate()`. */
+    set_stamps_json(json?: string): void;
+    /** Load a row-major float heightmap as brush `name`, replacing any of that name. */
+    add_stamp_brush(name: string, width: number, height: number, data: Float32Array): void;
+    cellular_return: number;
+    cellular_distance: number;
+    cellular_jitter: number;
+    multifractal_offset: number;
+    multifractal_gain: number;
+    ridged_offset: number;
+    ridged_gain: number;
+    ridged_exponent: number;
+    ridged_weight_frequency: number;
+    config_hash(): bigint;
+    to_json(): string;
+    validate(): void;
+    static from_json(json: string): TerrainConfig;
     free(): void;
   }
 
-  export function generate_chunk(config: TerrainConfig, chunkX: number, chunkZ: number): {
-    width: number;
-    height: number;
-    min_height: number;
-    max_height: number;
+  export class ChunkData {
+    readonly chunk_x: number;
+    readonly chunk_z: number;
+    readonly width: number;
+    readonly height: number;
+    readonly min_height: number;
+    readonly max_height: number;
     get_heightmap(): Float32Array;
     get_moisture_map(): Float32Array;
     get_biome_map(): Uint8Array;
+    /** Rock type id per cell; zeros without strata. */
+    get_material_map(): Uint8Array;
+    /** View into WASM memory, invalid after `free()`, `into_buffers()` or any WASM call that may grow memory; `.slice()` it first */
+    heightmap_view(): Float32Array;
+    /** View into WASM memory, invalid after `free()`, `into_buffers()` or any WASM call that may grow memory; `.slice()` it first */
+    moisture_map_view(): Float32Array;
+    /** View into WASM memory, invalid after `free()`, `into_buffers()` or any WASM call that may grow memory; `.slice()` it first */
+    biome_map_view(): Uint8Array;
+    /** View into WASM memory, invalid after `free()`, `into_buffers()` or any WASM call that may grow memory; `.slice()` it first */
+    material_map_view(): Uint8Array;
+    /** Moves the layers out without cloning them; consumes this chunk (don't call `free()`). */
+    into_buffers(): ChunkBuffers;
+    /**
+     * Rebuilds a chunk from layers generated with `config`, for `update_chunk`.
+     * Throws if the config is invalid or a layer isn't `chunk_size * chunk_size` long.
+     */
+    static from_layers(
+      config: TerrainConfig,
+      chunkX: number,
+      chunkZ: number,
+      heightmap: Float32Array,
+      moistureMap: Float32Array,
+      biomeMap: Uint8Array,
+      materialMap: Uint8Array,
+    ): ChunkData;
     free(): void;
-  };
+  }
+
+  /**
+   * Owned chunk layers. Not zero-copy: each `take_*` copies its layer once into
+   * a JS-owned typed array that can be transferred; a layer can be taken once.
+   */
+  export class ChunkBuffers {
+    take_heightmap(): Float32Array;
+    take_moisture_map(): Float32Array;
+    take_biome_map(): Uint8Array;
+    take_material_map(): Uint8Array;
+    free(): void;
+  }
+
+  /** Throws if `config.validate()` fails. */
+  export function generate_chunk(config: TerrainConfig, chunkX: number, chunkZ: number): ChunkData;
+
+  /** Versioned, checksummed binary form of a chunk, suitable for IndexedDB. */
+  export function encode_chunk(chunk: ChunkData, config: TerrainConfig): Uint8Array;
 
+  /** Throws on corruption, unsupported versions or a config hash mismatch. */
+  export function decode_chunk(bytes: Uint8Array, config: TerrainConfig): ChunkData;
+
+  /**
+   * origin_x/origin_z: world position of the first cell (chunk_x * (chunk_size - 1) for a chunk).
+   * Throws if the config is invalid or the heightmap isn't `width * height` long.
+   */
   export function erode_heightmap(
     heightmap: Float32Array,
     width: number,
     height: number,
+    origin_x: number,
+    origin_z: number,
     config: TerrainConfig,
   ): void;
+
+  export function generate_cell_mask(
+    config: TerrainConfig,
+    chunk_x: number,
+    chunk_z: number,
+  ): Uint32Array;
+
+  /** Erosion hardness per cell at the chunk's current heights, for debugging. */
+  export function chunk_hardness_map(config: TerrainConfig, chunk: ChunkData): Float32Array;
+
+  export function generate_region(
+    config: TerrainConfig,
+    x0: number,
+    z0: number,
+    x1: number,
+    z1: number,
+  ): {
+    origin_x: number;
+    origin_z: number;
+    width: number;
+    height: number;
+    min_height: number;
+    max_height: number;
+    get_heightmap(): Float32Array;
+    get_moisture_map(): Float32Array;
+    get_biome_map(): Uint8Array;
+    get_material_map(): Uint8Array;
+    free(): void;
+  };
+
+  export class StlOptions {
+    constructor();
+    base_thickness_mm: number;
+    vertical_exaggeration: number;
+    size_mm: number;
+    binary: boolean;
+    free(): void;
+  }
+
+  export function export_stl(
+    config: TerrainConfig,
+    chunkX0: number,
+    chunkZ0: number,
+    chunkX1: number,
+    chunkZ1: number,
+    options: StlOptions,
+  ): Uint8Array;
+
+  export function sample_height(config: TerrainConfig, x: number, z: number): number;
+  export function sample_continentalness(config: TerrainConfig, x: number, z: number): number;
+  export function sample_biome(config: TerrainConfig, x: number, z: number): number;
+  export function sample_normal(config: TerrainConfig, x: number, z: number): Float32Array;
+
+  /** Reusable point sampler over the un-eroded terrain; cheaper than the one-shot `sample_*`. */
+  export class TerrainSampler {
+    constructor(config: TerrainConfig);
+    sample_height(x: number, z: number): number;
+    sample_continentalness(x: number, z: number): number;
+    sample_biome(x: number, z: number): number;
+    sample_normal(x: number, z: number): Float32Array;
+    free(): void;
+  }
+
+  export class RayHit {
+    x: number;
+    y: number;
+    z: number;
+    normal_x: number;
+    normal_y: number;
+    normal_z: number;
+    chunk_x: number;
+    chunk_z: number;
+    /** Undefined if a cached chunk stores an unknown biome id at the hit. */
+    biome: number | undefined;
+    distance: number;
+    free(): void;
+  }
+
+  /** `maxDistance` must be finite and is clamped to 65536 world units. */
+  export function raycast_terrain(
+    config: TerrainConfig,
+    originX: number,
+    originY: number,
+    originZ: number,
+    dirX: number,
+    dirY: number,
+    dirZ: number,
+    maxDistance: number,
+  ): RayHit | undefined;
+
+  export class World {
+    constructor(config: TerrainConfig);
+    readonly config: TerrainConfig;
+    set_config(config: TerrainConfig): boolean;
+    memory_budget: number;
+    readonly memory_usage: number;
+    readonly cached_chunks: number;
+    is_cached(chunkX: number, chunkZ: number): boolean;
+    get_chunk(chunkX: number, chunkZ: number): ChunkData;
+    invalidate_chunk(chunkX: number, chunkZ: number): boolean;
+    invalidate_all(): void;
+    sample_height(x: number, z: number): number;
+    /** Undefined if a cached chunk stores an unknown biome id there. */
+    sample_biome(x: number, z: number): number | undefined;
+    sample_normal(x: number, z: number): Float32Array;
+    raycast(
+      originX: number,
+      originY: number,
+      originZ: number,
+      dirX: number,
+      dirY: number,
+      dirZ: number,
+      maxDistance: number,
+    ): RayHit | undefined;
+    free(): void;
+  }
+
+  /** 0 height, 1 erosion, 2 moisture, 3 classification, 4 derived maps */
+  export enum Stage {
+    Height = 0,
+    Erosion = 1,
+    Moisture = 2,
+    Classification = 3,
+    Derived = 4,
+  }
+
+  /** Rerun only the stale stages of a chunk in place; returns the stages run. */
+  export function update_chunk(config: TerrainConfig, chunk: ChunkData): Uint8Array;
+
+  export class ErosionJob {
+    constructor(
+      heightmap: Float32Array,
+      width: number,
+      height: number,
+      origin_x: number,
+      origin_z: number,
+      config: TerrainConfig,
+    );
+    readonly progress: number;
+    readonly iterations_done: number;
+    readonly total_iterations: number;
+    readonly width: number;
+    readonly height: number;
+    step(droplets: number): number;
+    is_finished(): boolean;
+    cancel(): void;
+    is_cancelled(): boolean;
+    snapshot(): Float32Array;
+    /** View into WASM memory, invalid after `free()`, `into_heightmap()`, `step()` or any WASM call that may grow memory */
+    heightmap_view(): Float32Array;
+    into_heightmap(): Float32Array;
+    free(): void;
+  }
+
+  export class ChunkPlan {
+    /** Chunks to generate as flat [x, z, lod, ...] triples, highest priority first */
+    load_list(): Int32Array;
+    /** Chunks to drop as flat [x, z, ...] pairs */
+    unload_list(): Int32Array;
+    /** LOD changes as flat [x, z, lod, ...] triples */
+    relod_list(): Int32Array;
+    free(): void;
+  }
+
+  export class ChunkPlanner {
+    constructor(chunkSize: number, viewDistance: number);
+    view_distance: number;
+    unload_margin: number;
+    lod_margin: number;
+    max_loads: number;
+    readonly loaded_count: number;
+    set_lod_distances(lod1: number, lod2: number): void;
+    forget(chunkX: number, chunkZ: number): void;
+    reset(): void;
+    is_loaded(chunkX: number, chunkZ: number): boolean;
+    plan(cameraX: number, cameraZ: number, dirX: number, dirZ: number): ChunkPlan;
+    free(): void;
+  }
 }
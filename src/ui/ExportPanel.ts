@@ -1,7 +1,7 @@
 import type * as THREE from 'three';
 import type { WorldConfig } from '../types/terrain';
-import { exportHeightmapPNG, exportBiomeMapPNG, exportNormalMapPNG, exportSettingsJSON, exportScreenshot } from '../utils/export';
-import { generateChunk } from '../generation/WasmBridge';
+import { exportHeightmapPNG, exportBiomeMapPNG, exportNormalMapPNG, exportSettingsJSON, exportScreenshot, exportSTL } from '../utils/export';
+import { exportChunkRangeSTL, generateChunk } from '../generation/WasmBridge';
 
 export class ExportPanel {
   private container: HTMLDivElement;
@@ -71,6 +71,16 @@ export class ExportPanel {
       exportNormalMapPNG(data.heightmap, data.width, data.height, getResolution(), 8.0);
     }));
 
+    btnRow.appendChild(makeBtn('STL (3x3)', () => {
+      const bytes = exportChunkRangeSTL(getConfig(), -1, -1, 1, 1, {
+        baseThicknessMm: 3,
+        verticalExaggeration: 1.5,
+        sizeMm: 150,
+        binary: true,
+      });
+      exportSTL(bytes);
+    }));
+
     btnRow.appendChild(makeBtn('Screenshot', () => {
       exportScreenshot(getRenderer(), getScene(), getCamera());
     }));
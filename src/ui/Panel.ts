@@ -66,6 +66,19 @@ export class ControlPanel {
       this.createSlider('Sea Level', 'seaLevel', 0.0, 0.8, 0.01),
     ]);
 
+    // Continents section
+    this.addSection('Continents', [
+      this.createSelect('Mode', 'continentMode', [
+        { value: '0', label: 'Off' },
+        { value: '1', label: 'Noise' },
+        { value: '2', label: 'Plates' },
+      ]),
+      this.createSlider('Frequency', 'continentFrequency', 0.0001, 0.002, 0.0001),
+      this.createSlider('Land Threshold', 'continentThreshold', 0.2, 0.8, 0.01),
+      this.createSlider('Strength', 'continentStrength', 0.0, 1.0, 0.05),
+      this.createSlider('Spline Erosion Freq', 'splineErosionFrequency', 0.0001, 0.01, 0.0001),
+    ]);
+
     // Noise section
     this.addSection('Noise', [
       this.createSelect('Type', 'noiseType', [
@@ -73,11 +86,63 @@ export class ControlPanel {
         { value: '1', label: 'Perlin' },
         { value: '2', label: 'Ridged' },
         { value: '3', label: 'Warped' },
+        { value: '4', label: 'Cellular' },
+        { value: '5', label: 'Billow' },
+        { value: '6', label: 'Hybrid Multifractal' },
+        { value: '7', label: 'Hetero Multifractal' },
       ]),
       this.createSlider('Octaves', 'octaves', 1, 8, 1),
       this.createSlider('Persistence', 'persistence', 0.1, 1.0, 0.05),
       this.createSlider('Lacunarity', 'lacunarity', 1.0, 4.0, 0.1),
       this.createSlider('Warp Strength', 'warpStrength', 0.1, 2.0, 0.1),
+      this.createSelect('Warp Base', 'warpBase', [
+        { value: '0', label: 'Simplex' },
+        { value: '1', label: 'Perlin' },
+        { value: '2', label: 'Ridged' },
+        { value: '4', label: 'Cellular' },
+        { value: '5', label: 'Billow' },
+        { value: '6', label: 'Hybrid Multifractal' },
+        { value: '7', label: 'Hetero Multifractal' },
+      ]),
+      this.createSlider('Warp Depth', 'warpDepth', 1, 4, 1),
+      this.createSlider('Warp Octaves', 'warpOctaves', 1, 8, 1),
+      this.createSlider('Warp Frequency', 'warpFrequency', 0.0005, 0.02, 0.0005),
+      this.createSlider('Warp Level Gain', 'warpLevelGain', 0.0, 2.0, 0.05),
+      this.createSelect('Cells', 'cellularReturn', [
+        { value: '0', label: 'F1' },
+        { value: '1', label: 'F2' },
+        { value: '2', label: 'F2 - F1' },
+        { value: '3', label: 'Cell Id' },
+      ]),
+      this.createSelect('Distance', 'cellularDistance', [
+        { value: '0', label: 'Euclidean' },
+        { value: '1', label: 'Manhattan' },
+        { value: '2', label: 'Chebyshev' },
+      ]),
+      this.createSlider('Jitter', 'cellularJitter', 0.0, 1.0, 0.05),
+      this.createSlider('Offset', 'multifractalOffset', 0.0, 2.0, 0.05),
+      this.createSlider('Gain', 'multifractalGain', 0.0, 4.0, 0.1),
+      this.createSlider('Ridge Offset', 'ridgedOffset', 0.5, 1.5, 0.05),
+      this.createSlider('Ridge Gain', 'ridgedGain', 0.0, 8.0, 0.1),
+      this.createSlider('Ridge Sharpness', 'ridgedExponent', 0.5, 4.0, 0.1),
+      this.createSlider('Range Field', 'ridgedWeightFrequency', 0.0, 1.0, 0.05),
+    ]);
+
+    // Terraces section
+    this.addSection('Terraces', [
+      this.createSlider('Steps', 'terraceSteps', 0, 32, 1),
+      this.createSlider('Smoothness', 'terraceSmoothness', 0.0, 1.0, 0.05),
+      this.createSlider('Jitter', 'terraceJitter', 0.0, 1.0, 0.05),
+    ]);
+
+    // Craters section
+    this.addSection('Craters', [
+      this.createSlider('Density', 'craterDensity', 0, 64, 1),
+      this.createSlider('Min Radius', 'craterMinRadius', 1, 64, 1),
+      this.createSlider('Max Radius', 'craterMaxRadius', 16, 256, 4),
+      this.createSlider('Size Exponent', 'craterSizeExponent', 0.5, 4.0, 0.1),
+      this.createSlider('Depth', 'craterDepth', 0.0, 0.5, 0.01),
+      this.createSlider('Central Peak', 'craterPeak', 0.0, 1.0, 0.05),
     ]);
 
     // Erosion section
@@ -88,6 +153,8 @@ export class ControlPanel {
       this.createSlider('Capacity', 'erosionCapacity', 1.0, 8.0, 0.5),
       this.createSlider('Erosion Rate', 'erosionErosionRate', 0.1, 0.9, 0.05),
       this.createSlider('Deposition', 'erosionDeposition', 0.1, 0.9, 0.05),
+      this.createSlider('Rock Hardness', 'hardnessNoise', 0.0, 1.0, 0.05),
+      this.createSlider('Hardness Frequency', 'hardnessFrequency', 0.001, 0.05, 0.001),
     ]);
 
     // Presets section
@@ -47,6 +47,17 @@ export const PRESETS: Preset[] = [
       lacunarity: 2.5,
       heightMultiplier: 120,
       noiseType: 2, // Ridged
+      terraceSteps: 7,
+      terraceSmoothness: 0.15,
+      terraceJitter: 0.4,
+      // Hard caprock over soft sandstone, so erosion cuts mesas
+      strata: {
+        layers: [
+          { rock: 1, thickness: 0.06, hardness: 0.1 },
+          { rock: 2, thickness: 0.02, hardness: 0.8 },
+          { rock: 3, thickness: 0.05, hardness: 0.3 },
+        ],
+      },
       erosionEnabled: true,
       erosionIterations: 100000,
       moistureEnabled: true,
@@ -65,6 +76,26 @@ export const PRESETS: Preset[] = [
       moistureEnabled: true,
     },
   },
+  {
+    name: 'Moon',
+    config: {
+      seaLevel: 0.0,
+      scale: 0.6,
+      octaves: 5,
+      persistence: 0.45,
+      lacunarity: 2.0,
+      heightMultiplier: 70,
+      noiseType: 5, // Billow
+      craterDensity: 40,
+      craterMinRadius: 6,
+      craterMaxRadius: 200,
+      craterSizeExponent: 1.8,
+      craterDepth: 0.25,
+      craterPeak: 0.35,
+      moistureEnabled: false,
+      generateWater: false,
+    },
+  },
   {
     name: 'Volcanic',
     config: {
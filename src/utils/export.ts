@@ -124,6 +124,11 @@ export function exportSettingsJSON(config: Record<string, unknown>, filename: st
   downloadBlob(blob, filename);
 }
 
+export function exportSTL(bytes: Uint8Array, filename: string = 'terrasnyth-terrain.stl'): void {
+  const blob = new Blob([bytes], { type: 'model/stl' });
+  downloadBlob(blob, filename);
+}
+
 export function exportScreenshot(renderer: THREE.WebGLRenderer, scene: THREE.Scene, camera: THREE.Camera): void {
   // Render at current resolution
   renderer.render(scene, camera);
@@ -11,8 +11,37 @@ const HASH_KEYS: Array<[keyof WorldConfig, string]> = [
   ['seaLevel', 'sl'],
   ['noiseType', 'nt'],
   ['warpStrength', 'ws'],
+  ['warpBase', 'wb'],
+  ['warpDepth', 'wd'],
+  ['warpOctaves', 'wo'],
+  ['warpFrequency', 'wf'],
+  ['warpLevelGain', 'wg'],
+  ['continentMode', 'cm'],
+  ['continentFrequency', 'cf'],
+  ['continentThreshold', 'ct'],
+  ['continentStrength', 'cs'],
+  ['terraceSteps', 'ts'],
+  ['terraceSmoothness', 'tm'],
+  ['terraceJitter', 'tj'],
+  ['craterDensity', 'kd'],
+  ['craterMinRadius', 'kn'],
+  ['craterMaxRadius', 'kx'],
+  ['craterSizeExponent', 'ke'],
+  ['craterDepth', 'kh'],
+  ['craterPeak', 'kp'],
+  ['cellularReturn', 'cr'],
+  ['cellularDistance', 'cd'],
+  ['cellularJitter', 'cj'],
+  ['multifractalOffset', 'mo'],
+  ['multifractalGain', 'mg'],
+  ['ridgedOffset', 'ro'],
+  ['ridgedGain', 'rg'],
+  ['ridgedExponent', 're'],
+  ['ridgedWeightFrequency', 'rw'],
   ['erosionEnabled', 'ee'],
   ['erosionIterations', 'ei'],
+  ['hardnessNoise', 'hn'],
+  ['hardnessFrequency', 'hf'],
   ['viewDistance', 'vd'],
 ];
 
@@ -2,8 +2,24 @@ import type { WorldConfig } from '../types/terrain';
 
 interface WasmModule {
   default: (input?: RequestInfo | URL) => Promise<void>;
-  TerrainConfig: new (seed: bigint) => WasmTerrainConfig;
+  init_thread_pool?: (numThreads: number) => Promise<void>;
+  TerrainConfig: {
+    new (seed: bigint): WasmTerrainConfig;
+    from_json: (json: string) => WasmTerrainConfig;
+  };
+  ChunkData: {
+    from_layers: (
+      config: WasmTerrainConfig,
+      chunkX: number,
+      chunkZ: number,
+      heightmap: Float32Array,
+      moistureMap: Float32Array,
+      biomeMap: Uint8Array,
+      materialMap: Uint8Array,
+    ) => WasmChunkData;
+  };
   generate_chunk: (config: WasmTerrainConfig, chunkX: number, chunkZ: number) => WasmChunkData;
+  update_chunk: (config: WasmTerrainConfig, chunk: WasmChunkData) => Uint8Array;
 }
 
 interface WasmTerrainConfig {
@@ -20,10 +36,47 @@ interface WasmTerrainConfig {
   erosion_deposition: number;
   erosion_erosion_rate: number;
   erosion_evaporation: number;
+  hardness_noise: number;
+  hardness_frequency: number;
   moisture_enabled: boolean;
   erosion_enabled: boolean;
   noise_type: number;
   warp_strength: number;
+  warp_base: number;
+  warp_depth: number;
+  warp_octaves: number;
+  warp_frequency: number;
+  warp_level_gain: number;
+  continent_mode: number;
+  continent_frequency: number;
+  continent_threshold: number;
+  continent_strength: number;
+  spline_erosion_frequency: number;
+  height_spline_json: () => string | undefined;
+  set_height_spline_json: (json?: string) => void;
+  terrace_steps: number;
+  terrace_smoothness: number;
+  terrace_jitter: number;
+  strata_json: () => string | undefined;
+  set_strata_json: (json?: string) => void;
+  crater_density: number;
+  crater_min_radius: number;
+  crater_max_radius: number;
+  crater_size_exponent: number;
+  crater_depth: number;
+  crater_peak: number;
+  stamps_json: () => string | undefined;
+  set_stamps_json: (json?: string) => void;
+  cellular_return: number;
+  cellular_distance: number;
+  cellular_jitter: number;
+  multifractal_offset: number;
+  multifractal_gain: number;
+  ridged_offset: number;
+  ridged_gain: number;
+  ridged_exponent: number;
+  ridged_weight_frequency: number;
+  validate: () => void;
   free: () => void;
 }
 
@@ -35,6 +88,20 @@ interface WasmChunkData {
   get_heightmap: () => Float32Array;
   get_moisture_map: () => Float32Array;
   get_biome_map: () => Uint8Array;
+  get_material_map: () => Uint8Array;
+  heightmap_view: () => Float32Array;
+  moisture_map_view: () => Float32Array;
+  biome_map_view: () => Uint8Array;
+  material_map_view: () => Uint8Array;
+  into_buffers: () => WasmChunkBuffers;
+  free: () => void;
+}
+
+interface WasmChunkBuffers {
+  take_heightmap: () => Float32Array;
+  take_moisture_map: () => Float32Array;
+  take_biome_map: () => Uint8Array;
+  take_material_map: () => Uint8Array;
   free: () => void;
 }
 
@@ -47,6 +114,25 @@ interface ChunkRequestMessage {
   config: WorldConfig;
 }
 
+/** Bring a chunk generated with `previousConfig` up to `config`, rerunning only stale stages. */
+interface ChunkUpdateMessage {
+  type: 'update';
+  requestId: number;
+  generationId: number;
+  chunkX: number;
+  chunkZ: number;
+  previousConfig: WorldConfig;
+  config: WorldConfig;
+  layers: ChunkLayers;
+}
+
+interface ChunkLayers {
+  heightmap: Float32Array;
+  moistureMap: Float32Array;
+  biomeMap: Uint8Array;
+  materialMap: Uint8Array;
+}
+
 interface ChunkResponseMessage {
   type: 'chunk';
   requestId: number;
@@ -61,6 +147,7 @@ interface ChunkResponseMessage {
     heightmap: Float32Array;
     moistureMap: Float32Array;
     biomeMap: Uint8Array;
+    materialMap: Uint8Array;
   };
 }
 
@@ -68,9 +155,13 @@ interface ChunkErrorMessage {
   type: 'error';
   requestId: number;
   generationId: number;
+  /** `config` when the config itself was rejected, so retrying can't help until it changes. */
+  reason: 'config' | 'generation';
   message: string;
 }
 
+class InvalidConfigError extends Error {}
+
 let wasmModule: WasmModule | null = null;
 let wasmReady: Promise<void> | null = null;
 const workerScope = self as unknown as {
@@ -79,11 +170,35 @@ const workerScope = self as unknown as {
 };
 
 
+async function loadThreadedWasm(): Promise<WasmModule | null> {
+  // SharedArrayBuffer (and so the threaded build) needs a cross-origin isolated page
+  if (!(self as unknown as { crossOriginIsolated?: boolean }).crossOriginIsolated) return null;
+
+  try {
+    const threadedModulePath = '../generation/wasm-pkg-threads/terra_wasm.js';
+    const mod = await import(/* @vite-ignore */ threadedModulePath) as unknown as WasmModule;
+    await mod.default();
+    if (!mod.init_thread_pool) return null;
+    await mod.init_thread_pool(Math.max(1, (navigator.hardwareConcurrency || 2) - 1));
+    return mod;
+  } catch {
+    return null;
+  }
+}
+
+async function loadWasm(): Promise<WasmModule> {
+  const threaded = await loadThreadedWasm();
+  if (threaded) return threaded;
+
+  const wasmModulePath = '../generation/wasm-pkg/terra_wasm.js';
+  const mod = await import(/* @vite-ignore */ wasmModulePath) as unknown as WasmModule;
+  await mod.default();
+  return mod;
+}
+
 async function ensureWasm(): Promise<WasmModule> {
   if (!wasmModule) {
-    const wasmModulePath = '../generation/wasm-pkg/terra_wasm.js';
-    const mod = await import(/* @vite-ignore */ wasmModulePath) as unknown as WasmModule;
-    wasmReady = mod.default().then(() => {
+    wasmReady ??= loadWasm().then((mod) => {
       wasmModule = mod;
     });
   }
@@ -100,64 +215,114 @@ async function ensureWasm(): Promise<WasmModule> {
 }
 
 function createWasmConfig(wasm: WasmModule, config: WorldConfig): WasmTerrainConfig {
-  const wasmConfig = new wasm.TerrainConfig(BigInt(config.seed));
-
-  wasmConfig.chunk_size = config.chunkSize;
-  wasmConfig.scale = config.scale;
-  wasmConfig.octaves = config.octaves;
-  wasmConfig.persistence = config.persistence;
-  wasmConfig.lacunarity = config.lacunarity;
-  wasmConfig.height_multiplier = config.heightMultiplier;
-  wasmConfig.sea_level = config.seaLevel;
-  wasmConfig.erosion_iterations = config.erosionIterations;
-  wasmConfig.erosion_inertia = config.erosionInertia;
-  wasmConfig.erosion_capacity = config.erosionCapacity;
-  wasmConfig.erosion_deposition = config.erosionDeposition;
-  wasmConfig.erosion_erosion_rate = config.erosionErosionRate;
-  wasmConfig.erosion_evaporation = config.erosionEvaporation;
-  wasmConfig.moisture_enabled = config.moistureEnabled;
-  wasmConfig.erosion_enabled = config.erosionEnabled;
-  wasmConfig.noise_type = config.noiseType;
-  wasmConfig.warp_strength = config.warpStrength;
+  // Same document format as exported settings files; Rust migrates the camelCase keys
+  return wasm.TerrainConfig.from_json(JSON.stringify(config));
+}
 
+/** Like `createWasmConfig`, but throws `InvalidConfigError` for a config Rust won't generate with. */
+function createValidWasmConfig(wasm: WasmModule, config: WorldConfig): WasmTerrainConfig {
+  let wasmConfig: WasmTerrainConfig;
+  try {
+    wasmConfig = createWasmConfig(wasm, config);
+  } catch (err) {
+    throw new InvalidConfigError(err instanceof Error ? err.message : String(err));
+  }
+  try {
+    wasmConfig.validate();
+  } catch (err) {
+    wasmConfig.free();
+    throw new InvalidConfigError(err instanceof Error ? err.message : String(err));
+  }
   return wasmConfig;
 }
 
-workerScope.addEventListener('message', async (event: MessageEvent<ChunkRequestMessage>) => {
+function postChunk(msg: ChunkRequestMessage | ChunkUpdateMessage, result: WasmChunkData): void {
+  const { width, height, min_height: minHeight, max_height: maxHeight } = result;
+  // Consumes `result`; each take copies its layer once into a buffer that can be transferred
+  const buffers = result.into_buffers();
+  const heightmap = buffers.take_heightmap();
+  const moistureMap = buffers.take_moisture_map();
+  const biomeMap = buffers.take_biome_map();
+  const materialMap = buffers.take_material_map();
+  buffers.free();
+
+  const response: ChunkResponseMessage = {
+    type: 'chunk',
+    requestId: msg.requestId,
+    generationId: msg.generationId,
+    chunkX: msg.chunkX,
+    chunkZ: msg.chunkZ,
+    data: {
+      width,
+      height,
+      minHeight,
+      maxHeight,
+      heightmap,
+      moistureMap,
+      biomeMap,
+      materialMap,
+    },
+  };
+
+  workerScope.postMessage(response, [
+    response.data.heightmap.buffer,
+    response.data.moistureMap.buffer,
+    response.data.biomeMap.buffer,
+    response.data.materialMap.buffer,
+  ]);
+}
+
+function updateChunk(wasm: WasmModule, msg: ChunkUpdateMessage): WasmChunkData {
+  const previousConfig = createWasmConfig(wasm, msg.previousConfig);
+  let chunk: WasmChunkData;
+  try {
+    const { heightmap, moistureMap, biomeMap, materialMap } = msg.layers;
+    chunk = wasm.ChunkData.from_layers(
+      previousConfig,
+      msg.chunkX,
+      msg.chunkZ,
+      heightmap,
+      moistureMap,
+      biomeMap,
+      materialMap,
+    );
+  } finally {
+    previousConfig.free();
+  }
+
+  let config: WasmTerrainConfig;
+  try {
+    config = createValidWasmConfig(wasm, msg.config);
+  } catch (err) {
+    chunk.free();
+    throw err;
+  }
+  try {
+    wasm.update_chunk(config, chunk);
+    return chunk;
+  } catch (err) {
+    chunk.free();
+    throw err;
+  } finally {
+    config.free();
+  }
+}
+
+workerScope.addEventListener('message', async (event: MessageEvent<ChunkRequestMessage | ChunkUpdateMessage>) => {
   const msg = event.data;
-  if (!msg || msg.type !== 'generate') return;
+  if (!msg || (msg.type !== 'generate' && msg.type !== 'update')) return;
 
   try {
     const wasm = await ensureWasm();
 
-    const wasmConfig = createWasmConfig(wasm, msg.config);
+    if (msg.type === 'update') {
+      postChunk(msg, updateChunk(wasm, msg));
+      return;
+    }
+
+    const wasmConfig = createValidWasmConfig(wasm, msg.config);
     try {
-      const result = wasm.generate_chunk(wasmConfig, msg.chunkX, msg.chunkZ);
-
-      const response: ChunkResponseMessage = {
-        type: 'chunk',
-        requestId: msg.requestId,
-        generationId: msg.generationId,
-        chunkX: msg.chunkX,
-        chunkZ: msg.chunkZ,
-        data: {
-          width: result.width,
-          height: result.height,
-          minHeight: result.min_height,
-          maxHeight: result.max_height,
-          heightmap: result.get_heightmap(),
-          moistureMap: result.get_moisture_map(),
-          biomeMap: result.get_biome_map(),
-        },
-      };
-
-      result.free();
-
-      workerScope.postMessage(response, [
-        response.data.heightmap.buffer,
-        response.data.moistureMap.buffer,
-        response.data.biomeMap.buffer,
-      ]);
+      postChunk(msg, wasm.generate_chunk(wasmConfig, msg.chunkX, msg.chunkZ));
     } finally {
       wasmConfig.free();
     }
@@ -166,6 +331,7 @@ workerScope.addEventListener('message', async (event: MessageEvent<ChunkRequestM
       type: 'error',
       requestId: msg.requestId,
       generationId: msg.generationId,
+      reason: err instanceof InvalidConfigError ? 'config' : 'generation',
       message: err instanceof Error ? err.message : String(err),
     };
     workerScope.postMessage(errorMessage);
@@ -14,6 +14,19 @@ export default defineConfig({
   build: {
     target: 'esnext',
   },
+  // Cross-origin isolation enables SharedArrayBuffer for the threaded WASM build
+  server: {
+    headers: {
+      'Cross-Origin-Opener-Policy': 'same-origin',
+      'Cross-Origin-Embedder-Policy': 'require-corp',
+    },
+  },
+  preview: {
+    headers: {
+      'Cross-Origin-Opener-Policy': 'same-origin',
+      'Cross-Origin-Embedder-Policy': 'require-corp',
+    },
+  },
   optimizeDeps: {
     exclude: ['terra-wasm'],
   },